@@ -0,0 +1,56 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+
+use std::fmt;
+
+/// Data that can be put on the clipboard with `Clipboard::set`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipboardData {
+    Text(String),
+    Image(RgbaImage),
+    /// Data in a format named by a MIME type, such as `text/html`, or by a name the platform
+    /// registers for the application. Well-known MIME types are mapped to the names and layouts
+    /// other applications on the platform use, so `text/html` data is an HTML fragment.
+    Custom(String, Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardError {
+    /// Another application kept the clipboard open.
+    Unavailable,
+    /// The clipboard holds an image in a pixel format that isn't supported.
+    UnsupportedImage,
+    /// The clipboard holds data that doesn't have the layout of its format.
+    MalformedData,
+    /// The platform reported an error, holding its error code.
+    Os(i32),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "the clipboard is in use by another application"),
+            Self::UnsupportedImage => write!(f, "the clipboard image has an unsupported format"),
+            Self::MalformedData => write!(f, "the clipboard data is malformed"),
+            Self::Os(code) => write!(f, "failed to access the clipboard (error 0x{:08X})", code),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
@@ -0,0 +1,170 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+
+use bitflags::bitflags;
+use std::path::PathBuf;
+
+/// The data carried by a drag and drop operation. A drag may carry several kinds of data, such
+/// as a link dragged from a browser which comes with its URI and its text.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DragData {
+    pub files: Vec<PathBuf>,
+    pub text: Option<String>,
+    pub uris: Vec<String>,
+}
+
+impl DragData {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.text.is_none() && self.uris.is_empty()
+    }
+}
+
+bitflags! {
+    /// What dropping the data does to it, as agreed between the source and the target.
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct DropEffect: u32 {
+        /// The data is copied to the target.
+        const COPY = 1 << 0;
+        /// The data is moved to the target, and the source removes it.
+        const MOVE = 1 << 1;
+        /// The target links to the data.
+        const LINK = 1 << 2;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DragEvent {
+    data: DragData,
+    x: f32,
+    y: f32,
+    modifiers: Modifiers,
+    allowed: DropEffect,
+    effect: DropEffect,
+    timestamp: Timestamp,
+}
+
+impl DragEvent {
+    pub(crate) const fn new(
+        data: DragData,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+        allowed: DropEffect,
+        effect: DropEffect,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            data,
+            x,
+            y,
+            modifiers,
+            allowed,
+            effect,
+            timestamp,
+        }
+    }
+
+    pub const fn data(&self) -> &DragData {
+        &self.data
+    }
+
+    /// Returns the position of the cursor in client coordinates.
+    pub const fn pos(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the effects the source of the drag allows. The effect chosen by the window is
+    /// limited to these.
+    pub const fn allowed(&self) -> DropEffect {
+        self.allowed
+    }
+
+    /// Returns the effect the window chose for the previous event of the drag, or no effect when
+    /// the drag enters the window.
+    pub const fn effect(&self) -> DropEffect {
+        self.effect
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// A drag that left the window or was cancelled.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DragLeaveEvent {
+    timestamp: Timestamp,
+}
+
+impl DragLeaveEvent {
+    pub(crate) const fn new(timestamp: Timestamp) -> Self {
+        Self { timestamp }
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_data_is_empty() {
+        assert!(DragData::default().is_empty());
+        let text = DragData {
+            text: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(!text.is_empty());
+        let uris = DragData {
+            uris: vec!["https://example.com/".to_string()],
+            ..Default::default()
+        };
+        assert!(!uris.is_empty());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_round_trip() {
+        let event = DragEvent::new(
+            DragData {
+                files: vec!["/tmp/a.txt".into()],
+                text: None,
+                uris: vec!["file:///tmp/a.txt".to_string()],
+            },
+            1.0,
+            2.0,
+            Modifiers::LEFT_CONTROL,
+            DropEffect::COPY | DropEffect::MOVE,
+            DropEffect::COPY,
+            Timestamp::from_duration(std::time::Duration::from_millis(5)),
+        );
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(serde_json::from_str::<DragEvent>(&json).unwrap(), event);
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// An image with 8-bit RGBA pixels, stored row by row from the top with straight alpha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedRgbaImage"))]
+pub struct RgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Creates an image from its pixels.
+    ///
+    /// Panics if `pixels` doesn't hold exactly `width * height * 4` bytes.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert!(
+            pixels_match_size(width, height, &pixels),
+            "pixel data doesn't match the image size"
+        );
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub const fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+}
+
+fn pixels_match_size(width: u32, height: u32, pixels: &[u8]) -> bool {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|len| len.checked_mul(4))
+        .is_some_and(|len| len == pixels.len())
+}
+
+/// The serialized form of `RgbaImage`, which is checked before it becomes an image.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "RgbaImage")]
+struct UncheckedRgbaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedRgbaImage> for RgbaImage {
+    type Error = &'static str;
+
+    fn try_from(image: UncheckedRgbaImage) -> Result<Self, Self::Error> {
+        if !pixels_match_size(image.width, image.height, &image.pixels) {
+            return Err("pixel data doesn't match the image size");
+        }
+        Ok(Self {
+            width: image.width,
+            height: image.height,
+            pixels: image.pixels,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "pixel data doesn't match the image size")]
+    fn new_checks_size() {
+        RgbaImage::new(2, 2, vec![0; 15]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_round_trip() {
+        let image = RgbaImage::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+        let json = serde_json::to_string(&image).unwrap();
+        assert_eq!(
+            json,
+            r#"{"width":2,"height":1,"pixels":[255,0,0,255,0,0,255,128]}"#
+        );
+        assert_eq!(serde_json::from_str::<RgbaImage>(&json).unwrap(), image);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_checks_size() {
+        let json = r#"{"width":2,"height":2,"pixels":[255,0,0,255]}"#;
+        let error = serde_json::from_str::<RgbaImage>(json).unwrap_err();
+        assert!(error.to_string().contains("doesn't match the image size"));
+
+        let json = r#"{"width":4294967295,"height":4294967295,"pixels":[]}"#;
+        assert!(serde_json::from_str::<RgbaImage>(json).is_err());
+    }
+}
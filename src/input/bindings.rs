@@ -0,0 +1,1075 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Maps keyboard, mouse button and wheel input to named actions such as `"editor.save"`.
+//! Bindings can be multi-stroke chords (`Ctrl+K Ctrl+C`) and can belong to context scopes that
+//! are only active some of the time, such as while a text field is focused.
+
+use super::*;
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The context of bindings that are active at all times.
+pub const GLOBAL_CONTEXT: &str = "global";
+
+/// How long to wait for the next stroke of a chord by default.
+const DEFAULT_CHORD_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How far precise scrolling goes to trigger a wheel binding, a notch of a wheel scrolling three
+/// lines.
+const WHEEL_STEP_PIXELS: f32 = 3.0 * PIXELS_PER_LINE;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WheelDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// A single input stroke that a binding reacts to: a key, mouse button or wheel movement while
+/// a set of modifiers is held.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trigger {
+    Key(Shortcut),
+    MouseButton(Modifiers, MouseButton),
+    Wheel(Modifiers, WheelDirection),
+}
+
+impl Trigger {
+    /// Returns the trigger for a key event, or `None` for releases and for modifier keys, which
+    /// are part of the other strokes.
+    pub fn from_key_event(event: &KeyEvent) -> Option<Self> {
+        if event.state() == KeyState::Release || is_modifier_key(event.key()) {
+            return None;
+        }
+        Some(Self::Key(Shortcut::new(event.modifiers(), event.key())))
+    }
+
+    /// Returns the trigger for a mouse button event, or `None` for releases.
+    pub fn from_mouse_button_event(event: &MouseButtonEvent) -> Option<Self> {
+        match event.state() {
+            ButtonState::Press => Some(Self::MouseButton(
+                shortcut_modifiers(event.modifiers()),
+                event.button(),
+            )),
+            ButtonState::Release => None,
+        }
+    }
+
+    /// Returns the trigger for a wheel event. Vertical movement takes precedence when the wheel
+    /// moved in both directions. Precise scrolling arrives in many small pixel steps, so it
+    /// only triggers bindings once `Bindings::handle_mouse_wheel` has added the steps up.
+    pub fn from_mouse_wheel_event(event: &MouseWheelEvent) -> Option<Self> {
+        match event.delta() {
+            ScrollDelta::Lines(x, y) | ScrollDelta::Pages(x, y) => {
+                Self::from_wheel_delta(event.modifiers(), x, y)
+            }
+            ScrollDelta::Pixels(..) => None,
+        }
+    }
+
+    fn from_wheel_delta(modifiers: Modifiers, x: f32, y: f32) -> Option<Self> {
+        let direction = if y > 0.0 {
+            WheelDirection::Up
+        } else if y < 0.0 {
+            WheelDirection::Down
+        } else if x > 0.0 {
+            WheelDirection::Right
+        } else if x < 0.0 {
+            WheelDirection::Left
+        } else {
+            return None;
+        };
+        Some(Self::Wheel(shortcut_modifiers(modifiers), direction))
+    }
+
+    /// Parses a chord of strokes separated by whitespace, e.g. `"Ctrl+K Ctrl+C"`.
+    pub fn parse_sequence(s: &str) -> Result<Vec<Self>, ParseShortcutError> {
+        let sequence = s
+            .split_whitespace()
+            .map(Self::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if sequence.is_empty() {
+            return Err(ParseShortcutError::MissingKey);
+        }
+        Ok(sequence)
+    }
+}
+
+/// Adds a precise scroll delta to what is left over from earlier events.
+fn add_wheel_delta(remainder: &mut f32, delta: f32) {
+    // Leftovers from scrolling the other way would swallow the start of the new direction.
+    if *remainder * delta < 0.0 {
+        *remainder = 0.0;
+    }
+    *remainder += delta;
+}
+
+/// Takes a single wheel step out of the total, returning its direction, or zero if the total
+/// does not add up to a step yet.
+fn take_wheel_step(remainder: &mut f32) -> f32 {
+    if remainder.abs() < WHEEL_STEP_PIXELS {
+        return 0.0;
+    }
+    let step = remainder.signum();
+    *remainder -= step * WHEEL_STEP_PIXELS;
+    step
+}
+
+fn is_modifier_key(key: VirtualKey) -> bool {
+    matches!(
+        key,
+        VirtualKey::Shift
+            | VirtualKey::Control
+            | VirtualKey::Alt
+            | VirtualKey::LeftShift
+            | VirtualKey::RightShift
+            | VirtualKey::LeftControl
+            | VirtualKey::RightControl
+            | VirtualKey::LeftAlt
+            | VirtualKey::RightAlt
+            | VirtualKey::LeftSuper
+            | VirtualKey::RightSuper
+    )
+}
+
+const MOUSE_BUTTON_NAMES: [(&str, MouseButton); 5] = [
+    ("MouseLeft", MouseButton::Left),
+    ("MouseRight", MouseButton::Right),
+    ("MouseMiddle", MouseButton::Middle),
+    ("MouseX1", MouseButton::X1),
+    ("MouseX2", MouseButton::X2),
+];
+
+const WHEEL_NAMES: [(&str, WheelDirection); 4] = [
+    ("WheelUp", WheelDirection::Up),
+    ("WheelDown", WheelDirection::Down),
+    ("WheelLeft", WheelDirection::Left),
+    ("WheelRight", WheelDirection::Right),
+];
+
+/// Formats triggers like shortcuts, with the names `MouseLeft`, `MouseRight`, `MouseMiddle`,
+/// `MouseX1`, `MouseX2`, `WheelUp`, `WheelDown`, `WheelLeft` and `WheelRight` standing in for
+/// the key.
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(shortcut) => write!(f, "{}", shortcut),
+            Self::MouseButton(modifiers, button) => {
+                write_modifiers(f, *modifiers)?;
+                let (name, _) = MOUSE_BUTTON_NAMES
+                    .iter()
+                    .find(|(_, b)| b == button)
+                    .unwrap();
+                f.write_str(name)
+            }
+            Self::Wheel(modifiers, direction) => {
+                write_modifiers(f, *modifiers)?;
+                let (name, _) = WHEEL_NAMES.iter().find(|(_, d)| d == direction).unwrap();
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+impl FromStr for Trigger {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, name) = split_shortcut(s)?;
+        let modifiers = shortcut_modifiers(modifiers);
+
+        let button = MOUSE_BUTTON_NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name));
+        if let Some((_, button)) = button {
+            return Ok(Self::MouseButton(modifiers, *button));
+        }
+
+        let direction = WHEEL_NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name));
+        if let Some((_, direction)) = direction {
+            return Ok(Self::Wheel(modifiers, *direction));
+        }
+
+        Shortcut::from_str(s).map(Self::Key)
+    }
+}
+
+/// Binds a sequence of strokes to an action within a context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Binding {
+    sequence: Vec<Trigger>,
+    action: String,
+    context: String,
+}
+
+impl Binding {
+    /// Creates a binding in the global context.
+    pub fn new(sequence: Vec<Trigger>, action: impl Into<String>) -> Self {
+        Self {
+            sequence,
+            action: action.into(),
+            context: GLOBAL_CONTEXT.to_string(),
+        }
+    }
+
+    /// Creates a binding from a chord string such as `"Ctrl+K Ctrl+C"`.
+    pub fn parse(sequence: &str, action: impl Into<String>) -> Result<Self, ParseShortcutError> {
+        Ok(Self::new(Trigger::parse_sequence(sequence)?, action))
+    }
+
+    /// Moves the binding into a context.
+    pub fn in_context(mut self, context: impl Into<String>) -> Self {
+        self.context = context.into();
+        self
+    }
+
+    pub fn sequence(&self) -> &[Trigger] {
+        &self.sequence
+    }
+
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Returns whether the two bindings can't both be triggered: they share a context and one
+    /// sequence equals or starts with the other.
+    fn conflicts_with(&self, other: &Binding) -> bool {
+        let len = self.sequence.len().min(other.sequence.len());
+        self.context == other.context && self.sequence[..len] == other.sequence[..len]
+    }
+}
+
+/// Two bindings that can't both be triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindingConflict {
+    existing: Box<Binding>,
+    rejected: Box<Binding>,
+}
+
+impl BindingConflict {
+    /// Returns the binding that was already present.
+    pub fn existing(&self) -> &Binding {
+        &self.existing
+    }
+
+    /// Returns the binding that was not added.
+    pub fn rejected(&self) -> &Binding {
+        &self.rejected
+    }
+}
+
+impl fmt::Display for BindingConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sequence = |binding: &Binding| {
+            binding
+                .sequence
+                .iter()
+                .map(Trigger::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        write!(
+            f,
+            "\"{}\" for {} conflicts with \"{}\" for {} in context {}",
+            sequence(&self.rejected),
+            self.rejected.action,
+            sequence(&self.existing),
+            self.existing.action,
+            self.rejected.context
+        )
+    }
+}
+
+impl std::error::Error for BindingConflict {}
+
+/// The outcome of feeding a stroke to `Bindings`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingResult {
+    /// The stroke isn't bound in any active context.
+    Unbound,
+    /// The stroke continues a chord, which waits for the next stroke.
+    Pending,
+    /// The stroke completed the binding of an action.
+    Action(String),
+    /// The stroke didn't continue the chord in progress, which was abandoned. The stroke is
+    /// consumed rather than triggering its own binding, as in Emacs.
+    Abandoned,
+}
+
+/// A set of bindings along with the active contexts and the strokes of a chord in progress.
+///
+/// Time is passed in by the caller, so that chord timeouts can be tested without waiting.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    bindings: Vec<Binding>,
+    contexts: Vec<String>,
+    pending: Vec<Trigger>,
+    last_stroke: Option<Instant>,
+    chord_timeout: Duration,
+    wheel_remainder: (f32, f32),
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            contexts: Vec::new(),
+            pending: Vec::new(),
+            last_stroke: None,
+            chord_timeout: DEFAULT_CHORD_TIMEOUT,
+            wheel_remainder: (0.0, 0.0),
+        }
+    }
+
+    /// Adds a binding, unless it conflicts with one that is already present.
+    pub fn add(&mut self, binding: Binding) -> Result<(), BindingConflict> {
+        if let Some(existing) = self.bindings.iter().find(|b| b.conflicts_with(&binding)) {
+            return Err(BindingConflict {
+                existing: Box::new(existing.clone()),
+                rejected: Box::new(binding),
+            });
+        }
+        self.bindings.push(binding);
+        Ok(())
+    }
+
+    /// Removes the bindings of an action in all contexts.
+    pub fn remove_action(&mut self, action: &str) {
+        self.bindings.retain(|binding| binding.action != action);
+    }
+
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Returns the bindings of an action, e.g. to show its shortcut in a menu.
+    pub fn bindings_for<'a>(&'a self, action: &'a str) -> impl Iterator<Item = &'a Binding> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.action == action)
+    }
+
+    pub const fn chord_timeout(&self) -> Duration {
+        self.chord_timeout
+    }
+
+    /// Sets how long to wait for the next stroke of a chord before abandoning it.
+    pub fn set_chord_timeout(&mut self, timeout: Duration) {
+        self.chord_timeout = timeout;
+    }
+
+    /// Activates a context. Contexts entered later take precedence over earlier ones, and all
+    /// of them over the global context.
+    pub fn enter_context(&mut self, context: impl Into<String>) {
+        let context = context.into();
+        self.contexts.retain(|c| *c != context);
+        self.contexts.push(context);
+        self.reset();
+    }
+
+    pub fn leave_context(&mut self, context: &str) {
+        self.contexts.retain(|c| c != context);
+        self.reset();
+    }
+
+    pub fn is_context_active(&self, context: &str) -> bool {
+        context == GLOBAL_CONTEXT || self.contexts.iter().any(|c| c == context)
+    }
+
+    /// Returns the strokes of the chord in progress.
+    pub fn pending(&self) -> &[Trigger] {
+        &self.pending
+    }
+
+    /// Abandons the chord in progress and any precise scrolling that has not added up to a
+    /// wheel step yet.
+    pub fn reset(&mut self) {
+        self.end_chord();
+        self.wheel_remainder = (0.0, 0.0);
+    }
+
+    fn end_chord(&mut self) {
+        self.pending.clear();
+        self.last_stroke = None;
+    }
+
+    pub fn handle_key(&mut self, event: &KeyEvent, now: Instant) -> BindingResult {
+        match Trigger::from_key_event(event) {
+            Some(trigger) => self.handle_trigger(trigger, now),
+            None => BindingResult::Unbound,
+        }
+    }
+
+    pub fn handle_mouse_button(&mut self, event: &MouseButtonEvent, now: Instant) -> BindingResult {
+        match Trigger::from_mouse_button_event(event) {
+            Some(trigger) => self.handle_trigger(trigger, now),
+            None => BindingResult::Unbound,
+        }
+    }
+
+    /// Feeds a wheel event to the bindings. Precise scrolling triggers a wheel binding each time
+    /// it adds up to a notch of a wheel scrolling three lines. An event triggers at most one
+    /// binding, preferring the vertical axis; further notches are kept for the events after it.
+    pub fn handle_mouse_wheel(&mut self, event: &MouseWheelEvent, now: Instant) -> BindingResult {
+        let trigger = match event.delta() {
+            ScrollDelta::Pixels(x, y) => {
+                let (remainder_x, remainder_y) = &mut self.wheel_remainder;
+                add_wheel_delta(remainder_x, x);
+                add_wheel_delta(remainder_y, y);
+                let step_y = take_wheel_step(remainder_y);
+                let step_x = if step_y == 0.0 {
+                    take_wheel_step(remainder_x)
+                } else {
+                    0.0
+                };
+                Trigger::from_wheel_delta(event.modifiers(), step_x, step_y)
+            }
+            _ => Trigger::from_mouse_wheel_event(event),
+        };
+        match trigger {
+            Some(trigger) => self.handle_trigger(trigger, now),
+            None => BindingResult::Unbound,
+        }
+    }
+
+    /// Feeds a stroke to the bindings.
+    pub fn handle_trigger(&mut self, trigger: Trigger, now: Instant) -> BindingResult {
+        let timed_out = self.last_stroke.map_or(true, |last| {
+            now.saturating_duration_since(last) > self.chord_timeout
+        });
+        if timed_out {
+            self.pending.clear();
+        }
+
+        let chord_in_progress = !self.pending.is_empty();
+        self.pending.push(trigger);
+        self.last_stroke = Some(now);
+
+        match self.lookup_pending() {
+            Some(binding) if binding.sequence.len() == self.pending.len() => {
+                let action = binding.action.clone();
+                self.end_chord();
+                BindingResult::Action(action)
+            }
+            Some(_) => BindingResult::Pending,
+            None => {
+                self.end_chord();
+                if chord_in_progress {
+                    BindingResult::Abandoned
+                } else {
+                    BindingResult::Unbound
+                }
+            }
+        }
+    }
+
+    /// Finds the binding that the pending strokes complete or start, searching the active
+    /// contexts from the most recently entered to the global context.
+    fn lookup_pending(&self) -> Option<&Binding> {
+        let contexts = self
+            .contexts
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .chain([GLOBAL_CONTEXT]);
+        for context in contexts {
+            let binding = self.bindings.iter().find(|binding| {
+                binding.context == context && binding.sequence.starts_with(&self.pending)
+            });
+            if binding.is_some() {
+                return binding;
+            }
+        }
+        None
+    }
+}
+
+/// A binding in a keymap file, such as
+/// `{ "keys": "Ctrl+K Ctrl+C", "action": "editor.comment", "context": "editor" }`. The context is
+/// optional and defaults to the global context.
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeymapEntry {
+    keys: String,
+    action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+}
+
+/// A TOML keymap, which lists its bindings as an array of `[[binding]]` tables.
+#[cfg(feature = "toml")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct TomlKeymap {
+    #[serde(default)]
+    binding: Vec<KeymapEntry>,
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl Bindings {
+    fn load_entries(&mut self, entries: Vec<KeymapEntry>) -> Result<(), LoadBindingsError> {
+        for entry in entries {
+            let binding = Binding::parse(&entry.keys, entry.action)
+                .map_err(|error| LoadBindingsError::Parse(entry.keys, error))?;
+            let binding = match entry.context {
+                Some(context) => binding.in_context(context),
+                None => binding,
+            };
+            self.add(binding).map_err(LoadBindingsError::Conflict)?;
+        }
+        Ok(())
+    }
+
+    fn keymap_entries(&self) -> Vec<KeymapEntry> {
+        self.bindings
+            .iter()
+            .map(|binding| KeymapEntry {
+                keys: binding
+                    .sequence
+                    .iter()
+                    .map(Trigger::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                action: binding.action.clone(),
+                context: Some(binding.context.clone()).filter(|c| c != GLOBAL_CONTEXT),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Bindings {
+    /// Loads bindings from a JSON keymap, which is an array of bindings such as
+    /// `{ "keys": "Ctrl+K Ctrl+C", "action": "editor.comment", "context": "editor" }`. The
+    /// context is optional and defaults to the global context.
+    pub fn from_json(source: &str) -> Result<Self, LoadBindingsError> {
+        let mut bindings = Self::new();
+        bindings.load_json(source)?;
+        Ok(bindings)
+    }
+
+    /// Adds the bindings of a JSON keymap, stopping at the first invalid or conflicting binding.
+    pub fn load_json(&mut self, source: &str) -> Result<(), LoadBindingsError> {
+        let entries = serde_json::from_str(source).map_err(LoadBindingsError::Json)?;
+        self.load_entries(entries)
+    }
+
+    /// Writes the bindings as a JSON keymap that `from_json` reads back. The context is left out
+    /// for global bindings.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.keymap_entries()).expect("keymaps only contain strings")
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Bindings {
+    /// Loads bindings from a TOML keymap, which has a `[[binding]]` table per binding with the
+    /// same keys as the bindings of a JSON keymap:
+    ///
+    /// ```toml
+    /// [[binding]]
+    /// keys = "Ctrl+K Ctrl+C"
+    /// action = "editor.comment"
+    /// context = "editor"
+    /// ```
+    pub fn from_toml(source: &str) -> Result<Self, LoadBindingsError> {
+        let mut bindings = Self::new();
+        bindings.load_toml(source)?;
+        Ok(bindings)
+    }
+
+    /// Adds the bindings of a TOML keymap, stopping at the first invalid or conflicting binding.
+    pub fn load_toml(&mut self, source: &str) -> Result<(), LoadBindingsError> {
+        let keymap: TomlKeymap = toml::from_str(source).map_err(LoadBindingsError::Toml)?;
+        self.load_entries(keymap.binding)
+    }
+
+    /// Writes the bindings as a TOML keymap that `from_toml` reads back. The context is left out
+    /// for global bindings.
+    pub fn to_toml(&self) -> String {
+        let keymap = TomlKeymap {
+            binding: self.keymap_entries(),
+        };
+        toml::to_string_pretty(&keymap).expect("keymaps only contain strings")
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+#[derive(Debug)]
+pub enum LoadBindingsError {
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// A key sequence couldn't be parsed.
+    Parse(String, ParseShortcutError),
+    Conflict(BindingConflict),
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl fmt::Display for LoadBindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json(error) => write!(f, "invalid keymap: {}", error),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => write!(f, "invalid keymap: {}", error),
+            Self::Parse(keys, error) => write!(f, "invalid key sequence \"{}\": {}", keys, error),
+            Self::Conflict(conflict) => write!(f, "{}", conflict),
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml"))]
+impl std::error::Error for LoadBindingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "json")]
+            Self::Json(error) => Some(error),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => Some(error),
+            Self::Parse(_, error) => Some(error),
+            Self::Conflict(conflict) => Some(conflict),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp() -> Timestamp {
+        Timestamp::from_duration(Duration::ZERO)
+    }
+
+    fn trigger(s: &str) -> Trigger {
+        s.parse().unwrap()
+    }
+
+    fn bindings(entries: &[(&str, &str, &str)]) -> Bindings {
+        let mut bindings = Bindings::new();
+        for &(keys, action, context) in entries {
+            let binding = Binding::parse(keys, action).unwrap().in_context(context);
+            bindings.add(binding).unwrap();
+        }
+        bindings
+    }
+
+    fn action(action: &str) -> BindingResult {
+        BindingResult::Action(action.to_string())
+    }
+
+    #[test]
+    fn triggers_from_events() {
+        let key = |key, state, modifiers| {
+            KeyEvent::new(
+                key,
+                KeyLocation::Standard,
+                0,
+                state,
+                modifiers,
+                None,
+                timestamp(),
+            )
+        };
+        assert_eq!(
+            Trigger::from_key_event(&key(
+                VirtualKey::S,
+                KeyState::Press,
+                Modifiers::LEFT_CONTROL
+            )),
+            Some(trigger("Ctrl+S"))
+        );
+        assert_eq!(
+            Trigger::from_key_event(&key(VirtualKey::S, KeyState::Release, Modifiers::empty())),
+            None
+        );
+        assert_eq!(
+            Trigger::from_key_event(&key(
+                VirtualKey::LeftControl,
+                KeyState::Press,
+                Modifiers::LEFT_CONTROL
+            )),
+            None
+        );
+
+        let button = MouseButtonEvent::new(
+            MouseButton::X1,
+            ButtonState::Press,
+            0.0,
+            0.0,
+            Modifiers::RIGHT_SHIFT,
+            timestamp(),
+        );
+        assert_eq!(
+            Trigger::from_mouse_button_event(&button),
+            Some(trigger("Shift+MouseX1"))
+        );
+
+        let wheel = |delta| {
+            MouseWheelEvent::new(
+                delta,
+                GesturePhase::Changed,
+                0.0,
+                0.0,
+                Modifiers::empty(),
+                timestamp(),
+            )
+        };
+        let cases = [
+            (ScrollDelta::Lines(0.0, 3.0), Some("WheelUp")),
+            (ScrollDelta::Lines(0.0, -3.0), Some("WheelDown")),
+            (ScrollDelta::Lines(1.0, 0.0), Some("WheelRight")),
+            (ScrollDelta::Lines(-1.0, 0.0), Some("WheelLeft")),
+            (ScrollDelta::Lines(-1.0, 1.0), Some("WheelUp")),
+            (ScrollDelta::Lines(0.0, 0.0), None),
+            (ScrollDelta::Pages(0.0, -1.0), Some("WheelDown")),
+            (ScrollDelta::Pixels(0.0, 120.0), None),
+        ];
+        for (delta, expected) in cases {
+            assert_eq!(
+                Trigger::from_mouse_wheel_event(&wheel(delta)),
+                expected.map(trigger),
+                "{:?}",
+                delta
+            );
+        }
+    }
+
+    #[test]
+    fn trigger_round_trip() {
+        for s in [
+            "Ctrl+S",
+            "Ctrl+Shift+MouseLeft",
+            "Alt+WheelDown",
+            "MouseX2",
+            "F5",
+        ] {
+            let parsed = trigger(s);
+            assert_eq!(trigger(&parsed.to_string()), parsed);
+        }
+        assert_eq!(trigger("ctrl+wheelup"), trigger("Ctrl+WheelUp"));
+        assert_eq!(
+            Trigger::parse_sequence("Ctrl+K  Ctrl+C"),
+            Ok(vec![trigger("Ctrl+K"), trigger("Ctrl+C")])
+        );
+        assert_eq!(
+            Trigger::parse_sequence(" "),
+            Err(ParseShortcutError::MissingKey)
+        );
+    }
+
+    #[test]
+    fn single_stroke() {
+        let mut bindings = bindings(&[("Ctrl+S", "save", GLOBAL_CONTEXT)]);
+        let now = Instant::now();
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+S"), now),
+            action("save")
+        );
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+O"), now),
+            BindingResult::Unbound
+        );
+    }
+
+    #[test]
+    fn precise_wheel_steps() {
+        let mut bindings = bindings(&[
+            ("WheelUp", "up", GLOBAL_CONTEXT),
+            ("WheelDown", "down", GLOBAL_CONTEXT),
+            ("WheelRight", "right", GLOBAL_CONTEXT),
+        ]);
+        let now = Instant::now();
+        let scroll = |bindings: &mut Bindings, x, y| {
+            let event = MouseWheelEvent::new(
+                ScrollDelta::Pixels(x, y),
+                GesturePhase::Changed,
+                0.0,
+                0.0,
+                Modifiers::empty(),
+                timestamp(),
+            );
+            bindings.handle_mouse_wheel(&event, now)
+        };
+
+        // Steps add up to a notch, keeping what is left over.
+        assert_eq!(scroll(&mut bindings, 0.0, 30.0), BindingResult::Unbound);
+        assert_eq!(scroll(&mut bindings, 0.0, 15.0), action("up"));
+        assert_eq!(scroll(&mut bindings, 0.0, 40.0), action("up"));
+        // Turning around drops the leftovers rather than scrolling back through them.
+        assert_eq!(scroll(&mut bindings, 0.0, -25.0), BindingResult::Unbound);
+        assert_eq!(scroll(&mut bindings, 0.0, -20.0), action("down"));
+        // Several notches at once trigger the binding once, and the rest on the next events.
+        assert_eq!(scroll(&mut bindings, 0.0, -100.0), action("down"));
+        assert_eq!(scroll(&mut bindings, 0.0, -1.0), action("down"));
+        assert_eq!(scroll(&mut bindings, 0.0, -1.0), BindingResult::Unbound);
+        // The axes add up separately.
+        assert_eq!(scroll(&mut bindings, 30.0, 0.0), BindingResult::Unbound);
+        assert_eq!(scroll(&mut bindings, 30.0, 0.0), action("right"));
+        // A notch on both axes triggers the vertical binding first.
+        assert_eq!(scroll(&mut bindings, 30.0, 40.0), action("up"));
+        assert_eq!(scroll(&mut bindings, 1.0, 0.0), action("right"));
+
+        // Resetting and changing contexts drop the leftovers.
+        assert_eq!(scroll(&mut bindings, 0.0, 30.0), BindingResult::Unbound);
+        bindings.reset();
+        assert_eq!(scroll(&mut bindings, 0.0, 30.0), BindingResult::Unbound);
+        bindings.enter_context("editor");
+        assert_eq!(scroll(&mut bindings, 0.0, 30.0), BindingResult::Unbound);
+        bindings.leave_context("editor");
+        assert_eq!(scroll(&mut bindings, 0.0, 30.0), BindingResult::Unbound);
+        assert_eq!(scroll(&mut bindings, 0.0, 10.0), action("up"));
+    }
+
+    #[test]
+    fn chord() {
+        let mut bindings = bindings(&[
+            ("Ctrl+K Ctrl+C", "comment", GLOBAL_CONTEXT),
+            ("Ctrl+K Ctrl+U", "uncomment", GLOBAL_CONTEXT),
+        ]);
+        let now = Instant::now();
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+K"), now),
+            BindingResult::Pending
+        );
+        assert_eq!(bindings.pending(), [trigger("Ctrl+K")]);
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+U"), now),
+            action("uncomment")
+        );
+        assert!(bindings.pending().is_empty());
+
+        bindings.handle_trigger(trigger("Ctrl+K"), now);
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+X"), now),
+            BindingResult::Abandoned
+        );
+        assert!(bindings.pending().is_empty());
+
+        bindings.handle_trigger(trigger("Ctrl+K"), now);
+        bindings.reset();
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+C"), now),
+            BindingResult::Unbound
+        );
+    }
+
+    #[test]
+    fn chord_timeout() {
+        let mut bindings = bindings(&[
+            ("Ctrl+K Ctrl+C", "comment", GLOBAL_CONTEXT),
+            ("Ctrl+C", "copy", GLOBAL_CONTEXT),
+        ]);
+        bindings.set_chord_timeout(Duration::from_millis(500));
+        let start = Instant::now();
+
+        bindings.handle_trigger(trigger("Ctrl+K"), start);
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+C"), start + Duration::from_millis(500)),
+            action("comment")
+        );
+
+        // After the timeout the stroke starts over instead of continuing the chord.
+        bindings.handle_trigger(trigger("Ctrl+K"), start);
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+C"), start + Duration::from_millis(501)),
+            action("copy")
+        );
+    }
+
+    #[test]
+    fn contexts() {
+        let mut bindings = bindings(&[
+            ("Ctrl+B", "build", GLOBAL_CONTEXT),
+            ("Ctrl+B", "bold", "editor"),
+            ("Ctrl+B", "bookmark", "browser"),
+            ("Ctrl+I", "italic", "editor"),
+        ]);
+        let now = Instant::now();
+        assert!(bindings.is_context_active(GLOBAL_CONTEXT));
+        assert!(!bindings.is_context_active("editor"));
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+B"), now),
+            action("build")
+        );
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+I"), now),
+            BindingResult::Unbound
+        );
+
+        bindings.enter_context("editor");
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+B"), now),
+            action("bold")
+        );
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+I"), now),
+            action("italic")
+        );
+
+        // The most recently entered context wins, and entering a context again moves it up.
+        bindings.enter_context("browser");
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+B"), now),
+            action("bookmark")
+        );
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+I"), now),
+            action("italic")
+        );
+        bindings.enter_context("editor");
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+B"), now),
+            action("bold")
+        );
+
+        bindings.leave_context("editor");
+        bindings.leave_context("browser");
+        assert_eq!(
+            bindings.handle_trigger(trigger("Ctrl+B"), now),
+            action("build")
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut bindings = bindings(&[("Ctrl+K Ctrl+C", "comment", GLOBAL_CONTEXT)]);
+
+        let prefix = Binding::parse("Ctrl+K", "kill").unwrap();
+        let conflict = bindings.add(prefix.clone()).unwrap_err();
+        assert_eq!(conflict.rejected(), &prefix);
+        assert_eq!(conflict.existing().action(), "comment");
+
+        let extension = Binding::parse("Ctrl+K Ctrl+C Ctrl+D", "more").unwrap();
+        assert!(bindings.add(extension).is_err());
+
+        // Siblings and bindings in other contexts don't conflict.
+        bindings
+            .add(Binding::parse("Ctrl+K Ctrl+U", "uncomment").unwrap())
+            .unwrap();
+        bindings.add(prefix.in_context("editor")).unwrap();
+        assert_eq!(bindings.bindings().len(), 3);
+
+        bindings.remove_action("comment");
+        assert_eq!(bindings.bindings_for("comment").count(), 0);
+        assert_eq!(bindings.bindings_for("uncomment").count(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json() {
+        let source = r#"[
+            { "keys": "Ctrl+K Ctrl+C", "action": "editor.comment", "context": "editor" },
+            { "keys": "ctrl+s", "action": "file.save" },
+            { "keys": "Alt+WheelUp", "action": "zoom.in" }
+        ]"#;
+        let bindings = Bindings::from_json(source).unwrap();
+        assert_eq!(bindings.bindings().len(), 3);
+        assert_eq!(bindings.bindings()[0].context(), "editor");
+        assert_eq!(bindings.bindings()[1].context(), GLOBAL_CONTEXT);
+        assert_eq!(bindings.bindings()[1].sequence(), [trigger("Ctrl+S")]);
+
+        let saved = Bindings::from_json(&bindings.to_json()).unwrap();
+        assert_eq!(saved.bindings(), bindings.bindings());
+        assert!(!bindings.to_json().contains(GLOBAL_CONTEXT));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_errors() {
+        assert!(matches!(
+            Bindings::from_json("{"),
+            Err(LoadBindingsError::Json(_))
+        ));
+        match Bindings::from_json(r#"[{ "keys": "Ctrl+Nope", "action": "a" }]"#) {
+            Err(LoadBindingsError::Parse(keys, ParseShortcutError::UnknownKey(_))) => {
+                assert_eq!(keys, "Ctrl+Nope")
+            }
+            result => panic!("{:?}", result.map(|bindings| bindings.to_json())),
+        }
+        let conflicting = r#"[
+            { "keys": "Ctrl+K", "action": "a" },
+            { "keys": "Ctrl+K Ctrl+C", "action": "b" }
+        ]"#;
+        assert!(matches!(
+            Bindings::from_json(conflicting),
+            Err(LoadBindingsError::Conflict(_))
+        ));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        let source = r#"
+            [[binding]]
+            keys = "Ctrl+K Ctrl+C"
+            action = "editor.comment"
+            context = "editor"
+
+            [[binding]]
+            keys = "ctrl+s"
+            action = "file.save"
+        "#;
+        let bindings = Bindings::from_toml(source).unwrap();
+        assert_eq!(bindings.bindings().len(), 2);
+        assert_eq!(bindings.bindings()[0].context(), "editor");
+        assert_eq!(
+            bindings.bindings()[0].sequence(),
+            [trigger("Ctrl+K"), trigger("Ctrl+C")]
+        );
+        assert_eq!(bindings.bindings()[1].context(), GLOBAL_CONTEXT);
+
+        let saved = Bindings::from_toml(&bindings.to_toml()).unwrap();
+        assert_eq!(saved.bindings(), bindings.bindings());
+        assert!(!bindings.to_toml().contains(GLOBAL_CONTEXT));
+        assert!(Bindings::from_toml("").unwrap().bindings().is_empty());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_errors() {
+        assert!(matches!(
+            Bindings::from_toml("[[binding]]\nkeys = 1"),
+            Err(LoadBindingsError::Toml(_))
+        ));
+        assert!(matches!(
+            Bindings::from_toml("[[binding]]\nkeys = \"Ctrl+Nope\"\naction = \"a\""),
+            Err(LoadBindingsError::Parse(
+                _,
+                ParseShortcutError::UnknownKey(_)
+            ))
+        ));
+    }
+}
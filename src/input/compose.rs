@@ -0,0 +1,830 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A compose engine reading libX11 / xkbcommon `Compose` files, which turns sequences such as
+//! `<dead_acute> <e>` or `<Multi_key> <o> <c>` into text.
+
+use super::*;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// The directory libX11 installs its locale specific compose files to.
+const SYSTEM_LOCALE_DIR: &str = "/usr/share/X11/locale";
+
+/// Guards against include cycles.
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// Names of the keysyms for U+0020 to U+007E.
+const ASCII_KEYSYMS: [&str; 95] = [
+    "space",
+    "exclam",
+    "quotedbl",
+    "numbersign",
+    "dollar",
+    "percent",
+    "ampersand",
+    "apostrophe",
+    "parenleft",
+    "parenright",
+    "asterisk",
+    "plus",
+    "comma",
+    "minus",
+    "period",
+    "slash",
+    "0",
+    "1",
+    "2",
+    "3",
+    "4",
+    "5",
+    "6",
+    "7",
+    "8",
+    "9",
+    "colon",
+    "semicolon",
+    "less",
+    "equal",
+    "greater",
+    "question",
+    "at",
+    "A",
+    "B",
+    "C",
+    "D",
+    "E",
+    "F",
+    "G",
+    "H",
+    "I",
+    "J",
+    "K",
+    "L",
+    "M",
+    "N",
+    "O",
+    "P",
+    "Q",
+    "R",
+    "S",
+    "T",
+    "U",
+    "V",
+    "W",
+    "X",
+    "Y",
+    "Z",
+    "bracketleft",
+    "backslash",
+    "bracketright",
+    "asciicircum",
+    "underscore",
+    "grave",
+    "a",
+    "b",
+    "c",
+    "d",
+    "e",
+    "f",
+    "g",
+    "h",
+    "i",
+    "j",
+    "k",
+    "l",
+    "m",
+    "n",
+    "o",
+    "p",
+    "q",
+    "r",
+    "s",
+    "t",
+    "u",
+    "v",
+    "w",
+    "x",
+    "y",
+    "z",
+    "braceleft",
+    "bar",
+    "braceright",
+    "asciitilde",
+];
+
+/// Names of the keysyms for U+00A0 to U+00FF.
+const LATIN1_KEYSYMS: [&str; 96] = [
+    "nobreakspace",
+    "exclamdown",
+    "cent",
+    "sterling",
+    "currency",
+    "yen",
+    "brokenbar",
+    "section",
+    "diaeresis",
+    "copyright",
+    "ordfeminine",
+    "guillemotleft",
+    "notsign",
+    "hyphen",
+    "registered",
+    "macron",
+    "degree",
+    "plusminus",
+    "twosuperior",
+    "threesuperior",
+    "acute",
+    "mu",
+    "paragraph",
+    "periodcentered",
+    "cedilla",
+    "onesuperior",
+    "masculine",
+    "guillemotright",
+    "onequarter",
+    "onehalf",
+    "threequarters",
+    "questiondown",
+    "Agrave",
+    "Aacute",
+    "Acircumflex",
+    "Atilde",
+    "Adiaeresis",
+    "Aring",
+    "AE",
+    "Ccedilla",
+    "Egrave",
+    "Eacute",
+    "Ecircumflex",
+    "Ediaeresis",
+    "Igrave",
+    "Iacute",
+    "Icircumflex",
+    "Idiaeresis",
+    "ETH",
+    "Ntilde",
+    "Ograve",
+    "Oacute",
+    "Ocircumflex",
+    "Otilde",
+    "Odiaeresis",
+    "multiply",
+    "Oslash",
+    "Ugrave",
+    "Uacute",
+    "Ucircumflex",
+    "Udiaeresis",
+    "Yacute",
+    "THORN",
+    "ssharp",
+    "agrave",
+    "aacute",
+    "acircumflex",
+    "atilde",
+    "adiaeresis",
+    "aring",
+    "ae",
+    "ccedilla",
+    "egrave",
+    "eacute",
+    "ecircumflex",
+    "ediaeresis",
+    "igrave",
+    "iacute",
+    "icircumflex",
+    "idiaeresis",
+    "eth",
+    "ntilde",
+    "ograve",
+    "oacute",
+    "ocircumflex",
+    "otilde",
+    "odiaeresis",
+    "division",
+    "oslash",
+    "ugrave",
+    "uacute",
+    "ucircumflex",
+    "udiaeresis",
+    "yacute",
+    "thorn",
+    "ydiaeresis",
+];
+
+/// Deprecated keysym names that still appear in compose files.
+const KEYSYM_ALIASES: [(&str, char); 6] = [
+    ("quoteright", '\''),
+    ("quoteleft", '`'),
+    ("Ooblique", 'Ø'),
+    ("ooblique", 'ø'),
+    ("Eth", 'Ð'),
+    ("Thorn", 'Þ'),
+];
+
+/// Returns the keysym name for a character, e.g. `"eacute"` for `'é'`. Characters without a
+/// named keysym use the `U` + hex code point form.
+pub fn char_to_keysym(c: char) -> String {
+    match c as u32 {
+        code @ 0x20..=0x7E => ASCII_KEYSYMS[(code - 0x20) as usize].to_string(),
+        code @ 0xA0..=0xFF => LATIN1_KEYSYMS[(code - 0xA0) as usize].to_string(),
+        code => format!("U{:04X}", code),
+    }
+}
+
+/// Returns the character a keysym name stands for, or `None` if it is not a character keysym
+/// (e.g. `Multi_key` or `dead_acute`).
+pub fn keysym_to_char(keysym: &str) -> Option<char> {
+    if let Some(index) = ASCII_KEYSYMS.iter().position(|name| *name == keysym) {
+        return char::from_u32(0x20 + index as u32);
+    }
+    if let Some(index) = LATIN1_KEYSYMS.iter().position(|name| *name == keysym) {
+        return char::from_u32(0xA0 + index as u32);
+    }
+    if let Some((_, c)) = KEYSYM_ALIASES.iter().find(|(name, _)| *name == keysym) {
+        return Some(*c);
+    }
+
+    let hex = keysym.strip_prefix('U')?;
+    if hex.len() < 4 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+}
+
+/// Returns the dead keysym name for the character reported by a dead key.
+fn dead_char_to_keysym(c: char) -> Option<&'static str> {
+    let keysym = match c {
+        '`' => "dead_grave",
+        '´' | '\'' => "dead_acute",
+        '^' => "dead_circumflex",
+        '~' | '˜' => "dead_tilde",
+        '¯' => "dead_macron",
+        '˘' => "dead_breve",
+        '˙' => "dead_abovedot",
+        '¨' | '"' => "dead_diaeresis",
+        '˚' | '°' => "dead_abovering",
+        '˝' => "dead_doubleacute",
+        'ˇ' => "dead_caron",
+        '¸' | ',' => "dead_cedilla",
+        '˛' => "dead_ogonek",
+        _ => return None,
+    };
+    Some(keysym)
+}
+
+/// Maps equivalent keysym names, such as `eacute` and `U00E9`, to a single name.
+fn canonical_keysym(keysym: &str) -> String {
+    match keysym_to_char(keysym) {
+        Some(c) => char_to_keysym(c),
+        None => keysym.to_string(),
+    }
+}
+
+#[derive(Debug)]
+pub enum ComposeError {
+    Io(PathBuf, std::io::Error),
+    IncludeDepth(PathBuf),
+}
+
+impl fmt::Display for ComposeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, error) => write!(f, "failed to read {}: {}", path.display(), error),
+            Self::IncludeDepth(path) => {
+                write!(f, "compose files nested too deeply at {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ComposeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, error) => Some(error),
+            Self::IncludeDepth(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct ComposeNode {
+    result: Option<String>,
+    children: HashMap<String, ComposeNode>,
+}
+
+/// The sequences defined by one or more compose files.
+#[derive(Debug, Default, Clone)]
+pub struct ComposeTable {
+    root: ComposeNode,
+}
+
+impl ComposeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses compose definitions from a string. `include` statements are ignored, as there is no
+    /// file to resolve them relative to. Lines that can't be parsed are skipped, like libX11 does.
+    pub fn parse(source: &str) -> Self {
+        let mut table = Self::new();
+        table.add_source(source, None, 0).unwrap();
+        table
+    }
+
+    /// Loads a compose file, following its `include` statements.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ComposeError> {
+        let mut table = Self::new();
+        table.add_file(path.as_ref(), None, 0)?;
+        Ok(table)
+    }
+
+    /// Loads the system compose file for a locale such as `"en_US.UTF-8"`.
+    pub fn from_locale(locale: &str) -> Result<Self, ComposeError> {
+        let path = locale_compose_file(locale)?;
+        let mut table = Self::new();
+        table.add_file(&path, Some(locale), 0)?;
+        Ok(table)
+    }
+
+    /// Loads the compose file the user has configured, in the same order as libX11: the file
+    /// named by `XCOMPOSEFILE`, then `~/.XCompose`, then the system file for the locale.
+    pub fn from_env(locale: &str) -> Result<Self, ComposeError> {
+        let user_file = std::env::var_os("XCOMPOSEFILE")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".XCompose")))
+            .filter(|path| path.is_file());
+
+        let mut table = Self::new();
+        match user_file {
+            Some(path) => table.add_file(&path, Some(locale), 0)?,
+            None => table.add_file(&locale_compose_file(locale)?, Some(locale), 0)?,
+        }
+        Ok(table)
+    }
+
+    /// Looks up the result of a complete sequence of keysym names.
+    pub fn lookup<S: AsRef<str>>(&self, sequence: &[S]) -> Option<&str> {
+        self.node(sequence)?.result.as_deref()
+    }
+
+    /// Defines a sequence. Like xkbcommon, a later definition replaces any earlier one that it
+    /// conflicts with, including sequences it is a prefix of.
+    pub fn insert<S: AsRef<str>>(&mut self, sequence: &[S], result: impl Into<String>) {
+        if sequence.is_empty() {
+            return;
+        }
+
+        let mut node = &mut self.root;
+        for keysym in sequence {
+            node.result = None;
+            node = node
+                .children
+                .entry(canonical_keysym(keysym.as_ref()))
+                .or_default();
+        }
+        node.children.clear();
+        node.result = Some(result.into());
+    }
+
+    fn node<S: AsRef<str>>(&self, sequence: &[S]) -> Option<&ComposeNode> {
+        sequence.iter().try_fold(&self.root, |node, keysym| {
+            node.children.get(&canonical_keysym(keysym.as_ref()))
+        })
+    }
+
+    fn add_file(
+        &mut self,
+        path: &Path,
+        locale: Option<&str>,
+        depth: usize,
+    ) -> Result<(), ComposeError> {
+        if depth > MAX_INCLUDE_DEPTH {
+            return Err(ComposeError::IncludeDepth(path.to_path_buf()));
+        }
+        let source = std::fs::read_to_string(path)
+            .map_err(|error| ComposeError::Io(path.to_path_buf(), error))?;
+        self.add_source(&source, Some((path, locale)), depth)
+    }
+
+    fn add_source(
+        &mut self,
+        source: &str,
+        file: Option<(&Path, Option<&str>)>,
+        depth: usize,
+    ) -> Result<(), ComposeError> {
+        for line in source.lines() {
+            match parse_line(line) {
+                Some(Line::Include(include)) => {
+                    let Some((path, locale)) = file else {
+                        continue;
+                    };
+                    if let Some(include) = resolve_include(&include, path, locale)? {
+                        self.add_file(&include, locale, depth + 1)?;
+                    }
+                }
+                Some(Line::Sequence(sequence, result)) => self.insert(&sequence, result),
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn locale_compose_file(locale: &str) -> Result<PathBuf, ComposeError> {
+    let dir = Path::new(SYSTEM_LOCALE_DIR);
+    let index = dir.join("compose.dir");
+    let source =
+        std::fs::read_to_string(&index).map_err(|error| ComposeError::Io(index.clone(), error))?;
+
+    // Each line maps a compose file to a locale, e.g. "en_US.UTF-8/Compose: en_US.UTF-8".
+    source
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(':'))
+        .find(|(_, name)| name.trim() == locale)
+        .map(|(file, _)| dir.join(file.trim()))
+        .ok_or_else(|| {
+            ComposeError::Io(
+                index,
+                std::io::Error::new(std::io::ErrorKind::NotFound, locale.to_string()),
+            )
+        })
+}
+
+/// Expands the substitutions libX11 supports in `include` statements: `%H` for the home
+/// directory, `%L` for the compose file of the locale and `%S` for the system directory.
+fn resolve_include(
+    include: &str,
+    current: &Path,
+    locale: Option<&str>,
+) -> Result<Option<PathBuf>, ComposeError> {
+    if include == "%L" {
+        return match locale {
+            Some(locale) => locale_compose_file(locale).map(Some),
+            None => Ok(None),
+        };
+    }
+
+    let mut path = include.replace("%S", SYSTEM_LOCALE_DIR);
+    if path.contains("%H") {
+        let Some(home) = std::env::var_os("HOME") else {
+            return Ok(None);
+        };
+        path = path.replace("%H", &home.to_string_lossy());
+    }
+
+    let path = PathBuf::from(path);
+    if path.is_relative() {
+        Ok(current.parent().map(|dir| dir.join(&path)))
+    } else {
+        Ok(Some(path))
+    }
+}
+
+enum Line {
+    Include(String),
+    Sequence(Vec<String>, String),
+}
+
+/// Parses a single line of a compose file, e.g. `<dead_acute> <e> : "é" eacute # comment`.
+/// Sequences with modifier conditions are not supported and are skipped.
+fn parse_line(line: &str) -> Option<Line> {
+    let line = line.trim_start();
+    if let Some(rest) = line.strip_prefix("include") {
+        let (include, _) = parse_string(rest.trim_start())?;
+        return Some(Line::Include(include));
+    }
+
+    let (events, result) = line.split_once(':')?;
+    let mut sequence = Vec::new();
+    let mut events = events.trim();
+    while !events.is_empty() {
+        let rest = events.strip_prefix('<')?;
+        let (keysym, rest) = rest.split_once('>')?;
+        sequence.push(keysym.to_string());
+        events = rest.trim_start();
+    }
+    if sequence.is_empty() {
+        return None;
+    }
+
+    let result = result.trim_start();
+    let text = if result.starts_with('"') {
+        parse_string(result)?.0
+    } else {
+        // Only a keysym is given, which is only usable if it names a character.
+        let keysym = result.split_whitespace().next()?;
+        keysym_to_char(keysym)?.to_string()
+    };
+    Some(Line::Sequence(sequence, text))
+}
+
+/// Parses a quoted string with the escapes allowed in compose files, returning the string and the
+/// remaining input.
+fn parse_string(input: &str) -> Option<(String, &str)> {
+    let mut chars = input.strip_prefix('"')?.char_indices().peekable();
+    let mut text = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Some((text, &input[index + 2..])),
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                match escaped {
+                    'n' => text.push('\n'),
+                    'r' => text.push('\r'),
+                    't' => text.push('\t'),
+                    'x' | 'X' => {
+                        let mut code = 0;
+                        while let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(16)) {
+                            code = code * 16 + digit;
+                            chars.next();
+                        }
+                        text.push(char::from_u32(code)?);
+                    }
+                    '0'..='7' => {
+                        let mut code = escaped.to_digit(8)?;
+                        for _ in 0..2 {
+                            let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(8)) else {
+                                break;
+                            };
+                            code = code * 8 + digit;
+                            chars.next();
+                        }
+                        text.push(char::from_u32(code)?);
+                    }
+                    c => text.push(c),
+                }
+            }
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// The result of feeding a key to a `Composer`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComposeStatus {
+    /// The key isn't part of a compose sequence and should be handled normally.
+    Ignored,
+    /// The key continues a compose sequence, and should not be handled by the application.
+    Composing,
+    /// The key completed a compose sequence, producing text.
+    Composed(TextInputEvent),
+    /// The key doesn't continue the pending sequence, which was discarded along with the key.
+    Cancelled,
+}
+
+/// Tracks a compose sequence as keys are typed.
+#[derive(Debug, Clone)]
+pub struct Composer {
+    table: ComposeTable,
+    pending: Vec<String>,
+}
+
+impl Composer {
+    pub fn new(table: ComposeTable) -> Self {
+        Self {
+            table,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn table(&self) -> &ComposeTable {
+        &self.table
+    }
+
+    pub fn is_composing(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Discards the pending sequence.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Feeds a keysym name. `timestamp` is the time of the key, which is given to the composed
+    /// text.
+    pub fn feed_keysym(&mut self, keysym: &str, timestamp: Timestamp) -> ComposeStatus {
+        let keysym = canonical_keysym(keysym);
+        let Some(node) = self.table.node(&self.pending) else {
+            self.reset();
+            return ComposeStatus::Cancelled;
+        };
+
+        match node.children.get(&keysym) {
+            Some(ComposeNode {
+                result: Some(text), ..
+            }) => {
+                let text = text.clone();
+                self.reset();
+                ComposeStatus::Composed(TextInputEvent::new(text, timestamp))
+            }
+            Some(_) => {
+                self.pending.push(keysym);
+                ComposeStatus::Composing
+            }
+            None if self.pending.is_empty() => ComposeStatus::Ignored,
+            None => {
+                self.reset();
+                ComposeStatus::Cancelled
+            }
+        }
+    }
+
+    pub fn feed_char(&mut self, c: char, timestamp: Timestamp) -> ComposeStatus {
+        self.feed_keysym(&char_to_keysym(c), timestamp)
+    }
+
+    /// Feeds a key event, which only affects the sequence for dead keys. Characters should be fed
+    /// with `feed_char` as they are reported by `CharEvent` or `TextInputEvent`.
+    pub fn feed_key(&mut self, event: &KeyEvent) -> ComposeStatus {
+        if event.is_up() {
+            return ComposeStatus::Ignored;
+        }
+        match event.dead_key().and_then(dead_char_to_keysym) {
+            Some(keysym) => self.feed_keysym(keysym, event.timestamp()),
+            None => ComposeStatus::Ignored,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/compose")
+            .join(name)
+    }
+
+    fn timestamp() -> Timestamp {
+        Timestamp::from_duration(Duration::from_millis(10))
+    }
+
+    fn composer() -> Composer {
+        Composer::new(ComposeTable::from_file(fixture("user.XCompose")).unwrap())
+    }
+
+    fn dead_key(c: char, state: KeyState) -> KeyEvent {
+        KeyEvent::new(
+            VirtualKey::Quote,
+            KeyLocation::Standard,
+            0x0028,
+            state,
+            Modifiers::empty(),
+            Some(c),
+            timestamp(),
+        )
+    }
+
+    fn composed(text: &str) -> ComposeStatus {
+        ComposeStatus::Composed(TextInputEvent::new(text.to_string(), timestamp()))
+    }
+
+    #[test]
+    fn parse_base_file() {
+        let table = ComposeTable::from_file(fixture("base.Compose")).unwrap();
+        assert_eq!(table.lookup(&["dead_acute", "e"]), Some("é"));
+        assert_eq!(table.lookup(&["dead_acute", "E"]), Some("É"));
+        assert_eq!(table.lookup(&["Multi_key", "o", "c"]), Some("©"));
+        assert_eq!(
+            table.lookup(&["Multi_key", "minus", "minus", "period"]),
+            Some("–")
+        );
+        // Results given only as a keysym, including deprecated names in the sequence.
+        assert_eq!(table.lookup(&["Multi_key", "minus", "greater"]), Some("→"));
+        assert_eq!(table.lookup(&["Multi_key", "apostrophe", "a"]), Some("á"));
+        // Prefixes and skipped lines have no result.
+        assert_eq!(table.lookup(&["Multi_key", "minus"]), None);
+        assert_eq!(table.lookup(&["Multi_key", "x"]), None);
+        assert_eq!(table.lookup(&["Multi_key", "broken"]), None);
+    }
+
+    #[test]
+    fn include_and_override() {
+        let table = ComposeTable::from_file(fixture("user.XCompose")).unwrap();
+        assert_eq!(table.lookup(&["dead_acute", "e"]), Some("é"));
+        assert_eq!(table.lookup(&["Multi_key", "o", "c"]), Some("(c)"));
+        assert_eq!(
+            table.lookup(&["Multi_key", "s", "h", "r", "u", "g"]),
+            Some("¯\\_(ツ)_/¯")
+        );
+        assert_eq!(
+            table.lookup(&["Multi_key", "q", "u", "o", "t", "e"]),
+            Some("\"AB\n")
+        );
+    }
+
+    #[test]
+    fn include_cycle() {
+        assert!(matches!(
+            ComposeTable::from_file(fixture("cycle.XCompose")),
+            Err(ComposeError::IncludeDepth(_))
+        ));
+    }
+
+    #[test]
+    fn missing_file() {
+        assert!(matches!(
+            ComposeTable::from_file(fixture("missing.XCompose")),
+            Err(ComposeError::Io(..))
+        ));
+    }
+
+    #[test]
+    fn parse_ignores_includes() {
+        let table = ComposeTable::parse("include \"base.Compose\"\n<a> <b> : \"c\"\n");
+        assert_eq!(table.lookup(&["a", "b"]), Some("c"));
+        assert_eq!(table.lookup(&["dead_acute", "e"]), None);
+    }
+
+    #[test]
+    fn equivalent_keysyms() {
+        let table = ComposeTable::parse("<dead_acute> <U0065> : \"é\"\n");
+        assert_eq!(table.lookup(&["dead_acute", "e"]), Some("é"));
+        assert_eq!(char_to_keysym('é'), "eacute");
+        assert_eq!(keysym_to_char("U2192"), Some('→'));
+        assert_eq!(keysym_to_char("Multi_key"), None);
+    }
+
+    #[test]
+    fn later_definitions_replace_prefixes() {
+        let mut table = ComposeTable::new();
+        table.insert(&["Multi_key", "a", "b"], "long");
+        table.insert(&["Multi_key", "a"], "short");
+        assert_eq!(table.lookup(&["Multi_key", "a"]), Some("short"));
+        assert_eq!(table.lookup(&["Multi_key", "a", "b"]), None);
+    }
+
+    #[test]
+    fn compose_dead_key() {
+        let mut composer = composer();
+        assert_eq!(
+            composer.feed_key(&dead_key('´', KeyState::Press)),
+            ComposeStatus::Composing
+        );
+        assert!(composer.is_composing());
+        assert_eq!(
+            composer.feed_key(&dead_key('´', KeyState::Release)),
+            ComposeStatus::Ignored
+        );
+        assert_eq!(composer.feed_char('e', timestamp()), composed("é"));
+        assert!(!composer.is_composing());
+    }
+
+    #[test]
+    fn compose_multi_key() {
+        let mut composer = composer();
+        assert_eq!(
+            composer.feed_keysym("Multi_key", timestamp()),
+            ComposeStatus::Composing
+        );
+        assert_eq!(
+            composer.feed_char('-', timestamp()),
+            ComposeStatus::Composing
+        );
+        assert_eq!(
+            composer.feed_char('-', timestamp()),
+            ComposeStatus::Composing
+        );
+        assert_eq!(composer.feed_char('.', timestamp()), composed("–"));
+    }
+
+    #[test]
+    fn cancel_and_reset() {
+        let mut composer = composer();
+        assert_eq!(composer.feed_char('x', timestamp()), ComposeStatus::Ignored);
+
+        composer.feed_keysym("Multi_key", timestamp());
+        assert_eq!(
+            composer.feed_char('z', timestamp()),
+            ComposeStatus::Cancelled
+        );
+        assert!(!composer.is_composing());
+        assert_eq!(composer.feed_char('e', timestamp()), ComposeStatus::Ignored);
+
+        composer.feed_keysym("dead_acute", timestamp());
+        composer.reset();
+        assert_eq!(composer.feed_char('e', timestamp()), ComposeStatus::Ignored);
+    }
+}
@@ -14,12 +14,16 @@
  * limitations under the License.
  */
 
-use crate::platform_impl;
+use super::virtual_key;
+
+use std::fmt;
+use std::str::FromStr;
 
 pub(crate) type VirtualKeyT = u32;
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum KeyState {
     Press,
     Release,
@@ -28,6 +32,7 @@ pub enum KeyState {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ButtonState {
     Release,
     Press,
@@ -35,6 +40,7 @@ pub enum ButtonState {
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseButton {
     Left,
     Right,
@@ -43,173 +49,422 @@ pub enum MouseButton {
     X2,
 }
 
-#[repr(u32)]
-#[non_exhaustive]
+/// The stage a touch is in. A touch that is cancelled, e.g. because the system recognized a
+/// gesture, should not trigger the action it would have when ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
+/// Where on the keyboard a key is located, for keys that exist in more than one place.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum VirtualKey {
-    A = platform_impl::virtual_key::A,
-    B = platform_impl::virtual_key::B,
-    C = platform_impl::virtual_key::C,
-    D = platform_impl::virtual_key::D,
-    E = platform_impl::virtual_key::E,
-    F = platform_impl::virtual_key::F,
-    G = platform_impl::virtual_key::G,
-    H = platform_impl::virtual_key::H,
-    I = platform_impl::virtual_key::I,
-    J = platform_impl::virtual_key::J,
-    K = platform_impl::virtual_key::K,
-    L = platform_impl::virtual_key::L,
-    M = platform_impl::virtual_key::M,
-    N = platform_impl::virtual_key::N,
-    O = platform_impl::virtual_key::O,
-    P = platform_impl::virtual_key::P,
-    Q = platform_impl::virtual_key::Q,
-    R = platform_impl::virtual_key::R,
-    S = platform_impl::virtual_key::S,
-    T = platform_impl::virtual_key::T,
-    U = platform_impl::virtual_key::U,
-    V = platform_impl::virtual_key::V,
-    W = platform_impl::virtual_key::W,
-    X = platform_impl::virtual_key::X,
-    Y = platform_impl::virtual_key::Y,
-    Z = platform_impl::virtual_key::Z,
-
-    Num0 = platform_impl::virtual_key::NUM0,
-    Num1 = platform_impl::virtual_key::NUM1,
-    Num2 = platform_impl::virtual_key::NUM2,
-    Num3 = platform_impl::virtual_key::NUM3,
-    Num4 = platform_impl::virtual_key::NUM4,
-    Num5 = platform_impl::virtual_key::NUM5,
-    Num6 = platform_impl::virtual_key::NUM6,
-    Num7 = platform_impl::virtual_key::NUM7,
-    Num8 = platform_impl::virtual_key::NUM8,
-    Num9 = platform_impl::virtual_key::NUM9,
-
-    Numpad0 = platform_impl::virtual_key::NUMPAD0,
-    Numpad1 = platform_impl::virtual_key::NUMPAD1,
-    Numpad2 = platform_impl::virtual_key::NUMPAD2,
-    Numpad3 = platform_impl::virtual_key::NUMPAD3,
-    Numpad4 = platform_impl::virtual_key::NUMPAD4,
-    Numpad5 = platform_impl::virtual_key::NUMPAD5,
-    Numpad6 = platform_impl::virtual_key::NUMPAD6,
-    Numpad7 = platform_impl::virtual_key::NUMPAD7,
-    Numpad8 = platform_impl::virtual_key::NUMPAD8,
-    Numpad9 = platform_impl::virtual_key::NUMPAD9,
-    NumpadMultiply = platform_impl::virtual_key::NUMPAD_MULTIPLY,
-    NumpadAdd = platform_impl::virtual_key::NUMPAD_ADD,
-    NumpadSeparator = platform_impl::virtual_key::NUMPAD_SEPARATOR,
-    NumpadSubtract = platform_impl::virtual_key::NUMPAD_SUBTRACT,
-    NumpadDecimal = platform_impl::virtual_key::NUMPAD_DECIMAL,
-    NumpadDivide = platform_impl::virtual_key::NUMPAD_DIVIDE,
-
-    Backspace = platform_impl::virtual_key::BACKSPACE,
-    Tab = platform_impl::virtual_key::TAB,
-    Enter = platform_impl::virtual_key::ENTER,
-
-    F1 = platform_impl::virtual_key::F1,
-    F2 = platform_impl::virtual_key::F2,
-    F3 = platform_impl::virtual_key::F3,
-    F4 = platform_impl::virtual_key::F4,
-    F5 = platform_impl::virtual_key::F5,
-    F6 = platform_impl::virtual_key::F6,
-    F7 = platform_impl::virtual_key::F7,
-    F8 = platform_impl::virtual_key::F8,
-    F9 = platform_impl::virtual_key::F9,
-    F10 = platform_impl::virtual_key::F10,
-    F11 = platform_impl::virtual_key::F11,
-    F12 = platform_impl::virtual_key::F12,
-    F13 = platform_impl::virtual_key::F13,
-    F14 = platform_impl::virtual_key::F14,
-    F15 = platform_impl::virtual_key::F15,
-    F16 = platform_impl::virtual_key::F16,
-    F17 = platform_impl::virtual_key::F17,
-    F18 = platform_impl::virtual_key::F18,
-    F19 = platform_impl::virtual_key::F19,
-    F20 = platform_impl::virtual_key::F20,
-    F21 = platform_impl::virtual_key::F21,
-    F22 = platform_impl::virtual_key::F22,
-    F23 = platform_impl::virtual_key::F23,
-    F24 = platform_impl::virtual_key::F24,
-
-    Shift = platform_impl::virtual_key::SHIFT,
-    Control = platform_impl::virtual_key::CONTROL,
-    Alt = platform_impl::virtual_key::ALT,
-
-    LeftShift = platform_impl::virtual_key::LEFT_SHIFT,
-    RightShift = platform_impl::virtual_key::RIGHT_SHIFT,
-    LeftControl = platform_impl::virtual_key::LEFT_CONTROL,
-    RightControl = platform_impl::virtual_key::RIGHT_CONTROL,
-    LeftAlt = platform_impl::virtual_key::LEFT_ALT,
-    RightAlt = platform_impl::virtual_key::RIGHT_ALT,
-    LeftSuper = platform_impl::virtual_key::LEFT_SUPER,
-    RightSuper = platform_impl::virtual_key::RIGHT_SUPER,
-
-    Menu = platform_impl::virtual_key::MENU,
-
-    CapsLock = platform_impl::virtual_key::CAPS_LOCK,
-    NumLock = platform_impl::virtual_key::NUM_LOCK,
-    ScrollLock = platform_impl::virtual_key::SCROLL_LOCK,
-
-    Pause = platform_impl::virtual_key::PAUSE,
-    Escape = platform_impl::virtual_key::ESCAPE,
-    Space = platform_impl::virtual_key::SPACE,
-
-    Insert = platform_impl::virtual_key::INSERT,
-    Delete = platform_impl::virtual_key::DELETE,
-    PageUp = platform_impl::virtual_key::PAGE_UP,
-    PageDown = platform_impl::virtual_key::PAGE_DOWN,
-    End = platform_impl::virtual_key::END,
-    Home = platform_impl::virtual_key::HOME,
-
-    Left = platform_impl::virtual_key::LEFT,
-    Up = platform_impl::virtual_key::UP,
-    Right = platform_impl::virtual_key::RIGHT,
-    Down = platform_impl::virtual_key::DOWN,
-
-    Clear = platform_impl::virtual_key::CLEAR,
-    Select = platform_impl::virtual_key::SELECT,
-    Print = platform_impl::virtual_key::PRINT,
-    Execute = platform_impl::virtual_key::EXECUTE,
-    PrintScreen = platform_impl::virtual_key::PRINT_SCREEN,
-    Help = platform_impl::virtual_key::HELP,
-
-    Colon = platform_impl::virtual_key::COLON,
-    Plus = platform_impl::virtual_key::PLUS,
-    Comma = platform_impl::virtual_key::COMMA,
-    Minus = platform_impl::virtual_key::MINUS,
-    Period = platform_impl::virtual_key::PERIOD,
-    Slash = platform_impl::virtual_key::SLASH,
-    Tilde = platform_impl::virtual_key::TILDE,
-    LeftBracket = platform_impl::virtual_key::LEFT_BRACKET,
-    Backslash = platform_impl::virtual_key::BACKSLASH,
-    RightBracket = platform_impl::virtual_key::RIGHT_BRACKET,
-    Quote = platform_impl::virtual_key::QUOTE,
-
-    VolumeMute = platform_impl::virtual_key::VOLUME_MUTE,
-    VolumeDown = platform_impl::virtual_key::VOLUME_DOWN,
-    VolumeUp = platform_impl::virtual_key::VOLUME_UP,
-
-    MediaNextTrack = platform_impl::virtual_key::MEDIA_NEXT_TRACK,
-    MediaPreviousTrack = platform_impl::virtual_key::MEDIA_PREV_TRACK,
-    MediaStop = platform_impl::virtual_key::MEDIA_STOP,
-    MediaPlayPause = platform_impl::virtual_key::MEDIA_PLAY_PAUSE,
-    // Sleep = platform_impl::virtual_key::SLEEP,
-    // BrowserBack,
-    // BrowserForward ,
-    // BrowserRefresh,
-    // BrowserStop,
-    // BrowserSearch,
-    // BrowserFavorites,
-    // BrowserHome,
-
-    // LaunchMail,
-    // LaunchMediaSelect,
-    // LaunchApp1,
-    // LaunchApp2,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KeyLocation {
+    Standard,
+    Left,
+    Right,
+    Numpad,
+}
+
+/// Declares the `VirtualKey` enum along with the conversions to and from its key codes, which
+/// are the Windows virtual-key codes on every platform.
+macro_rules! virtual_keys {
+    ($($(#[$attr:meta])* $name:ident = $native:path,)*) => {
+        #[non_exhaustive]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum VirtualKey {
+            $($(#[$attr])* $name,)*
+            /// A key without a dedicated variant, holding its Windows virtual-key code. Keys
+            /// that have no virtual-key code at all are `Unknown(0xFF)`, as Windows reports them.
+            Unknown(u32),
+        }
+
+        impl VirtualKey {
+            /// Every key with a dedicated variant.
+            #[cfg(test)]
+            const NAMED: &'static [Self] = &[$(Self::$name,)*];
+
+            /// Converts a Windows virtual-key code into a `VirtualKey`, falling back to
+            /// `VirtualKey::Unknown` for codes that have no dedicated variant. Backends on other
+            /// platforms translate their key codes into virtual-key codes.
+            pub const fn from_native(value: u32) -> Self {
+                match value {
+                    $($native => Self::$name,)*
+                    _ => Self::Unknown(value),
+                }
+            }
+
+            /// Returns the Windows virtual-key code of this key.
+            pub const fn to_native(&self) -> u32 {
+                match self {
+                    $(Self::$name => $native,)*
+                    Self::Unknown(value) => *value,
+                }
+            }
+
+            /// Returns the canonical name of the key, e.g. `"PageUp"`, or `None` for
+            /// `VirtualKey::Unknown`.
+            pub const fn name(&self) -> Option<&'static str> {
+                match self {
+                    $(Self::$name => Some(stringify!($name)),)*
+                    Self::Unknown(_) => None,
+                }
+            }
+
+            /// Looks up a key by its canonical name.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(Self::$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+virtual_keys! {
+    A = virtual_key::A,
+    B = virtual_key::B,
+    C = virtual_key::C,
+    D = virtual_key::D,
+    E = virtual_key::E,
+    F = virtual_key::F,
+    G = virtual_key::G,
+    H = virtual_key::H,
+    I = virtual_key::I,
+    J = virtual_key::J,
+    K = virtual_key::K,
+    L = virtual_key::L,
+    M = virtual_key::M,
+    N = virtual_key::N,
+    O = virtual_key::O,
+    P = virtual_key::P,
+    Q = virtual_key::Q,
+    R = virtual_key::R,
+    S = virtual_key::S,
+    T = virtual_key::T,
+    U = virtual_key::U,
+    V = virtual_key::V,
+    W = virtual_key::W,
+    X = virtual_key::X,
+    Y = virtual_key::Y,
+    Z = virtual_key::Z,
+
+    Num0 = virtual_key::NUM0,
+    Num1 = virtual_key::NUM1,
+    Num2 = virtual_key::NUM2,
+    Num3 = virtual_key::NUM3,
+    Num4 = virtual_key::NUM4,
+    Num5 = virtual_key::NUM5,
+    Num6 = virtual_key::NUM6,
+    Num7 = virtual_key::NUM7,
+    Num8 = virtual_key::NUM8,
+    Num9 = virtual_key::NUM9,
+
+    Numpad0 = virtual_key::NUMPAD0,
+    Numpad1 = virtual_key::NUMPAD1,
+    Numpad2 = virtual_key::NUMPAD2,
+    Numpad3 = virtual_key::NUMPAD3,
+    Numpad4 = virtual_key::NUMPAD4,
+    Numpad5 = virtual_key::NUMPAD5,
+    Numpad6 = virtual_key::NUMPAD6,
+    Numpad7 = virtual_key::NUMPAD7,
+    Numpad8 = virtual_key::NUMPAD8,
+    Numpad9 = virtual_key::NUMPAD9,
+    NumpadMultiply = virtual_key::NUMPAD_MULTIPLY,
+    NumpadAdd = virtual_key::NUMPAD_ADD,
+    NumpadSeparator = virtual_key::NUMPAD_SEPARATOR,
+    NumpadSubtract = virtual_key::NUMPAD_SUBTRACT,
+    NumpadDecimal = virtual_key::NUMPAD_DECIMAL,
+    NumpadDivide = virtual_key::NUMPAD_DIVIDE,
+    NumpadEnter = virtual_key::NUMPAD_ENTER,
+
+    Backspace = virtual_key::BACKSPACE,
+    Tab = virtual_key::TAB,
+    Enter = virtual_key::ENTER,
+
+    F1 = virtual_key::F1,
+    F2 = virtual_key::F2,
+    F3 = virtual_key::F3,
+    F4 = virtual_key::F4,
+    F5 = virtual_key::F5,
+    F6 = virtual_key::F6,
+    F7 = virtual_key::F7,
+    F8 = virtual_key::F8,
+    F9 = virtual_key::F9,
+    F10 = virtual_key::F10,
+    F11 = virtual_key::F11,
+    F12 = virtual_key::F12,
+    F13 = virtual_key::F13,
+    F14 = virtual_key::F14,
+    F15 = virtual_key::F15,
+    F16 = virtual_key::F16,
+    F17 = virtual_key::F17,
+    F18 = virtual_key::F18,
+    F19 = virtual_key::F19,
+    F20 = virtual_key::F20,
+    F21 = virtual_key::F21,
+    F22 = virtual_key::F22,
+    F23 = virtual_key::F23,
+    F24 = virtual_key::F24,
+
+    Shift = virtual_key::SHIFT,
+    Control = virtual_key::CONTROL,
+    Alt = virtual_key::ALT,
+
+    LeftShift = virtual_key::LEFT_SHIFT,
+    RightShift = virtual_key::RIGHT_SHIFT,
+    LeftControl = virtual_key::LEFT_CONTROL,
+    RightControl = virtual_key::RIGHT_CONTROL,
+    LeftAlt = virtual_key::LEFT_ALT,
+    RightAlt = virtual_key::RIGHT_ALT,
+    LeftSuper = virtual_key::LEFT_SUPER,
+    RightSuper = virtual_key::RIGHT_SUPER,
+
+    /// The application (context menu) key.
+    Menu = virtual_key::MENU,
+
+    CapsLock = virtual_key::CAPS_LOCK,
+    NumLock = virtual_key::NUM_LOCK,
+    ScrollLock = virtual_key::SCROLL_LOCK,
+
+    Pause = virtual_key::PAUSE,
+    Escape = virtual_key::ESCAPE,
+    Space = virtual_key::SPACE,
+
+    Insert = virtual_key::INSERT,
+    Delete = virtual_key::DELETE,
+    PageUp = virtual_key::PAGE_UP,
+    PageDown = virtual_key::PAGE_DOWN,
+    End = virtual_key::END,
+    Home = virtual_key::HOME,
+
+    Left = virtual_key::LEFT,
+    Up = virtual_key::UP,
+    Right = virtual_key::RIGHT,
+    Down = virtual_key::DOWN,
+
+    Clear = virtual_key::CLEAR,
+    Select = virtual_key::SELECT,
+    Print = virtual_key::PRINT,
+    Execute = virtual_key::EXECUTE,
+    PrintScreen = virtual_key::PRINT_SCREEN,
+    Help = virtual_key::HELP,
+
+    Colon = virtual_key::COLON,
+    Plus = virtual_key::PLUS,
+    Comma = virtual_key::COMMA,
+    Minus = virtual_key::MINUS,
+    Period = virtual_key::PERIOD,
+    Slash = virtual_key::SLASH,
+    Tilde = virtual_key::TILDE,
+    LeftBracket = virtual_key::LEFT_BRACKET,
+    Backslash = virtual_key::BACKSLASH,
+    RightBracket = virtual_key::RIGHT_BRACKET,
+    Quote = virtual_key::QUOTE,
+
+    VolumeMute = virtual_key::VOLUME_MUTE,
+    VolumeDown = virtual_key::VOLUME_DOWN,
+    VolumeUp = virtual_key::VOLUME_UP,
+
+    MediaNextTrack = virtual_key::MEDIA_NEXT_TRACK,
+    MediaPreviousTrack = virtual_key::MEDIA_PREV_TRACK,
+    MediaStop = virtual_key::MEDIA_STOP,
+    MediaPlayPause = virtual_key::MEDIA_PLAY_PAUSE,
+
+    BrowserBack = virtual_key::BROWSER_BACK,
+    BrowserForward = virtual_key::BROWSER_FORWARD,
+    BrowserRefresh = virtual_key::BROWSER_REFRESH,
+    BrowserStop = virtual_key::BROWSER_STOP,
+    BrowserSearch = virtual_key::BROWSER_SEARCH,
+    BrowserFavorites = virtual_key::BROWSER_FAVORITES,
+    BrowserHome = virtual_key::BROWSER_HOME,
+
+    LaunchMail = virtual_key::LAUNCH_MAIL,
+    LaunchMediaSelect = virtual_key::LAUNCH_MEDIA_SELECT,
+    LaunchApp1 = virtual_key::LAUNCH_APP1,
+    LaunchApp2 = virtual_key::LAUNCH_APP2,
+
+    Sleep = virtual_key::SLEEP,
+
+    /// The Kana key on Japanese keyboards, which is the Hangul key on Korean keyboards.
+    Kana = virtual_key::KANA,
+    /// The Kanji key on Japanese keyboards, which is the Hanja key on Korean keyboards.
+    Kanji = virtual_key::KANJI,
+    Junja = virtual_key::JUNJA,
+    Final = virtual_key::FINAL,
+    ImeOn = virtual_key::IME_ON,
+    ImeOff = virtual_key::IME_OFF,
+    Convert = virtual_key::CONVERT,
+    NonConvert = virtual_key::NON_CONVERT,
+    Accept = virtual_key::ACCEPT,
+    ModeChange = virtual_key::MODE_CHANGE,
+    /// Reported while an input method editor is processing the key.
+    Process = virtual_key::PROCESS,
+
+    /// The additional key between left shift and Z on ISO keyboards.
+    Oem102 = virtual_key::OEM_102,
+    Oem8 = virtual_key::OEM_8,
+    OemClear = virtual_key::OEM_CLEAR,
+
+    Cancel = virtual_key::CANCEL,
+    Attn = virtual_key::ATTN,
+    CrSel = virtual_key::CRSEL,
+    ExSel = virtual_key::EXSEL,
+    EraseEof = virtual_key::ERASE_EOF,
+    Play = virtual_key::PLAY,
+    Zoom = virtual_key::ZOOM,
+    Pa1 = virtual_key::PA1,
 }
 
-impl VirtualKey {
-    pub(crate) const unsafe fn from_raw(value: VirtualKeyT) -> Self {
-        std::mem::transmute(value)
+impl TryFrom<u32> for VirtualKey {
+    type Error = u32;
+
+    /// Converts a virtual-key code into a `VirtualKey`, returning the code as the error if it has
+    /// no dedicated variant.
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match Self::from_native(value) {
+            Self::Unknown(value) => Err(value),
+            key => Ok(key),
+        }
+    }
+}
+
+/// Formats the key by its canonical name. Unknown keys are formatted as `Unknown(0x..)` with their
+/// virtual-key code, which `FromStr` accepts as well.
+impl fmt::Display for VirtualKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "Unknown(0x{:02X})", self.to_native()),
+        }
+    }
+}
+
+/// Parses canonical key names and the `Unknown(0x..)` form. A virtual-key code that has a
+/// dedicated variant parses to that variant, so parsing is consistent with `from_native`.
+impl FromStr for VirtualKey {
+    type Err = ParseVirtualKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = Self::from_name(s) {
+            return Ok(key);
+        }
+
+        s.strip_prefix("Unknown(0x")
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|code| u32::from_str_radix(code, 16).ok())
+            .map(Self::from_native)
+            .ok_or_else(|| ParseVirtualKeyError(s.to_string()))
+    }
+}
+
+/// Keys are serialized by their canonical name rather than their virtual-key code, so that data
+/// stays readable.
+#[cfg(feature = "serde")]
+impl serde::Serialize for VirtualKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for VirtualKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Self::from_str(&name).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVirtualKeyError(String);
+
+impl fmt::Display for ParseVirtualKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown key name \"{}\"", self.0)
+    }
+}
+
+impl std::error::Error for ParseVirtualKeyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_round_trip() {
+        for key in [
+            VirtualKey::A,
+            VirtualKey::Num1,
+            VirtualKey::NumpadEnter,
+            VirtualKey::F24,
+        ] {
+            assert_eq!(key.to_string().parse::<VirtualKey>(), Ok(key));
+        }
+    }
+
+    #[test]
+    fn native_round_trip() {
+        let mut named = 0;
+        for value in 0..=0x1FF {
+            let key = VirtualKey::from_native(value);
+            assert_eq!(key.to_native(), value, "{key:?}");
+            if key.name().is_some() {
+                named += 1;
+            }
+        }
+
+        // Every variant has a code of its own, so that it is the one `from_native` returns.
+        let mut codes = std::collections::HashSet::new();
+        for &key in VirtualKey::NAMED {
+            assert!(key.to_native() <= 0x1FF, "{key:?}");
+            assert!(codes.insert(key.to_native()), "{key:?} shares its code");
+            assert_eq!(VirtualKey::from_native(key.to_native()), key);
+        }
+        assert_eq!(named, VirtualKey::NAMED.len());
+    }
+
+    #[test]
+    fn unknown_round_trip() {
+        let key = VirtualKey::from_native(0xFFFF);
+        assert_eq!(key, VirtualKey::Unknown(0xFFFF));
+        assert_eq!(key.to_string(), "Unknown(0xFFFF)");
+        assert_eq!(key.to_string().parse::<VirtualKey>(), Ok(key));
+    }
+
+    #[test]
+    fn unknown_with_known_code() {
+        let s = format!("Unknown(0x{:02X})", VirtualKey::A.to_native());
+        assert_eq!(s.parse::<VirtualKey>(), Ok(VirtualKey::A));
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert!("NotAKey".parse::<VirtualKey>().is_err());
+        assert!("Unknown(0xZZ)".parse::<VirtualKey>().is_err());
+        assert!("Unknown(0x41".parse::<VirtualKey>().is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_by_name() {
+        let keys = [VirtualKey::PageUp, VirtualKey::Unknown(0xFFFF)];
+        let json = serde_json::to_string(&keys).unwrap();
+        assert_eq!(json, r#"["PageUp","Unknown(0xFFFF)"]"#);
+        assert_eq!(
+            serde_json::from_str::<[VirtualKey; 2]>(&json).unwrap(),
+            keys
+        );
+        assert!(serde_json::from_str::<VirtualKey>(r#""NotAKey""#).is_err());
+
+        let json = serde_json::to_string(&(KeyState::Repeat, MouseButton::X1)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<(KeyState, MouseButton)>(&json).unwrap(),
+            (KeyState::Repeat, MouseButton::X1)
+        );
     }
 }
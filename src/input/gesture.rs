@@ -0,0 +1,97 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::*;
+
+/// The stage of a continuous gesture, such as a pinch or touchpad scrolling.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+    /// The fingers were lifted and the system keeps the motion going, slowing it down over time.
+    Momentum,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gesture {
+    /// Two fingers moved apart or together. The scale changed by a factor of `1 + scale_delta`
+    /// since the last event, so `scale_delta` is positive while zooming in.
+    Pinch { scale_delta: f32 },
+    /// Two fingers rotated around each other by `angle_delta` degrees clockwise since the last
+    /// event.
+    Rotate { angle_delta: f32 },
+    /// Several fingers swiped across the touchpad in the direction of `(dx, dy)`, usually to
+    /// navigate back or forward. Not reported yet: Windows keeps three and four finger swipes for
+    /// the shell, and there is no backend for other platforms.
+    Swipe { dx: f32, dy: f32 },
+}
+
+/// A touchscreen or touchpad gesture. Two finger panning is reported as `MouseWheelEvent`s with
+/// a phase, so scrolling code handles it without looking at gestures.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GestureEvent {
+    gesture: Gesture,
+    phase: GesturePhase,
+    x: f32,
+    y: f32,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
+}
+
+impl GestureEvent {
+    pub(crate) const fn new(
+        gesture: Gesture,
+        phase: GesturePhase,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            gesture,
+            phase,
+            x,
+            y,
+            modifiers,
+            timestamp,
+        }
+    }
+
+    pub const fn gesture(&self) -> Gesture {
+        self.gesture
+    }
+
+    pub const fn phase(&self) -> GesturePhase {
+        self.phase
+    }
+
+    /// Returns the center of the gesture.
+    pub const fn pos(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::*;
+
+use std::fmt;
+
+/// Identifies a global hotkey registered with `Application::register_global_hotkey`. Global
+/// hotkeys are only available on Windows, as there is no X11 backend to grab the keys with yet.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HotkeyId(i32);
+
+#[cfg(target_os = "windows")]
+impl HotkeyId {
+    pub(crate) const fn new(id: i32) -> Self {
+        Self(id)
+    }
+
+    pub(crate) const fn native(&self) -> i32 {
+        self.0
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalHotkeyEvent {
+    id: HotkeyId,
+    shortcut: Shortcut,
+    timestamp: Timestamp,
+}
+
+impl GlobalHotkeyEvent {
+    #[cfg(target_os = "windows")]
+    pub(crate) const fn new(id: HotkeyId, shortcut: Shortcut, timestamp: Timestamp) -> Self {
+        Self {
+            id,
+            shortcut,
+            timestamp,
+        }
+    }
+
+    pub const fn id(&self) -> HotkeyId {
+        self.id
+    }
+
+    pub const fn shortcut(&self) -> Shortcut {
+        self.shortcut
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyError {
+    /// The combination is already registered, by this or another application.
+    AlreadyRegistered(Shortcut),
+    /// The key can't be used in a global hotkey.
+    UnsupportedKey(VirtualKey),
+    /// The hotkey isn't registered.
+    NotRegistered(HotkeyId),
+    /// Hotkeys are registered for a thread, so only the thread running the application can
+    /// register and unregister them.
+    WrongThread,
+    /// The platform reported an error, holding its error code.
+    Os(i32),
+}
+
+impl fmt::Display for HotkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyRegistered(shortcut) => {
+                write!(f, "the hotkey {} is already registered", shortcut)
+            }
+            Self::UnsupportedKey(key) => write!(f, "{} can't be used in a global hotkey", key),
+            Self::NotRegistered(id) => write!(f, "hotkey {} is not registered", id.0),
+            Self::WrongThread => write!(
+                f,
+                "hotkeys can only be registered by the application thread"
+            ),
+            Self::Os(code) => write!(f, "failed to register the hotkey (error 0x{:08X})", code),
+        }
+    }
+}
+
+impl std::error::Error for HotkeyError {}
@@ -0,0 +1,113 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#[cfg(target_os = "windows")]
+use super::*;
+#[cfg(target_os = "windows")]
+use crate::platform_impl;
+
+use std::hash::{Hash, Hasher};
+
+/// A keyboard layout installed on the system. Layouts are compared by their id, as the handle
+/// isn't kept across serialization.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyboardLayout {
+    id: String,
+    language: String,
+    /// The platform handle of the layout, which is only valid in the process that got it. It
+    /// isn't serialized, and layouts without one translate keys with the active layout.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) native: isize,
+}
+
+impl KeyboardLayout {
+    pub(crate) const fn new(id: String, language: String, native: isize) -> Self {
+        Self {
+            id,
+            language,
+            native,
+        }
+    }
+
+    /// Returns the platform identifier of the layout, e.g. `"00000407"` for the German layout on
+    /// Windows.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Returns the BCP 47 language tag of the layout, e.g. `"de-DE"`.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the character the key produces in this layout while the modifiers are held, or
+    /// `None` if it doesn't produce a printable character. Dead keys return their diacritic.
+    #[cfg(target_os = "windows")]
+    pub fn translate(&self, key: VirtualKey, modifiers: Modifiers) -> Option<char> {
+        platform_impl::translate_key(self, key, modifiers)
+    }
+}
+
+impl PartialEq for KeyboardLayout {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for KeyboardLayout {}
+
+impl Hash for KeyboardLayout {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_skips_the_native_handle() {
+        let layout = KeyboardLayout::new("00000407".to_string(), "de-DE".to_string(), 0x04070407);
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(json, r#"{"id":"00000407","language":"de-DE"}"#);
+
+        let layout = serde_json::from_str::<KeyboardLayout>(&json).unwrap();
+        assert_eq!(layout.id(), "00000407");
+        assert_eq!(layout.language(), "de-DE");
+        assert_eq!(layout.native, 0);
+    }
+
+    #[test]
+    fn layouts_compare_by_id() {
+        let layout = KeyboardLayout::new("00000407".to_string(), "de-DE".to_string(), 0x04070407);
+        let parsed =
+            serde_json::from_str::<KeyboardLayout>(&serde_json::to_string(&layout).unwrap())
+                .unwrap();
+        assert_eq!(parsed, layout);
+
+        let hash = |layout: &KeyboardLayout| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            layout.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&parsed), hash(&layout));
+
+        let other = KeyboardLayout::new("00000409".to_string(), "en-US".to_string(), 0x04070407);
+        assert_ne!(other, layout);
+    }
+}
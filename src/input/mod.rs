@@ -14,28 +14,98 @@
  * limitations under the License.
  */
 
+mod bindings;
+mod compose;
 mod enums;
+mod gesture;
+mod hotkey;
+mod layout;
+mod modifiers;
+mod physical_key;
+mod pointer;
+mod shortcut;
+mod state;
+mod timestamp;
+pub(crate) mod virtual_key;
+pub use bindings::*;
+pub use compose::*;
 pub use enums::*;
+pub use gesture::*;
+pub use hotkey::*;
+pub use layout::*;
+pub use modifiers::*;
+pub use physical_key::*;
+pub use pointer::*;
+pub use shortcut::*;
+pub use state::*;
+pub use timestamp::*;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyEvent {
     key: VirtualKey,
+    location: KeyLocation,
+    scan_code: ScanCode,
     state: KeyState,
+    modifiers: Modifiers,
+    dead_key: Option<char>,
+    timestamp: Timestamp,
 }
 
 impl KeyEvent {
-    pub(crate) const fn new(key: VirtualKey, state: KeyState) -> Self {
-        Self { key, state }
+    pub(crate) const fn new(
+        key: VirtualKey,
+        location: KeyLocation,
+        scan_code: ScanCode,
+        state: KeyState,
+        modifiers: Modifiers,
+        dead_key: Option<char>,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            key,
+            location,
+            scan_code,
+            state,
+            modifiers,
+            dead_key,
+            timestamp,
+        }
     }
 
     pub const fn key(&self) -> VirtualKey {
         self.key
     }
 
+    pub const fn location(&self) -> KeyLocation {
+        self.location
+    }
+
+    pub const fn scan_code(&self) -> ScanCode {
+        self.scan_code
+    }
+
+    /// Returns the layout-independent position of the key, e.g. `PhysicalKey::KeyW` for the key
+    /// that produces "Z" on an AZERTY layout.
+    pub const fn physical_key(&self) -> PhysicalKey {
+        PhysicalKey::from_scan_code(self.scan_code)
+    }
+
     pub const fn state(&self) -> KeyState {
         self.state
     }
 
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the diacritic of the key if it is a dead key in the current layout, such as `'´'`
+    /// on a US-International layout. Dead keys produce no character on their own, but modify the
+    /// character of the next key.
+    pub const fn dead_key(&self) -> Option<char> {
+        self.dead_key
+    }
+
     pub fn is_key(&self, key: VirtualKey) -> bool {
         self.key == key
     }
@@ -47,17 +117,27 @@ impl KeyEvent {
     pub fn is_up(&self) -> bool {
         self.state == KeyState::Release
     }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CharEvent {
     c: char,
     repeat: bool,
+    timestamp: Timestamp,
 }
 
 impl CharEvent {
-    pub(crate) const fn new(c: char, repeat: bool) -> Self {
-        Self { c, repeat }
+    pub(crate) const fn new(c: char, repeat: bool, timestamp: Timestamp) -> Self {
+        Self {
+            c,
+            repeat,
+            timestamp,
+        }
     }
 
     pub const fn char(&self) -> char {
@@ -67,39 +147,135 @@ impl CharEvent {
     pub const fn repeat(&self) -> bool {
         self.repeat
     }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// Text committed by the user, with control characters such as backspace and escape filtered
+/// out. Editors should insert this text rather than the characters reported by `CharEvent`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextInputEvent {
+    text: String,
+    timestamp: Timestamp,
+}
+
+impl TextInputEvent {
+    pub(crate) const fn new(text: String, timestamp: Timestamp) -> Self {
+        Self { text, timestamp }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// What happened to an input method editor composition.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ime {
+    /// A composition was started.
+    Enabled,
+    /// The text being composed changed. The cursor range is given as byte offsets into `text`,
+    /// and is `None` if no cursor should be shown. An empty `text` clears the preedit.
+    Preedit {
+        text: String,
+        cursor_range: Option<(usize, usize)>,
+    },
+    /// The composed text was committed.
+    Commit(String),
+    /// The composition ended.
+    Disabled,
+}
+
+/// An input method editor event, used to enter text that takes more than one key press, such as
+/// Chinese, Japanese or Korean.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImeEvent {
+    ime: Ime,
+    timestamp: Timestamp,
+}
+
+impl ImeEvent {
+    pub(crate) const fn new(ime: Ime, timestamp: Timestamp) -> Self {
+        Self { ime, timestamp }
+    }
+
+    pub const fn ime(&self) -> &Ime {
+        &self.ime
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseMoveEvent {
     x: f32,
     y: f32,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
 }
 
 impl MouseMoveEvent {
-    pub(crate) const fn new(x: f32, y: f32) -> Self {
-        Self { x, y }
+    pub(crate) const fn new(x: f32, y: f32, modifiers: Modifiers, timestamp: Timestamp) -> Self {
+        Self {
+            x,
+            y,
+            modifiers,
+            timestamp,
+        }
     }
 
     pub const fn pos(&self) -> (f32, f32) {
         (self.x, self.y)
     }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseButtonEvent {
     button: MouseButton,
     state: ButtonState,
     x: f32,
     y: f32,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
 }
 
 impl MouseButtonEvent {
-    pub(crate) const fn new(button: MouseButton, state: ButtonState, x: f32, y: f32) -> Self {
+    pub(crate) const fn new(
+        button: MouseButton,
+        state: ButtonState,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+        timestamp: Timestamp,
+    ) -> Self {
         Self {
             button,
             state,
             x,
             y,
+            modifiers,
+            timestamp,
         }
     }
 
@@ -114,77 +290,319 @@ impl MouseButtonEvent {
     pub const fn pos(&self) -> (f32, f32) {
         (self.x, self.y)
     }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// How far to scroll. Positive `y` scrolls up, towards the start of the content, and positive `x`
+/// scrolls right, on every platform.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScrollDelta {
+    /// Lines and columns from a mouse wheel, already multiplied by the number of lines the user
+    /// chose to scroll per notch.
+    Lines(f32, f32),
+    /// Pixels from a touchpad, touchscreen or high resolution wheel.
+    Pixels(f32, f32),
+    /// Pages from a mouse wheel, when the user chose to scroll a page per notch. How far a page
+    /// goes is up to the application, usually the height of the visible content.
+    Pages(f32, f32),
+}
+
+/// The pixels a line is worth when wheel deltas that aren't whole notches are reported in
+/// pixels. Like in browsers, a notch scrolling three lines moves the content 40 pixels.
+pub(crate) const PIXELS_PER_LINE: f32 = 40.0 / 3.0;
+
+impl ScrollDelta {
+    /// Returns the delta in pixels, with lines and columns `line_height` pixels apart and pages
+    /// the width and height of `page_size`.
+    pub fn to_pixels(self, line_height: f32, page_size: (f32, f32)) -> (f32, f32) {
+        match self {
+            Self::Lines(x, y) => (x * line_height, y * line_height),
+            Self::Pixels(x, y) => (x, y),
+            Self::Pages(x, y) => (x * page_size.0, y * page_size.1),
+        }
+    }
 }
 
+/// Scrolling with a mouse wheel, or panning on a touchpad or touchscreen. Wheel notches are
+/// reported in the `Changed` phase.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MouseWheelEvent {
-    vert: f32,
-    horz: f32,
+    delta: ScrollDelta,
+    phase: GesturePhase,
     x: f32,
     y: f32,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
 }
 
 impl MouseWheelEvent {
-    pub(crate) const fn new(vert: f32, horz: f32, x: f32, y: f32) -> Self {
-        Self { vert, horz, x, y }
+    pub(crate) const fn new(
+        delta: ScrollDelta,
+        phase: GesturePhase,
+        x: f32,
+        y: f32,
+        modifiers: Modifiers,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            delta,
+            phase,
+            x,
+            y,
+            modifiers,
+            timestamp,
+        }
+    }
+
+    pub const fn delta(&self) -> ScrollDelta {
+        self.delta
+    }
+
+    pub const fn phase(&self) -> GesturePhase {
+        self.phase
+    }
+
+    /// Returns whether the delta is in pixels from a touchpad, touchscreen or high resolution
+    /// wheel, rather than in lines from a notched mouse wheel.
+    pub const fn is_precise(&self) -> bool {
+        matches!(self.delta, ScrollDelta::Pixels(..))
     }
 
-    pub const fn vert(&self) -> f32 {
-        self.vert
+    pub const fn pos(&self) -> (f32, f32) {
+        (self.x, self.y)
     }
 
-    pub const fn horz(&self) -> f32 {
-        self.horz
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+/// A finger touching the window. A touch keeps its id from the `Started` event until the
+/// `Ended` or `Cancelled` event, after which the id may be reused.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TouchEvent {
+    id: u64,
+    phase: TouchPhase,
+    x: f32,
+    y: f32,
+    force: Option<f32>,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
+}
+
+impl TouchEvent {
+    pub(crate) const fn new(
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+        force: Option<f32>,
+        modifiers: Modifiers,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            id,
+            phase,
+            x,
+            y,
+            force,
+            modifiers,
+            timestamp,
+        }
+    }
+
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub const fn phase(&self) -> TouchPhase {
+        self.phase
     }
 
     pub const fn pos(&self) -> (f32, f32) {
         (self.x, self.y)
     }
+
+    /// Returns how firmly the finger presses, from 0 to 1, if the touchscreen reports pressure.
+    pub const fn force(&self) -> Option<f32> {
+        self.force
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowMovedEvent {
     x: f32,
     y: f32,
+    timestamp: Timestamp,
 }
 
 impl WindowMovedEvent {
-    pub(crate) const fn new(x: f32, y: f32) -> Self {
-        Self { x, y }
+    pub(crate) const fn new(x: f32, y: f32, timestamp: Timestamp) -> Self {
+        Self { x, y, timestamp }
     }
 
     pub const fn pos(&self) -> (f32, f32) {
         (self.x, self.y)
     }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WindowResizedEvent {
     width: f32,
     height: f32,
+    timestamp: Timestamp,
 }
 
 impl WindowResizedEvent {
-    pub(crate) const fn new(width: f32, height: f32) -> Self {
-        Self { width, height }
+    pub(crate) const fn new(width: f32, height: f32, timestamp: Timestamp) -> Self {
+        Self {
+            width,
+            height,
+            timestamp,
+        }
     }
 
     pub const fn size(&self) -> (f32, f32) {
         (self.width, self.height)
     }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FocusChangedEvent {
     focused: bool,
+    timestamp: Timestamp,
 }
 
 impl FocusChangedEvent {
-    pub(crate) const fn new(focused: bool) -> Self {
-        Self { focused }
+    pub(crate) const fn new(focused: bool, timestamp: Timestamp) -> Self {
+        Self { focused, timestamp }
     }
 
     pub const fn focus(&self) -> bool {
         self.focused
     }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModifiersChangedEvent {
+    modifiers: Modifiers,
+    timestamp: Timestamp,
+}
+
+impl ModifiersChangedEvent {
+    pub(crate) const fn new(modifiers: Modifiers, timestamp: Timestamp) -> Self {
+        Self {
+            modifiers,
+            timestamp,
+        }
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyboardLayoutChangedEvent {
+    layout: KeyboardLayout,
+    timestamp: Timestamp,
+}
+
+impl KeyboardLayoutChangedEvent {
+    pub(crate) const fn new(layout: KeyboardLayout, timestamp: Timestamp) -> Self {
+        Self { layout, timestamp }
+    }
+
+    pub const fn layout(&self) -> &KeyboardLayout {
+        &self.layout
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    fn round_trip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let timestamp = Timestamp::from_duration(Duration::from_micros(1500));
+        let key = KeyEvent::new(
+            VirtualKey::NumpadEnter,
+            KeyLocation::Numpad,
+            0xE01C,
+            KeyState::Press,
+            Modifiers::LEFT_CONTROL | Modifiers::NUM_LOCK,
+            Some('^'),
+            timestamp,
+        );
+        assert_eq!(round_trip(&key), key);
+
+        let wheel = MouseWheelEvent::new(
+            ScrollDelta::Pixels(0.5, -12.0),
+            GesturePhase::Momentum,
+            10.0,
+            20.0,
+            Modifiers::empty(),
+            timestamp,
+        );
+        assert_eq!(round_trip(&wheel), wheel);
+
+        let text = TextInputEvent::new("日本語".to_string(), timestamp);
+        assert_eq!(round_trip(&text), text);
+    }
 }
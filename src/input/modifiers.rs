@@ -0,0 +1,71 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The state of the keyboard modifiers at the time an input event was generated.
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Modifiers: u32 {
+        const LEFT_SHIFT = 1 << 0;
+        const RIGHT_SHIFT = 1 << 1;
+        const LEFT_CONTROL = 1 << 2;
+        const RIGHT_CONTROL = 1 << 3;
+        const LEFT_ALT = 1 << 4;
+        const RIGHT_ALT = 1 << 5;
+        const LEFT_SUPER = 1 << 6;
+        const RIGHT_SUPER = 1 << 7;
+
+        const CAPS_LOCK = 1 << 8;
+        const NUM_LOCK = 1 << 9;
+
+        const SHIFT = Self::LEFT_SHIFT.bits() | Self::RIGHT_SHIFT.bits();
+        const CONTROL = Self::LEFT_CONTROL.bits() | Self::RIGHT_CONTROL.bits();
+        const ALT = Self::LEFT_ALT.bits() | Self::RIGHT_ALT.bits();
+        const SUPER = Self::LEFT_SUPER.bits() | Self::RIGHT_SUPER.bits();
+    }
+}
+
+impl Modifiers {
+    /// Returns true if either shift key is held.
+    pub const fn shift(&self) -> bool {
+        self.intersects(Self::SHIFT)
+    }
+
+    /// Returns true if either control key is held.
+    pub const fn control(&self) -> bool {
+        self.intersects(Self::CONTROL)
+    }
+
+    /// Returns true if either alt key is held.
+    pub const fn alt(&self) -> bool {
+        self.intersects(Self::ALT)
+    }
+
+    /// Returns true if either super (Windows / Command) key is held.
+    pub const fn super_key(&self) -> bool {
+        self.intersects(Self::SUPER)
+    }
+
+    pub const fn caps_lock(&self) -> bool {
+        self.contains(Self::CAPS_LOCK)
+    }
+
+    pub const fn num_lock(&self) -> bool {
+        self.contains(Self::NUM_LOCK)
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+/// A layout-independent key position, encoded as a PC/AT set 1 scan code. Keys that are sent with
+/// the `0xE0` prefix have it stored in the high byte (e.g. `0xE01C` for the numpad enter key).
+pub type ScanCode = u32;
+
+macro_rules! physical_keys {
+    ($($name:ident = $code:expr,)*) => {
+        /// The physical location of a key on the keyboard, independent of the active layout.
+        ///
+        /// Variants are named after the W3C `KeyboardEvent.code` values, so `KeyW` refers to the
+        /// key that is labeled "W" on a US keyboard, even if the layout produces "Z" for it.
+        #[non_exhaustive]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        pub enum PhysicalKey {
+            $($name,)*
+            Unidentified,
+        }
+
+        impl PhysicalKey {
+            /// Every identified key, for tests that cover the whole table.
+            #[cfg(test)]
+            const ALL: &'static [Self] = &[$(Self::$name,)*];
+
+            pub const fn from_scan_code(scan_code: ScanCode) -> Self {
+                match scan_code {
+                    $($code => Self::$name,)*
+                    _ => Self::Unidentified,
+                }
+            }
+
+            pub const fn scan_code(&self) -> Option<ScanCode> {
+                match self {
+                    $(Self::$name => Some($code),)*
+                    Self::Unidentified => None,
+                }
+            }
+
+            /// Returns the W3C `KeyboardEvent.code` name of the key.
+            pub const fn code(&self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name),)*
+                    Self::Unidentified => "Unidentified",
+                }
+            }
+
+            /// Looks up a key by its W3C `KeyboardEvent.code` name.
+            pub fn from_code(code: &str) -> Option<Self> {
+                match code {
+                    $(stringify!($name) => Some(Self::$name),)*
+                    "Unidentified" => Some(Self::Unidentified),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+physical_keys! {
+    Escape = 0x0001,
+    Digit1 = 0x0002,
+    Digit2 = 0x0003,
+    Digit3 = 0x0004,
+    Digit4 = 0x0005,
+    Digit5 = 0x0006,
+    Digit6 = 0x0007,
+    Digit7 = 0x0008,
+    Digit8 = 0x0009,
+    Digit9 = 0x000A,
+    Digit0 = 0x000B,
+    Minus = 0x000C,
+    Equal = 0x000D,
+    Backspace = 0x000E,
+    Tab = 0x000F,
+    KeyQ = 0x0010,
+    KeyW = 0x0011,
+    KeyE = 0x0012,
+    KeyR = 0x0013,
+    KeyT = 0x0014,
+    KeyY = 0x0015,
+    KeyU = 0x0016,
+    KeyI = 0x0017,
+    KeyO = 0x0018,
+    KeyP = 0x0019,
+    BracketLeft = 0x001A,
+    BracketRight = 0x001B,
+    Enter = 0x001C,
+    ControlLeft = 0x001D,
+    KeyA = 0x001E,
+    KeyS = 0x001F,
+    KeyD = 0x0020,
+    KeyF = 0x0021,
+    KeyG = 0x0022,
+    KeyH = 0x0023,
+    KeyJ = 0x0024,
+    KeyK = 0x0025,
+    KeyL = 0x0026,
+    Semicolon = 0x0027,
+    Quote = 0x0028,
+    Backquote = 0x0029,
+    ShiftLeft = 0x002A,
+    Backslash = 0x002B,
+    KeyZ = 0x002C,
+    KeyX = 0x002D,
+    KeyC = 0x002E,
+    KeyV = 0x002F,
+    KeyB = 0x0030,
+    KeyN = 0x0031,
+    KeyM = 0x0032,
+    Comma = 0x0033,
+    Period = 0x0034,
+    Slash = 0x0035,
+    ShiftRight = 0x0036,
+    NumpadMultiply = 0x0037,
+    AltLeft = 0x0038,
+    Space = 0x0039,
+    CapsLock = 0x003A,
+    F1 = 0x003B,
+    F2 = 0x003C,
+    F3 = 0x003D,
+    F4 = 0x003E,
+    F5 = 0x003F,
+    F6 = 0x0040,
+    F7 = 0x0041,
+    F8 = 0x0042,
+    F9 = 0x0043,
+    F10 = 0x0044,
+    Pause = 0x0045,
+    ScrollLock = 0x0046,
+    Numpad7 = 0x0047,
+    Numpad8 = 0x0048,
+    Numpad9 = 0x0049,
+    NumpadSubtract = 0x004A,
+    Numpad4 = 0x004B,
+    Numpad5 = 0x004C,
+    Numpad6 = 0x004D,
+    NumpadAdd = 0x004E,
+    Numpad1 = 0x004F,
+    Numpad2 = 0x0050,
+    Numpad3 = 0x0051,
+    Numpad0 = 0x0052,
+    NumpadDecimal = 0x0053,
+    IntlBackslash = 0x0056,
+    F11 = 0x0057,
+    F12 = 0x0058,
+    NumpadEqual = 0x0059,
+    F13 = 0x0064,
+    F14 = 0x0065,
+    F15 = 0x0066,
+    F16 = 0x0067,
+    F17 = 0x0068,
+    F18 = 0x0069,
+    F19 = 0x006A,
+    F20 = 0x006B,
+    F21 = 0x006C,
+    F22 = 0x006D,
+    F23 = 0x006E,
+    KanaMode = 0x0070,
+    Lang2 = 0x0071,
+    Lang1 = 0x0072,
+    IntlRo = 0x0073,
+    F24 = 0x0076,
+    Convert = 0x0079,
+    NonConvert = 0x007B,
+    IntlYen = 0x007D,
+    NumpadComma = 0x007E,
+
+    MediaTrackPrevious = 0xE010,
+    MediaTrackNext = 0xE019,
+    NumpadEnter = 0xE01C,
+    ControlRight = 0xE01D,
+    AudioVolumeMute = 0xE020,
+    LaunchApp2 = 0xE021,
+    MediaPlayPause = 0xE022,
+    MediaStop = 0xE024,
+    AudioVolumeDown = 0xE02E,
+    AudioVolumeUp = 0xE030,
+    BrowserHome = 0xE032,
+    NumpadDivide = 0xE035,
+    PrintScreen = 0xE037,
+    AltRight = 0xE038,
+    NumLock = 0xE045,
+    Home = 0xE047,
+    ArrowUp = 0xE048,
+    PageUp = 0xE049,
+    ArrowLeft = 0xE04B,
+    ArrowRight = 0xE04D,
+    End = 0xE04F,
+    ArrowDown = 0xE050,
+    PageDown = 0xE051,
+    Insert = 0xE052,
+    Delete = 0xE053,
+    MetaLeft = 0xE05B,
+    MetaRight = 0xE05C,
+    ContextMenu = 0xE05D,
+    Power = 0xE05E,
+    Sleep = 0xE05F,
+    WakeUp = 0xE063,
+    BrowserSearch = 0xE065,
+    BrowserFavorites = 0xE066,
+    BrowserRefresh = 0xE067,
+    BrowserStop = 0xE068,
+    BrowserForward = 0xE069,
+    BrowserBack = 0xE06A,
+    LaunchApp1 = 0xE06B,
+    LaunchMail = 0xE06C,
+    MediaSelect = 0xE06D,
+}
+
+/// Physical keys are serialized by their code, e.g. `"KeyW"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for PhysicalKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PhysicalKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Self::from_code(&code).ok_or_else(|| {
+            serde::de::Error::custom(format!("unknown physical key code \"{}\"", code))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_code_round_trip() {
+        for &key in PhysicalKey::ALL {
+            let scan_code = key.scan_code().unwrap();
+            assert_eq!(
+                PhysicalKey::from_scan_code(scan_code),
+                key,
+                "{:#06X}",
+                scan_code
+            );
+        }
+    }
+
+    #[test]
+    fn code_round_trip() {
+        for &key in PhysicalKey::ALL {
+            assert_eq!(PhysicalKey::from_code(key.code()), Some(key));
+        }
+        assert_eq!(
+            PhysicalKey::from_code("Unidentified"),
+            Some(PhysicalKey::Unidentified)
+        );
+        assert_eq!(PhysicalKey::from_code("keyw"), None);
+    }
+
+    #[test]
+    fn pause_and_num_lock() {
+        // Pause is sent as E1 1D 45, which Win32 reports without a prefix, while Num Lock is
+        // reported with the extended flag.
+        assert_eq!(PhysicalKey::from_scan_code(0x0045), PhysicalKey::Pause);
+        assert_eq!(PhysicalKey::from_scan_code(0xE045), PhysicalKey::NumLock);
+        assert_eq!(PhysicalKey::Pause.scan_code(), Some(0x0045));
+        assert_eq!(PhysicalKey::NumLock.scan_code(), Some(0xE045));
+    }
+
+    #[test]
+    fn print_screen_and_numpad_multiply() {
+        assert_eq!(
+            PhysicalKey::from_scan_code(0xE037),
+            PhysicalKey::PrintScreen
+        );
+        assert_eq!(
+            PhysicalKey::from_scan_code(0x0037),
+            PhysicalKey::NumpadMultiply
+        );
+        assert_eq!(
+            PhysicalKey::from_code("PrintScreen"),
+            Some(PhysicalKey::PrintScreen)
+        );
+    }
+
+    #[test]
+    fn unidentified() {
+        assert_eq!(PhysicalKey::from_scan_code(0), PhysicalKey::Unidentified);
+        assert_eq!(
+            PhysicalKey::from_scan_code(0xE0FF),
+            PhysicalKey::Unidentified
+        );
+        assert_eq!(PhysicalKey::Unidentified.scan_code(), None);
+        assert_eq!(PhysicalKey::Unidentified.code(), "Unidentified");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serde_by_code() {
+        let json = serde_json::to_string(&PhysicalKey::KeyW).unwrap();
+        assert_eq!(json, r#""KeyW""#);
+        assert_eq!(
+            serde_json::from_str::<PhysicalKey>(&json).unwrap(),
+            PhysicalKey::KeyW
+        );
+        assert!(serde_json::from_str::<PhysicalKey>(r#""keyw""#).is_err());
+    }
+}
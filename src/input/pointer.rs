@@ -0,0 +1,172 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::*;
+
+use bitflags::bitflags;
+
+/// The kind of device behind a pointer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerType {
+    Mouse,
+    Pen,
+    Touch,
+}
+
+/// What happened to a pointer. A mouse is `Down` when its first button is pressed and `Up` when
+/// its last button is released; pressing or releasing other buttons in between is a `Move`.
+/// A cancelled pointer should not trigger the action it would have when lifted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PointerPhase {
+    Down,
+    Move,
+    Up,
+    Cancel,
+}
+
+bitflags! {
+    /// The buttons held on a pointer, with the same values as the `buttons` of W3C pointer
+    /// events. Pen and touch contact with the surface count as the `PRIMARY` button and the pen
+    /// barrel button as `SECONDARY`.
+    #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct PointerButtons: u32 {
+        const PRIMARY = 1 << 0;
+        const SECONDARY = 1 << 1;
+        const MIDDLE = 1 << 2;
+        const X1 = 1 << 3;
+        const X2 = 1 << 4;
+        /// The pen is turned around to use its eraser, whether or not it touches the surface.
+        const ERASER = 1 << 5;
+    }
+}
+
+/// The pointer id reported for the mouse.
+pub const MOUSE_POINTER_ID: u64 = 1;
+
+/// Input from a mouse, pen or touch, modelled after W3C pointer events. Pointer events are
+/// delivered alongside the mouse and touch events, so a window only needs to handle one of them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointerEvent {
+    id: u64,
+    pointer_type: PointerType,
+    phase: PointerPhase,
+    x: f32,
+    y: f32,
+    pressure: f32,
+    tilt_x: f32,
+    tilt_y: f32,
+    twist: f32,
+    buttons: PointerButtons,
+    primary: bool,
+    modifiers: Modifiers,
+    timestamp: Timestamp,
+}
+
+impl PointerEvent {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) const fn new(
+        id: u64,
+        pointer_type: PointerType,
+        phase: PointerPhase,
+        x: f32,
+        y: f32,
+        pressure: f32,
+        (tilt_x, tilt_y): (f32, f32),
+        twist: f32,
+        buttons: PointerButtons,
+        primary: bool,
+        modifiers: Modifiers,
+        timestamp: Timestamp,
+    ) -> Self {
+        Self {
+            id,
+            pointer_type,
+            phase,
+            x,
+            y,
+            pressure,
+            tilt_x,
+            tilt_y,
+            twist,
+            buttons,
+            primary,
+            modifiers,
+            timestamp,
+        }
+    }
+
+    /// Returns the id of the pointer, which stays the same until the pointer goes `Up` or is
+    /// cancelled. The mouse always has the id `MOUSE_POINTER_ID`.
+    pub const fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub const fn pointer_type(&self) -> PointerType {
+        self.pointer_type
+    }
+
+    pub const fn phase(&self) -> PointerPhase {
+        self.phase
+    }
+
+    pub const fn pos(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the pressure from 0 to 1. Devices that don't report pressure give 0.5 while a
+    /// button is held and 0 otherwise.
+    pub const fn pressure(&self) -> f32 {
+        self.pressure
+    }
+
+    /// Returns the angles in degrees, from -90 to 90, between the pen and the surface normal
+    /// along the x and y axes. Positive angles tilt towards the right and the bottom.
+    pub const fn tilt(&self) -> (f32, f32) {
+        (self.tilt_x, self.tilt_y)
+    }
+
+    /// Returns the clockwise rotation of the pen around its own axis in degrees, from 0 to 359.
+    pub const fn twist(&self) -> f32 {
+        self.twist
+    }
+
+    pub const fn buttons(&self) -> PointerButtons {
+        self.buttons
+    }
+
+    /// Returns whether this is the primary pointer of its type, such as the first finger to
+    /// touch the screen. The mouse is always primary.
+    pub const fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    /// Returns whether the pen is turned around to use its eraser.
+    pub const fn is_eraser(&self) -> bool {
+        self.buttons.contains(PointerButtons::ERASER)
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+}
@@ -0,0 +1,478 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::*;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The modifiers that take part in shortcuts. Lock keys are ignored.
+const SHORTCUT_MODIFIERS: [Modifiers; 4] = [
+    Modifiers::CONTROL,
+    Modifiers::ALT,
+    Modifiers::SHIFT,
+    Modifiers::SUPER,
+];
+
+/// A keyboard shortcut, such as `Ctrl+Shift+S`: a key pressed while a set of modifiers is held.
+///
+/// Shortcuts don't distinguish between the left and right modifier keys, so `Ctrl` is satisfied
+/// by either control key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Shortcut {
+    modifiers: Modifiers,
+    key: VirtualKey,
+}
+
+impl Shortcut {
+    /// Creates a shortcut. A modifier given for one side applies to both.
+    pub fn new(modifiers: Modifiers, key: VirtualKey) -> Self {
+        Self {
+            modifiers: shortcut_modifiers(modifiers),
+            key,
+        }
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub const fn key(&self) -> VirtualKey {
+        self.key
+    }
+
+    /// Returns whether a key press or repeat triggers the shortcut while `modifiers` are held,
+    /// usually `event.modifiers()`. The held modifiers must match exactly, apart from lock keys.
+    pub fn matches(&self, event: &KeyEvent, modifiers: Modifiers) -> bool {
+        if event.state() == KeyState::Release || !key_matches(self.key, event.key()) {
+            return false;
+        }
+
+        SHORTCUT_MODIFIERS
+            .iter()
+            .all(|&group| self.modifiers.intersects(group) == modifiers.intersects(group))
+    }
+}
+
+/// Reduces modifiers to the ones that take part in shortcuts, with a modifier held on one side
+/// applying to both.
+pub(crate) fn shortcut_modifiers(modifiers: Modifiers) -> Modifiers {
+    let mut both = Modifiers::empty();
+    for group in SHORTCUT_MODIFIERS {
+        if modifiers.intersects(group) {
+            both |= group;
+        }
+    }
+    both
+}
+
+/// Returns whether a reported key satisfies a shortcut key, treating the generic modifier keys
+/// as either of their sides.
+fn key_matches(expected: VirtualKey, actual: VirtualKey) -> bool {
+    match expected {
+        VirtualKey::Shift => matches!(
+            actual,
+            VirtualKey::Shift | VirtualKey::LeftShift | VirtualKey::RightShift
+        ),
+        VirtualKey::Control => matches!(
+            actual,
+            VirtualKey::Control | VirtualKey::LeftControl | VirtualKey::RightControl
+        ),
+        VirtualKey::Alt => matches!(
+            actual,
+            VirtualKey::Alt | VirtualKey::LeftAlt | VirtualKey::RightAlt
+        ),
+        _ => expected == actual,
+    }
+}
+
+/// The conventions for showing shortcuts, which differ between platforms.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LabelStyle {
+    /// Symbols without separators, e.g. `⇧⌘S`.
+    Mac,
+    /// Names joined by `+`, with the super key labeled `Win`.
+    Windows,
+    /// Names joined by `+`, with the super key labeled `Super`.
+    Other,
+}
+
+impl LabelStyle {
+    const NATIVE: Self = if cfg!(target_os = "macos") {
+        Self::Mac
+    } else if cfg!(target_os = "windows") {
+        Self::Windows
+    } else {
+        Self::Other
+    };
+}
+
+/// Returns the label of a modifier in shortcuts, in the given style.
+const fn modifier_label(modifier: Modifiers, style: LabelStyle) -> &'static str {
+    match style {
+        LabelStyle::Mac => match modifier {
+            Modifiers::CONTROL => "⌃",
+            Modifiers::ALT => "⌥",
+            Modifiers::SHIFT => "⇧",
+            _ => "⌘",
+        },
+        _ => match modifier {
+            Modifiers::CONTROL => "Ctrl",
+            Modifiers::ALT => "Alt",
+            Modifiers::SHIFT => "Shift",
+            _ if matches!(style, LabelStyle::Windows) => "Win",
+            _ => "Super",
+        },
+    }
+}
+
+fn parse_modifier(name: &str) -> Option<Modifiers> {
+    let modifier = match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" | "⌃" => Modifiers::CONTROL,
+        "alt" | "option" | "opt" | "⌥" => Modifiers::ALT,
+        "shift" | "⇧" => Modifiers::SHIFT,
+        "super" | "win" | "windows" | "cmd" | "command" | "meta" | "⌘" => Modifiers::SUPER,
+        _ => return None,
+    };
+    Some(modifier)
+}
+
+/// Parses a key name in a shortcut. Besides the canonical `VirtualKey` names, digits,
+/// lowercase letters, punctuation and a few common abbreviations are accepted.
+fn parse_key(name: &str) -> Option<VirtualKey> {
+    if let Ok(key) = VirtualKey::from_str(name) {
+        return Some(key);
+    }
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let key = match c.to_ascii_uppercase() {
+            'A'..='Z' => VirtualKey::from_name(&c.to_ascii_uppercase().to_string())?,
+            '0'..='9' => VirtualKey::from_name(&format!("Num{}", c))?,
+            '+' | '=' => VirtualKey::Plus,
+            '-' => VirtualKey::Minus,
+            ',' => VirtualKey::Comma,
+            '.' => VirtualKey::Period,
+            '/' => VirtualKey::Slash,
+            ';' => VirtualKey::Colon,
+            '\'' => VirtualKey::Quote,
+            '`' => VirtualKey::Tilde,
+            '[' => VirtualKey::LeftBracket,
+            ']' => VirtualKey::RightBracket,
+            '\\' => VirtualKey::Backslash,
+            _ => return None,
+        };
+        return Some(key);
+    }
+
+    let key = match name.to_ascii_lowercase().as_str() {
+        "esc" => VirtualKey::Escape,
+        "return" => VirtualKey::Enter,
+        "del" => VirtualKey::Delete,
+        "ins" => VirtualKey::Insert,
+        "pgup" => VirtualKey::PageUp,
+        "pgdn" => VirtualKey::PageDown,
+        "space" => VirtualKey::Space,
+        "backspace" => VirtualKey::Backspace,
+        "tab" => VirtualKey::Tab,
+        "enter" => VirtualKey::Enter,
+        "escape" => VirtualKey::Escape,
+        "delete" => VirtualKey::Delete,
+        "home" => VirtualKey::Home,
+        "end" => VirtualKey::End,
+        "left" => VirtualKey::Left,
+        "right" => VirtualKey::Right,
+        "up" => VirtualKey::Up,
+        "down" => VirtualKey::Down,
+        _ => {
+            // Function keys are commonly written in lowercase, e.g. "alt+f4".
+            let number = name.strip_prefix(['f', 'F'])?;
+            VirtualKey::from_name(&format!("F{}", number))?
+        }
+    };
+    Some(key)
+}
+
+/// Writes the modifiers of a shortcut followed by their separator, e.g. `Ctrl+Shift+`.
+pub(crate) fn write_modifiers(f: &mut fmt::Formatter<'_>, modifiers: Modifiers) -> fmt::Result {
+    write_modifiers_in(f, modifiers, LabelStyle::NATIVE)
+}
+
+fn write_modifiers_in(
+    f: &mut fmt::Formatter<'_>,
+    modifiers: Modifiers,
+    style: LabelStyle,
+) -> fmt::Result {
+    let separator = if style == LabelStyle::Mac { "" } else { "+" };
+
+    for group in SHORTCUT_MODIFIERS {
+        if modifiers.intersects(group) {
+            write!(f, "{}{}", modifier_label(group, style), separator)?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits a shortcut string into its modifiers and the unparsed key name.
+pub(crate) fn split_shortcut(s: &str) -> Result<(Modifiers, &str), ParseShortcutError> {
+    let mut rest = s.trim();
+    let mut modifiers = Modifiers::empty();
+
+    // macOS style symbols are written without separators.
+    while let Some(c) = rest.chars().next() {
+        match parse_modifier(&c.to_string()).filter(|_| !c.is_ascii()) {
+            Some(modifier) => {
+                modifiers |= modifier;
+                rest = rest[c.len_utf8()..].trim_start_matches('+');
+            }
+            None => break,
+        }
+    }
+
+    let (names, key) = match rest.strip_suffix("++") {
+        Some(names) => (names, "+"),
+        None if rest == "+" => ("", "+"),
+        None => match rest.rsplit_once('+') {
+            Some((names, key)) => (names, key),
+            None => ("", rest),
+        },
+    };
+
+    for name in names
+        .split('+')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        modifiers |= parse_modifier(name)
+            .ok_or_else(|| ParseShortcutError::UnknownModifier(name.to_string()))?;
+    }
+
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(ParseShortcutError::MissingKey);
+    }
+    Ok((modifiers, key))
+}
+
+/// Formats shortcuts the way the platform shows them in menus, e.g. `Ctrl+Shift+S` on Windows
+/// and Linux and `⇧⌘S` on macOS. The output can be parsed back with `FromStr`.
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_in(f, LabelStyle::NATIVE)
+    }
+}
+
+impl Shortcut {
+    fn fmt_in(&self, f: &mut fmt::Formatter<'_>, style: LabelStyle) -> fmt::Result {
+        write_modifiers_in(f, self.modifiers, style)?;
+
+        match self.key.name().and_then(|name| name.strip_prefix("Num")) {
+            Some(digit) if digit.len() == 1 => f.write_str(digit),
+            _ => write!(f, "{}", self.key),
+        }
+    }
+}
+
+/// Parses shortcuts such as `"Ctrl+Shift+S"`, `"alt+f4"`, `"Ctrl++"` or `"⇧⌘S"`. Modifier names
+/// are case-insensitive and may appear in any order.
+impl FromStr for Shortcut {
+    type Err = ParseShortcutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (modifiers, key) = split_shortcut(s)?;
+        let key = parse_key(key).ok_or_else(|| ParseShortcutError::UnknownKey(key.to_string()))?;
+        Ok(Self::new(modifiers, key))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseShortcutError {
+    MissingKey,
+    UnknownModifier(String),
+    UnknownKey(String),
+}
+
+impl fmt::Display for ParseShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKey => write!(f, "shortcut has no key"),
+            Self::UnknownModifier(name) => write!(f, "unknown modifier \"{}\"", name),
+            Self::UnknownKey(name) => write!(f, "unknown key name \"{}\"", name),
+        }
+    }
+}
+
+impl std::error::Error for ParseShortcutError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    /// Formats a shortcut in a style other than the native one.
+    struct Styled(Shortcut, LabelStyle);
+
+    impl fmt::Display for Styled {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt_in(f, self.1)
+        }
+    }
+
+    fn shortcut(s: &str) -> Shortcut {
+        s.parse().unwrap()
+    }
+
+    fn key_press(key: VirtualKey, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent::new(
+            key,
+            KeyLocation::Standard,
+            0,
+            KeyState::Press,
+            modifiers,
+            None,
+            Timestamp::from_duration(Duration::ZERO),
+        )
+    }
+
+    #[test]
+    fn parse() {
+        let save = Shortcut::new(Modifiers::CONTROL | Modifiers::SHIFT, VirtualKey::S);
+        assert_eq!(shortcut("Ctrl+Shift+S"), save);
+        assert_eq!(shortcut("Shift+Ctrl+S"), save);
+        assert_eq!(shortcut(" control + shift + s "), save);
+        assert_eq!(shortcut("CTRL+SHIFT+s"), save);
+        assert_eq!(shortcut("⇧⌃S"), save);
+
+        assert_eq!(
+            shortcut("alt+f4"),
+            Shortcut::new(Modifiers::ALT, VirtualKey::F4)
+        );
+        assert_eq!(
+            shortcut("Ctrl++"),
+            Shortcut::new(Modifiers::CONTROL, VirtualKey::Plus)
+        );
+        assert_eq!(
+            shortcut("+"),
+            Shortcut::new(Modifiers::empty(), VirtualKey::Plus)
+        );
+        assert_eq!(
+            shortcut("Cmd+1"),
+            Shortcut::new(Modifiers::SUPER, VirtualKey::Num1)
+        );
+        assert_eq!(
+            shortcut("⇧⌘PgDn"),
+            Shortcut::new(Modifiers::SHIFT | Modifiers::SUPER, VirtualKey::PageDown)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Shortcut::from_str(""), Err(ParseShortcutError::MissingKey));
+        assert_eq!(
+            Shortcut::from_str("Ctrl+"),
+            Err(ParseShortcutError::MissingKey)
+        );
+        assert_eq!(
+            Shortcut::from_str("Hyper+S"),
+            Err(ParseShortcutError::UnknownModifier("Hyper".to_string()))
+        );
+        assert_eq!(
+            Shortcut::from_str("Ctrl+Banana"),
+            Err(ParseShortcutError::UnknownKey("Banana".to_string()))
+        );
+        assert_eq!(
+            Shortcut::from_str("Ctrl+F99"),
+            Err(ParseShortcutError::UnknownKey("F99".to_string()))
+        );
+    }
+
+    #[test]
+    fn one_sided_modifiers() {
+        let shortcut = Shortcut::new(
+            Modifiers::LEFT_CONTROL | Modifiers::RIGHT_ALT,
+            VirtualKey::A,
+        );
+        assert_eq!(shortcut.modifiers(), Modifiers::CONTROL | Modifiers::ALT);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for s in [
+            "Ctrl+Shift+S",
+            "Alt+F4",
+            "Ctrl+Plus",
+            "Shift+1",
+            "Ctrl+Alt+Shift+Delete",
+        ] {
+            let parsed = shortcut(s);
+            assert_eq!(Styled(parsed, LabelStyle::Other).to_string(), s);
+            for style in [LabelStyle::Mac, LabelStyle::Windows, LabelStyle::Other] {
+                let formatted = Styled(parsed, style).to_string();
+                assert_eq!(shortcut(&formatted), parsed, "{}", formatted);
+            }
+            assert_eq!(shortcut(&parsed.to_string()), parsed);
+        }
+    }
+
+    #[test]
+    fn labels() {
+        let shortcut = shortcut("Ctrl+Alt+Shift+Super+S");
+        assert_eq!(Styled(shortcut, LabelStyle::Mac).to_string(), "⌃⌥⇧⌘S");
+        assert_eq!(
+            Styled(shortcut, LabelStyle::Windows).to_string(),
+            "Ctrl+Alt+Shift+Win+S"
+        );
+        assert_eq!(
+            Styled(shortcut, LabelStyle::Other).to_string(),
+            "Ctrl+Alt+Shift+Super+S"
+        );
+        assert_eq!(
+            Styled(
+                Shortcut::new(Modifiers::SHIFT | Modifiers::SUPER, VirtualKey::Num1),
+                LabelStyle::Mac
+            )
+            .to_string(),
+            "⇧⌘1"
+        );
+    }
+
+    #[test]
+    fn matches() {
+        let save = shortcut("Ctrl+S");
+        let modifiers = Modifiers::RIGHT_CONTROL | Modifiers::NUM_LOCK;
+        assert!(save.matches(&key_press(VirtualKey::S, modifiers), modifiers));
+
+        let modifiers = Modifiers::LEFT_CONTROL | Modifiers::LEFT_SHIFT;
+        assert!(!save.matches(&key_press(VirtualKey::S, modifiers), modifiers));
+        assert!(!save.matches(
+            &key_press(VirtualKey::S, Modifiers::empty()),
+            Modifiers::empty()
+        ));
+
+        let release = KeyEvent::new(
+            VirtualKey::S,
+            KeyLocation::Standard,
+            0,
+            KeyState::Release,
+            Modifiers::LEFT_CONTROL,
+            None,
+            Timestamp::from_duration(Duration::ZERO),
+        );
+        assert!(!save.matches(&release, Modifiers::LEFT_CONTROL));
+    }
+}
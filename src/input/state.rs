@@ -0,0 +1,391 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::*;
+
+use std::collections::HashSet;
+
+/// Input state for polling-style queries, e.g. once per frame in a game loop. The state is kept
+/// up to date by the window it is attached to with `Window::attach_input_state`, or by feeding
+/// it events from a `WindowController`.
+///
+/// The just pressed and just released sets, the scroll deltas and the mouse delta accumulate
+/// until `end_frame` is called.
+#[derive(Debug, Default, Clone)]
+pub struct InputState {
+    keys_down: HashSet<VirtualKey>,
+    keys_pressed: HashSet<VirtualKey>,
+    keys_released: HashSet<VirtualKey>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    modifiers: Modifiers,
+    mouse_pos: Option<(f32, f32)>,
+    mouse_delta: (f32, f32),
+    scroll_lines: (f32, f32),
+    scroll_pixels: (f32, f32),
+    scroll_pages: (f32, f32),
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the key is held. The generic `Shift`, `Control` and `Alt` keys are held
+    /// while either of their sides is.
+    pub fn is_key_down(&self, key: VirtualKey) -> bool {
+        Self::contains_key(&self.keys_down, key)
+    }
+
+    /// Returns whether the key was pressed since the last frame. Key repeats don't count.
+    pub fn is_key_pressed(&self, key: VirtualKey) -> bool {
+        Self::contains_key(&self.keys_pressed, key)
+    }
+
+    /// Returns whether the key was released since the last frame.
+    pub fn is_key_released(&self, key: VirtualKey) -> bool {
+        Self::contains_key(&self.keys_released, key)
+    }
+
+    pub fn keys_down(&self) -> impl Iterator<Item = VirtualKey> + '_ {
+        self.keys_down.iter().copied()
+    }
+
+    pub fn keys_pressed(&self) -> impl Iterator<Item = VirtualKey> + '_ {
+        self.keys_pressed.iter().copied()
+    }
+
+    pub fn keys_released(&self) -> impl Iterator<Item = VirtualKey> + '_ {
+        self.keys_released.iter().copied()
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// Returns whether the button was pressed since the last frame.
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Returns whether the button was released since the last frame.
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    pub fn buttons_down(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.buttons_down.iter().copied()
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the last known cursor position in client coordinates, or `None` if the cursor
+    /// hasn't entered the window yet.
+    pub const fn mouse_position(&self) -> Option<(f32, f32)> {
+        self.mouse_pos
+    }
+
+    /// Returns how far the cursor moved since the last frame.
+    pub const fn mouse_delta(&self) -> (f32, f32) {
+        self.mouse_delta
+    }
+
+    /// Returns how many columns and lines mouse wheels scrolled since the last frame, with the
+    /// sign convention of `ScrollDelta`.
+    pub const fn scroll_lines(&self) -> (f32, f32) {
+        self.scroll_lines
+    }
+
+    /// Returns how many pixels touchpads, touchscreens and high resolution wheels scrolled since
+    /// the last frame, with the sign convention of `ScrollDelta`.
+    pub const fn scroll_pixels(&self) -> (f32, f32) {
+        self.scroll_pixels
+    }
+
+    /// Returns how many pages mouse wheels scrolled since the last frame, when the user chose to
+    /// scroll a page per notch, with the sign convention of `ScrollDelta`.
+    pub const fn scroll_pages(&self) -> (f32, f32) {
+        self.scroll_pages
+    }
+
+    /// Clears the per-frame state: the just pressed and just released sets and the mouse and
+    /// scroll deltas. Held keys and buttons are kept.
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll_lines = (0.0, 0.0);
+        self.scroll_pixels = (0.0, 0.0);
+        self.scroll_pages = (0.0, 0.0);
+    }
+
+    /// Releases all held keys and buttons, reporting them as just released.
+    pub fn release_all(&mut self) {
+        self.keys_released.extend(self.keys_down.drain());
+        self.buttons_released.extend(self.buttons_down.drain());
+        self.modifiers &= Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK;
+    }
+
+    pub fn handle_key(&mut self, event: &KeyEvent) {
+        let key = event.key();
+        match event.state() {
+            KeyState::Press => {
+                if self.keys_down.insert(key) {
+                    self.keys_pressed.insert(key);
+                }
+            }
+            KeyState::Repeat => {
+                self.keys_down.insert(key);
+            }
+            KeyState::Release => {
+                if self.keys_down.remove(&key) {
+                    self.keys_released.insert(key);
+                }
+            }
+        }
+        self.modifiers = event.modifiers();
+    }
+
+    pub fn handle_mouse_move(&mut self, event: &MouseMoveEvent) {
+        let (x, y) = event.pos();
+        if let Some((last_x, last_y)) = self.mouse_pos {
+            self.mouse_delta.0 += x - last_x;
+            self.mouse_delta.1 += y - last_y;
+        }
+        self.mouse_pos = Some((x, y));
+        self.modifiers = event.modifiers();
+    }
+
+    pub fn handle_mouse_button(&mut self, event: &MouseButtonEvent) {
+        let button = event.button();
+        match event.state() {
+            ButtonState::Press => {
+                if self.buttons_down.insert(button) {
+                    self.buttons_pressed.insert(button);
+                }
+            }
+            ButtonState::Release => {
+                if self.buttons_down.remove(&button) {
+                    self.buttons_released.insert(button);
+                }
+            }
+        }
+        self.modifiers = event.modifiers();
+    }
+
+    pub fn handle_mouse_wheel(&mut self, event: &MouseWheelEvent) {
+        let (total, (x, y)) = match event.delta() {
+            ScrollDelta::Lines(x, y) => (&mut self.scroll_lines, (x, y)),
+            ScrollDelta::Pixels(x, y) => (&mut self.scroll_pixels, (x, y)),
+            ScrollDelta::Pages(x, y) => (&mut self.scroll_pages, (x, y)),
+        };
+        total.0 += x;
+        total.1 += y;
+        self.modifiers = event.modifiers();
+    }
+
+    pub fn handle_modifiers_changed(&mut self, event: &ModifiersChangedEvent) {
+        self.modifiers = event.modifiers();
+    }
+
+    /// Releases all keys and buttons when the window loses focus, since their release would be
+    /// delivered to another window.
+    pub fn handle_focus(&mut self, event: &FocusChangedEvent) {
+        if !event.focus() {
+            self.release_all();
+        }
+    }
+
+    fn contains_key(keys: &HashSet<VirtualKey>, key: VirtualKey) -> bool {
+        let sides = match key {
+            VirtualKey::Shift => [VirtualKey::LeftShift, VirtualKey::RightShift],
+            VirtualKey::Control => [VirtualKey::LeftControl, VirtualKey::RightControl],
+            VirtualKey::Alt => [VirtualKey::LeftAlt, VirtualKey::RightAlt],
+            _ => return keys.contains(&key),
+        };
+        keys.contains(&key) || sides.iter().any(|side| keys.contains(side))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: VirtualKey, state: KeyState, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent::new(
+            key,
+            KeyLocation::Standard,
+            0,
+            state,
+            modifiers,
+            None,
+            Timestamp::default(),
+        )
+    }
+
+    fn mouse_move(x: f32, y: f32) -> MouseMoveEvent {
+        MouseMoveEvent::new(x, y, Modifiers::empty(), Timestamp::default())
+    }
+
+    fn wheel(delta: ScrollDelta) -> MouseWheelEvent {
+        MouseWheelEvent::new(
+            delta,
+            GesturePhase::Changed,
+            0.0,
+            0.0,
+            Modifiers::empty(),
+            Timestamp::default(),
+        )
+    }
+
+    fn focus(focused: bool) -> FocusChangedEvent {
+        FocusChangedEvent::new(focused, Timestamp::default())
+    }
+
+    #[test]
+    fn press_repeat_release() {
+        let mut state = InputState::new();
+        state.handle_key(&key(VirtualKey::A, KeyState::Press, Modifiers::empty()));
+        assert!(state.is_key_down(VirtualKey::A));
+        assert!(state.is_key_pressed(VirtualKey::A));
+
+        state.end_frame();
+        state.handle_key(&key(VirtualKey::A, KeyState::Repeat, Modifiers::empty()));
+        assert!(state.is_key_down(VirtualKey::A));
+        assert!(!state.is_key_pressed(VirtualKey::A));
+
+        state.end_frame();
+        state.handle_key(&key(VirtualKey::A, KeyState::Release, Modifiers::empty()));
+        assert!(!state.is_key_down(VirtualKey::A));
+        assert!(state.is_key_released(VirtualKey::A));
+
+        // A release without a press isn't reported.
+        state.end_frame();
+        state.handle_key(&key(VirtualKey::B, KeyState::Release, Modifiers::empty()));
+        assert!(!state.is_key_released(VirtualKey::B));
+    }
+
+    #[test]
+    fn generic_modifier_keys() {
+        let mut state = InputState::new();
+        let event = key(
+            VirtualKey::RightShift,
+            KeyState::Press,
+            Modifiers::RIGHT_SHIFT,
+        );
+        state.handle_key(&event);
+        assert!(state.is_key_down(VirtualKey::Shift));
+        assert!(state.is_key_pressed(VirtualKey::Shift));
+        assert!(!state.is_key_down(VirtualKey::LeftShift));
+        assert_eq!(state.modifiers(), Modifiers::RIGHT_SHIFT);
+    }
+
+    #[test]
+    fn focus_loss_releases_everything() {
+        let mut state = InputState::new();
+        let modifiers = Modifiers::LEFT_ALT | Modifiers::CAPS_LOCK;
+        state.handle_key(&key(VirtualKey::LeftAlt, KeyState::Press, modifiers));
+        state.handle_mouse_button(&MouseButtonEvent::new(
+            MouseButton::Left,
+            ButtonState::Press,
+            0.0,
+            0.0,
+            modifiers,
+            Timestamp::default(),
+        ));
+        state.end_frame();
+
+        state.handle_focus(&focus(false));
+        assert!(!state.is_key_down(VirtualKey::LeftAlt));
+        assert!(state.is_key_released(VirtualKey::LeftAlt));
+        assert!(!state.is_button_down(MouseButton::Left));
+        assert!(state.is_button_released(MouseButton::Left));
+        assert_eq!(state.modifiers(), Modifiers::CAPS_LOCK);
+
+        // Gaining focus doesn't change anything.
+        state.end_frame();
+        state.handle_focus(&focus(true));
+        assert_eq!(state.keys_down().count(), 0);
+        assert_eq!(state.keys_released().count(), 0);
+    }
+
+    #[test]
+    fn keys_held_across_a_focus_loss() {
+        let mut state = InputState::new();
+        state.handle_key(&key(VirtualKey::W, KeyState::Press, Modifiers::empty()));
+        state.handle_focus(&focus(false));
+        state.end_frame();
+
+        // The key is still held when the window gets focus back, so it repeats without being
+        // reported as pressed again.
+        state.handle_focus(&focus(true));
+        state.handle_key(&key(VirtualKey::W, KeyState::Repeat, Modifiers::empty()));
+        assert!(state.is_key_down(VirtualKey::W));
+        assert!(!state.is_key_pressed(VirtualKey::W));
+
+        state.handle_key(&key(VirtualKey::W, KeyState::Release, Modifiers::empty()));
+        assert!(state.is_key_released(VirtualKey::W));
+    }
+
+    #[test]
+    fn scroll_accumulation() {
+        let mut state = InputState::new();
+        state.handle_mouse_wheel(&wheel(ScrollDelta::Lines(0.0, 3.0)));
+        state.handle_mouse_wheel(&wheel(ScrollDelta::Lines(-1.0, 3.0)));
+        state.handle_mouse_wheel(&wheel(ScrollDelta::Pixels(2.5, -4.0)));
+        state.handle_mouse_wheel(&wheel(ScrollDelta::Pages(0.0, 1.0)));
+        assert_eq!(state.scroll_lines(), (-1.0, 6.0));
+        assert_eq!(state.scroll_pixels(), (2.5, -4.0));
+        assert_eq!(state.scroll_pages(), (0.0, 1.0));
+    }
+
+    #[test]
+    fn mouse_delta() {
+        let mut state = InputState::new();
+        // The first position has nothing to measure from.
+        state.handle_mouse_move(&mouse_move(10.0, 20.0));
+        assert_eq!(state.mouse_delta(), (0.0, 0.0));
+
+        state.handle_mouse_move(&mouse_move(15.0, 18.0));
+        state.handle_mouse_move(&mouse_move(16.0, 25.0));
+        assert_eq!(state.mouse_delta(), (6.0, 5.0));
+        assert_eq!(state.mouse_position(), Some((16.0, 25.0)));
+    }
+
+    #[test]
+    fn end_frame() {
+        let mut state = InputState::new();
+        state.handle_key(&key(VirtualKey::A, KeyState::Press, Modifiers::empty()));
+        state.handle_key(&key(VirtualKey::B, KeyState::Press, Modifiers::empty()));
+        state.handle_key(&key(VirtualKey::B, KeyState::Release, Modifiers::empty()));
+        state.handle_mouse_move(&mouse_move(0.0, 0.0));
+        state.handle_mouse_move(&mouse_move(1.0, 1.0));
+        state.handle_mouse_wheel(&wheel(ScrollDelta::Lines(0.0, 1.0)));
+
+        state.end_frame();
+        assert!(state.is_key_down(VirtualKey::A));
+        assert_eq!(state.keys_pressed().count(), 0);
+        assert_eq!(state.keys_released().count(), 0);
+        assert_eq!(state.mouse_delta(), (0.0, 0.0));
+        assert_eq!(state.mouse_position(), Some((1.0, 1.0)));
+        assert_eq!(state.scroll_lines(), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::platform_impl;
+
+use std::time::{Duration, Instant};
+
+/// A point in time on the monotonic clock that the platform stamps input with, such as the time
+/// since the system started on Windows. Timestamps of events from different windows can be
+/// compared with each other.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp(Duration);
+
+impl Timestamp {
+    /// Creates a timestamp from the time since the start of the platform's clock.
+    pub const fn from_duration(duration: Duration) -> Self {
+        Self(duration)
+    }
+
+    /// Returns the time since the start of the platform's clock.
+    pub const fn as_duration(&self) -> Duration {
+        self.0
+    }
+
+    /// Returns the current time on the platform's clock.
+    pub fn now() -> Self {
+        platform_impl::current_timestamp()
+    }
+
+    /// Returns the time elapsed from `earlier` to this timestamp, or zero if `earlier` is later.
+    pub fn duration_since(&self, earlier: Timestamp) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Converts the timestamp to an `Instant`, e.g. to measure input latency.
+    pub fn to_instant(&self) -> Instant {
+        let now = Instant::now();
+        let elapsed = Self::now().duration_since(*self);
+        now.checked_sub(elapsed).unwrap_or(now)
+    }
+
+    /// Extends a 32-bit millisecond time, which wraps around every 49.7 days, to a full timestamp
+    /// using the current time on the same clock. The time is taken to be the one closest to
+    /// `now`, which may be slightly later than `now` as the two are not read together.
+    #[cfg(any(target_os = "windows", test))]
+    pub(crate) fn from_wrapping_millis(millis: u32, now: Timestamp) -> Self {
+        const WRAP: u64 = 1 << 32;
+
+        let now = now.0.as_millis() as u64;
+        let mut extended = (now & !(WRAP - 1)) | millis as u64;
+        if extended > now + WRAP / 2 && extended >= WRAP {
+            extended -= WRAP;
+        } else if extended + WRAP / 2 < now {
+            extended += WRAP;
+        }
+        Self(Duration::from_millis(extended))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRAP: u64 = 1 << 32;
+
+    fn millis(millis: u64) -> Timestamp {
+        Timestamp::from_duration(Duration::from_millis(millis))
+    }
+
+    fn extend(time: u64, now: u64) -> Timestamp {
+        Timestamp::from_wrapping_millis(time as u32, millis(now))
+    }
+
+    #[test]
+    fn before_the_first_wrap() {
+        assert_eq!(extend(1000, 1500), millis(1000));
+        assert_eq!(extend(1500, 1500), millis(1500));
+    }
+
+    #[test]
+    fn just_before_a_wrap() {
+        assert_eq!(extend(WRAP - 20, WRAP - 10), millis(WRAP - 20));
+        assert_eq!(extend(2 * WRAP - 20, 2 * WRAP - 10), millis(2 * WRAP - 20));
+    }
+
+    #[test]
+    fn just_after_a_wrap() {
+        // Posted before the wrap, read after it.
+        assert_eq!(extend(WRAP - 20, WRAP + 10), millis(WRAP - 20));
+        // Posted and read after the wrap.
+        assert_eq!(extend(WRAP + 5, WRAP + 10), millis(WRAP + 5));
+        assert_eq!(extend(2 * WRAP + 5, 2 * WRAP + 10), millis(2 * WRAP + 5));
+    }
+
+    #[test]
+    fn now_earlier_than_the_message() {
+        assert_eq!(extend(1005, 1000), millis(1005));
+        assert_eq!(extend(WRAP + 1005, WRAP + 1000), millis(WRAP + 1005));
+        // The message time has wrapped but the current time has not.
+        assert_eq!(extend(WRAP + 5, WRAP - 10), millis(WRAP + 5));
+    }
+}
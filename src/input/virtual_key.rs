@@ -0,0 +1,208 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The Windows virtual-key codes, which `VirtualKey` uses as its key codes on every platform.
+//! They are spelled out here rather than taken from the `windows` crate, so that the key tables
+//! build and can be tested on any host.
+
+use super::VirtualKeyT;
+
+pub(crate) const BACKSPACE: VirtualKeyT = 0x08; // VK_BACK
+pub(crate) const TAB: VirtualKeyT = 0x09; // VK_TAB
+pub(crate) const ENTER: VirtualKeyT = 0x0D; // VK_RETURN
+
+pub(crate) const SHIFT: VirtualKeyT = 0x10; // VK_SHIFT
+pub(crate) const CONTROL: VirtualKeyT = 0x11; // VK_CONTROL
+pub(crate) const ALT: VirtualKeyT = 0x12; // VK_MENU
+
+pub(crate) const PAUSE: VirtualKeyT = 0x13; // VK_PAUSE
+pub(crate) const CAPS_LOCK: VirtualKeyT = 0x14; // VK_CAPITAL
+pub(crate) const ESCAPE: VirtualKeyT = 0x1B; // VK_ESCAPE
+pub(crate) const SPACE: VirtualKeyT = 0x20; // VK_SPACE
+pub(crate) const PAGE_UP: VirtualKeyT = 0x21; // VK_PRIOR
+pub(crate) const PAGE_DOWN: VirtualKeyT = 0x22; // VK_NEXT
+pub(crate) const END: VirtualKeyT = 0x23; // VK_END
+pub(crate) const HOME: VirtualKeyT = 0x24; // VK_HOME
+pub(crate) const LEFT: VirtualKeyT = 0x25; // VK_LEFT
+pub(crate) const UP: VirtualKeyT = 0x26; // VK_UP
+pub(crate) const RIGHT: VirtualKeyT = 0x27; // VK_RIGHT
+pub(crate) const DOWN: VirtualKeyT = 0x28; // VK_DOWN
+pub(crate) const SELECT: VirtualKeyT = 0x29; // VK_SELECT
+pub(crate) const PRINT: VirtualKeyT = 0x2A; // VK_PRINT
+pub(crate) const EXECUTE: VirtualKeyT = 0x2B; // VK_EXECUTE
+pub(crate) const PRINT_SCREEN: VirtualKeyT = 0x2C; // VK_SNAPSHOT
+pub(crate) const INSERT: VirtualKeyT = 0x2D; // VK_INSERT
+pub(crate) const DELETE: VirtualKeyT = 0x2E; // VK_DELETE
+pub(crate) const HELP: VirtualKeyT = 0x2F; // VK_HELP
+pub(crate) const CLEAR: VirtualKeyT = 0x0C; // VK_CLEAR
+
+pub(crate) const NUM0: VirtualKeyT = 0x30; // VK_0
+pub(crate) const NUM1: VirtualKeyT = 0x31; // VK_1
+pub(crate) const NUM2: VirtualKeyT = 0x32; // VK_2
+pub(crate) const NUM3: VirtualKeyT = 0x33; // VK_3
+pub(crate) const NUM4: VirtualKeyT = 0x34; // VK_4
+pub(crate) const NUM5: VirtualKeyT = 0x35; // VK_5
+pub(crate) const NUM6: VirtualKeyT = 0x36; // VK_6
+pub(crate) const NUM7: VirtualKeyT = 0x37; // VK_7
+pub(crate) const NUM8: VirtualKeyT = 0x38; // VK_8
+pub(crate) const NUM9: VirtualKeyT = 0x39; // VK_9
+
+pub(crate) const A: VirtualKeyT = 0x41; // VK_A
+pub(crate) const B: VirtualKeyT = 0x42; // VK_B
+pub(crate) const C: VirtualKeyT = 0x43; // VK_C
+pub(crate) const D: VirtualKeyT = 0x44; // VK_D
+pub(crate) const E: VirtualKeyT = 0x45; // VK_E
+pub(crate) const F: VirtualKeyT = 0x46; // VK_F
+pub(crate) const G: VirtualKeyT = 0x47; // VK_G
+pub(crate) const H: VirtualKeyT = 0x48; // VK_H
+pub(crate) const I: VirtualKeyT = 0x49; // VK_I
+pub(crate) const J: VirtualKeyT = 0x4A; // VK_J
+pub(crate) const K: VirtualKeyT = 0x4B; // VK_K
+pub(crate) const L: VirtualKeyT = 0x4C; // VK_L
+pub(crate) const M: VirtualKeyT = 0x4D; // VK_M
+pub(crate) const N: VirtualKeyT = 0x4E; // VK_N
+pub(crate) const O: VirtualKeyT = 0x4F; // VK_O
+pub(crate) const P: VirtualKeyT = 0x50; // VK_P
+pub(crate) const Q: VirtualKeyT = 0x51; // VK_Q
+pub(crate) const R: VirtualKeyT = 0x52; // VK_R
+pub(crate) const S: VirtualKeyT = 0x53; // VK_S
+pub(crate) const T: VirtualKeyT = 0x54; // VK_T
+pub(crate) const U: VirtualKeyT = 0x55; // VK_U
+pub(crate) const V: VirtualKeyT = 0x56; // VK_V
+pub(crate) const W: VirtualKeyT = 0x57; // VK_W
+pub(crate) const X: VirtualKeyT = 0x58; // VK_X
+pub(crate) const Y: VirtualKeyT = 0x59; // VK_Y
+pub(crate) const Z: VirtualKeyT = 0x5A; // VK_Z
+
+pub(crate) const LEFT_SUPER: VirtualKeyT = 0x5B; // VK_LWIN
+pub(crate) const RIGHT_SUPER: VirtualKeyT = 0x5C; // VK_RWIN
+pub(crate) const MENU: VirtualKeyT = 0x5D; // VK_APPS
+pub(crate) const SLEEP: VirtualKeyT = 0x5F; // VK_SLEEP
+
+pub(crate) const CANCEL: VirtualKeyT = 0x03; // VK_CANCEL
+pub(crate) const KANA: VirtualKeyT = 0x15; // VK_KANA
+pub(crate) const IME_ON: VirtualKeyT = 0x16; // VK_IME_ON
+pub(crate) const JUNJA: VirtualKeyT = 0x17; // VK_JUNJA
+pub(crate) const FINAL: VirtualKeyT = 0x18; // VK_FINAL
+pub(crate) const KANJI: VirtualKeyT = 0x19; // VK_KANJI
+pub(crate) const IME_OFF: VirtualKeyT = 0x1A; // VK_IME_OFF
+pub(crate) const CONVERT: VirtualKeyT = 0x1C; // VK_CONVERT
+pub(crate) const NON_CONVERT: VirtualKeyT = 0x1D; // VK_NONCONVERT
+pub(crate) const ACCEPT: VirtualKeyT = 0x1E; // VK_ACCEPT
+pub(crate) const MODE_CHANGE: VirtualKeyT = 0x1F; // VK_MODECHANGE
+pub(crate) const PROCESS: VirtualKeyT = 0xE5; // VK_PROCESSKEY
+
+pub(crate) const NUMPAD0: VirtualKeyT = 0x60; // VK_NUMPAD0
+pub(crate) const NUMPAD1: VirtualKeyT = 0x61; // VK_NUMPAD1
+pub(crate) const NUMPAD2: VirtualKeyT = 0x62; // VK_NUMPAD2
+pub(crate) const NUMPAD3: VirtualKeyT = 0x63; // VK_NUMPAD3
+pub(crate) const NUMPAD4: VirtualKeyT = 0x64; // VK_NUMPAD4
+pub(crate) const NUMPAD5: VirtualKeyT = 0x65; // VK_NUMPAD5
+pub(crate) const NUMPAD6: VirtualKeyT = 0x66; // VK_NUMPAD6
+pub(crate) const NUMPAD7: VirtualKeyT = 0x67; // VK_NUMPAD7
+pub(crate) const NUMPAD8: VirtualKeyT = 0x68; // VK_NUMPAD8
+pub(crate) const NUMPAD9: VirtualKeyT = 0x69; // VK_NUMPAD9
+
+pub(crate) const NUMPAD_MULTIPLY: VirtualKeyT = 0x6A; // VK_MULTIPLY
+pub(crate) const NUMPAD_ADD: VirtualKeyT = 0x6B; // VK_ADD
+pub(crate) const NUMPAD_SEPARATOR: VirtualKeyT = 0x6C; // VK_SEPARATOR
+pub(crate) const NUMPAD_SUBTRACT: VirtualKeyT = 0x6D; // VK_SUBTRACT
+pub(crate) const NUMPAD_DECIMAL: VirtualKeyT = 0x6E; // VK_DECIMAL
+pub(crate) const NUMPAD_DIVIDE: VirtualKeyT = 0x6F; // VK_DIVIDE
+
+/// Win32 has no virtual key for the numpad enter key, it is reported as `VK_RETURN` with the
+/// extended-key flag set. This value lies outside the range of real virtual keys.
+pub(crate) const NUMPAD_ENTER: VirtualKeyT = 0x100 | ENTER;
+
+pub(crate) const F1: VirtualKeyT = 0x70; // VK_F1
+pub(crate) const F2: VirtualKeyT = 0x71; // VK_F2
+pub(crate) const F3: VirtualKeyT = 0x72; // VK_F3
+pub(crate) const F4: VirtualKeyT = 0x73; // VK_F4
+pub(crate) const F5: VirtualKeyT = 0x74; // VK_F5
+pub(crate) const F6: VirtualKeyT = 0x75; // VK_F6
+pub(crate) const F7: VirtualKeyT = 0x76; // VK_F7
+pub(crate) const F8: VirtualKeyT = 0x77; // VK_F8
+pub(crate) const F9: VirtualKeyT = 0x78; // VK_F9
+pub(crate) const F10: VirtualKeyT = 0x79; // VK_F10
+pub(crate) const F11: VirtualKeyT = 0x7A; // VK_F11
+pub(crate) const F12: VirtualKeyT = 0x7B; // VK_F12
+pub(crate) const F13: VirtualKeyT = 0x7C; // VK_F13
+pub(crate) const F14: VirtualKeyT = 0x7D; // VK_F14
+pub(crate) const F15: VirtualKeyT = 0x7E; // VK_F15
+pub(crate) const F16: VirtualKeyT = 0x7F; // VK_F16
+pub(crate) const F17: VirtualKeyT = 0x80; // VK_F17
+pub(crate) const F18: VirtualKeyT = 0x81; // VK_F18
+pub(crate) const F19: VirtualKeyT = 0x82; // VK_F19
+pub(crate) const F20: VirtualKeyT = 0x83; // VK_F20
+pub(crate) const F21: VirtualKeyT = 0x84; // VK_F21
+pub(crate) const F22: VirtualKeyT = 0x85; // VK_F22
+pub(crate) const F23: VirtualKeyT = 0x86; // VK_F23
+pub(crate) const F24: VirtualKeyT = 0x87; // VK_F24
+
+pub(crate) const NUM_LOCK: VirtualKeyT = 0x90; // VK_NUMLOCK
+pub(crate) const SCROLL_LOCK: VirtualKeyT = 0x91; // VK_SCROLL
+
+pub(crate) const LEFT_SHIFT: VirtualKeyT = 0xA0; // VK_LSHIFT
+pub(crate) const RIGHT_SHIFT: VirtualKeyT = 0xA1; // VK_RSHIFT
+pub(crate) const LEFT_CONTROL: VirtualKeyT = 0xA2; // VK_LCONTROL
+pub(crate) const RIGHT_CONTROL: VirtualKeyT = 0xA3; // VK_RCONTROL
+pub(crate) const LEFT_ALT: VirtualKeyT = 0xA4; // VK_LMENU
+pub(crate) const RIGHT_ALT: VirtualKeyT = 0xA5; // VK_RMENU
+
+pub(crate) const BROWSER_BACK: VirtualKeyT = 0xA6; // VK_BROWSER_BACK
+pub(crate) const BROWSER_FORWARD: VirtualKeyT = 0xA7; // VK_BROWSER_FORWARD
+pub(crate) const BROWSER_REFRESH: VirtualKeyT = 0xA8; // VK_BROWSER_REFRESH
+pub(crate) const BROWSER_STOP: VirtualKeyT = 0xA9; // VK_BROWSER_STOP
+pub(crate) const BROWSER_SEARCH: VirtualKeyT = 0xAA; // VK_BROWSER_SEARCH
+pub(crate) const BROWSER_FAVORITES: VirtualKeyT = 0xAB; // VK_BROWSER_FAVORITES
+pub(crate) const BROWSER_HOME: VirtualKeyT = 0xAC; // VK_BROWSER_HOME
+
+pub(crate) const VOLUME_MUTE: VirtualKeyT = 0xAD; // VK_VOLUME_MUTE
+pub(crate) const VOLUME_DOWN: VirtualKeyT = 0xAE; // VK_VOLUME_DOWN
+pub(crate) const VOLUME_UP: VirtualKeyT = 0xAF; // VK_VOLUME_UP
+
+pub(crate) const MEDIA_NEXT_TRACK: VirtualKeyT = 0xB0; // VK_MEDIA_NEXT_TRACK
+pub(crate) const MEDIA_PREV_TRACK: VirtualKeyT = 0xB1; // VK_MEDIA_PREV_TRACK
+pub(crate) const MEDIA_STOP: VirtualKeyT = 0xB2; // VK_MEDIA_STOP
+pub(crate) const MEDIA_PLAY_PAUSE: VirtualKeyT = 0xB3; // VK_MEDIA_PLAY_PAUSE
+
+pub(crate) const LAUNCH_MAIL: VirtualKeyT = 0xB4; // VK_LAUNCH_MAIL
+pub(crate) const LAUNCH_MEDIA_SELECT: VirtualKeyT = 0xB5; // VK_LAUNCH_MEDIA_SELECT
+pub(crate) const LAUNCH_APP1: VirtualKeyT = 0xB6; // VK_LAUNCH_APP1
+pub(crate) const LAUNCH_APP2: VirtualKeyT = 0xB7; // VK_LAUNCH_APP2
+
+pub(crate) const COLON: VirtualKeyT = 0xBA; // VK_OEM_1
+pub(crate) const PLUS: VirtualKeyT = 0xBB; // VK_OEM_PLUS
+pub(crate) const COMMA: VirtualKeyT = 0xBC; // VK_OEM_COMMA
+pub(crate) const MINUS: VirtualKeyT = 0xBD; // VK_OEM_MINUS
+pub(crate) const PERIOD: VirtualKeyT = 0xBE; // VK_OEM_PERIOD
+pub(crate) const SLASH: VirtualKeyT = 0xBF; // VK_OEM_2
+pub(crate) const TILDE: VirtualKeyT = 0xC0; // VK_OEM_3
+pub(crate) const LEFT_BRACKET: VirtualKeyT = 0xDB; // VK_OEM_4
+pub(crate) const BACKSLASH: VirtualKeyT = 0xDC; // VK_OEM_5
+pub(crate) const RIGHT_BRACKET: VirtualKeyT = 0xDD; // VK_OEM_6
+pub(crate) const QUOTE: VirtualKeyT = 0xDE; // VK_OEM_7
+pub(crate) const OEM_8: VirtualKeyT = 0xDF; // VK_OEM_8
+pub(crate) const OEM_102: VirtualKeyT = 0xE2; // VK_OEM_102
+pub(crate) const OEM_CLEAR: VirtualKeyT = 0xFE; // VK_OEM_CLEAR
+
+pub(crate) const ATTN: VirtualKeyT = 0xF6; // VK_ATTN
+pub(crate) const CRSEL: VirtualKeyT = 0xF7; // VK_CRSEL
+pub(crate) const EXSEL: VirtualKeyT = 0xF8; // VK_EXSEL
+pub(crate) const ERASE_EOF: VirtualKeyT = 0xF9; // VK_EREOF
+pub(crate) const PLAY: VirtualKeyT = 0xFA; // VK_PLAY
+pub(crate) const ZOOM: VirtualKeyT = 0xFB; // VK_ZOOM
+pub(crate) const PA1: VirtualKeyT = 0xFD; // VK_PA1
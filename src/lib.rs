@@ -14,44 +14,117 @@
  * limitations under the License.
  */
 
+extern crate bitflags;
 extern crate lazy_static;
 extern crate eventify;
 
 #[cfg(target_os = "windows")]
 extern crate windows;
 
+mod clipboard;
+mod drag;
+mod image;
 mod input;
 mod platform_impl;
+mod record;
 
+// Windowing is only implemented on Windows so far. The input handling builds on every platform.
+#[cfg(target_os = "windows")]
 pub use platform_impl::Application;
+#[cfg(target_os = "windows")]
+pub use platform_impl::Clipboard;
+#[cfg(target_os = "windows")]
 pub use platform_impl::Window;
+// Until the Linux backends land, the keymap handling is offered to applications that run their
+// own X11 or Wayland connection.
+#[cfg(all(target_os = "linux", feature = "xkb"))]
+pub use platform_impl::{XkbError, XkbKeyEvents, XkbKeymap, XkbState, EVDEV_OFFSET};
+pub use clipboard::*;
+pub use drag::*;
+pub use image::*;
 pub use input::*;
+pub use record::*;
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size {
     pub width: f32,
     pub height: f32,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     pub x: f32,
     pub y: f32,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rect {
     pos: Point,
     size: Size,
 }
 
+impl Rect {
+    pub const fn new(pos: Point, size: Size) -> Self {
+        Self { pos, size }
+    }
+
+    pub const fn pos(&self) -> Point {
+        self.pos
+    }
+
+    pub const fn size(&self) -> Size {
+        self.size
+    }
+}
+
+/// Identifies a window for as long as it exists.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowId(u64);
+
+impl WindowId {
+    pub const fn from_raw(raw: u64) -> Self {
+        Self(raw)
+    }
+
+    pub const fn to_raw(&self) -> u64 {
+        self.0
+    }
+}
+
+bitflags::bitflags! {
+    /// The system behaviours triggered by system keys that are left to the platform. Behaviours
+    /// that are not included are consumed, leaving the keys to the application.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SystemKeys: u32 {
+        /// Alt+F4 closes the window.
+        const ALT_F4_CLOSE = 1 << 0;
+        /// Alt or F10 on their own, and alt mnemonics, activate the window menu.
+        const MENU_ACTIVATION = 1 << 1;
+        /// Alt+Space opens the system menu.
+        const SYSTEM_MENU = 1 << 2;
+    }
+}
+
+/// The initial configuration of a window. Deserializing fills in missing fields from
+/// `WindowConfig::default`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct WindowConfig {
     pub title: String,
     pub size: Option<Size>,
     pub pos: Option<Point>,
     pub decorated: bool,
     pub resizable: bool,
+    pub system_keys: SystemKeys,
+    /// Whether touches are also reported as mouse events, for controllers that only handle the
+    /// mouse.
+    pub touch_emulates_mouse: bool,
 }
 
 impl WindowConfig {
@@ -62,6 +135,8 @@ impl WindowConfig {
             pos: None,
             decorated: true,
             resizable: true,
+            system_keys: SystemKeys::all(),
+            touch_emulates_mouse: true,
         }
     }
 }
@@ -72,6 +147,7 @@ impl Default for WindowConfig {
     }
 }
 
+#[cfg(target_os = "windows")]
 #[allow(unused_variables)]
 pub trait WindowController {
     fn get_config(&self) -> WindowConfig {
@@ -90,13 +166,54 @@ pub trait WindowController {
 
     fn on_key(&self, window: &Window, event: &KeyEvent) {}
     fn on_char(&self, window: &Window, event: &CharEvent) {}
+    fn on_text_input(&self, window: &Window, event: &TextInputEvent) {}
     fn on_focus(&self, window: &Window, event: &FocusChangedEvent) {}
+    fn on_modifiers_changed(&self, window: &Window, event: &ModifiersChangedEvent) {}
+    fn on_keyboard_layout_changed(&self, window: &Window, event: &KeyboardLayoutChangedEvent) {}
+
+    /// Called for input method editor events. By default, committed text is forwarded to
+    /// `on_text_input` so that controllers which don't handle composition still receive it.
+    fn on_ime(&self, window: &Window, event: &ImeEvent) {
+        if let Ime::Commit(text) = event.ime() {
+            self.on_text_input(
+                window,
+                &TextInputEvent::new(text.clone(), event.timestamp()),
+            );
+        }
+    }
 
     fn on_mouse_move(&self, window: &Window, event: &MouseMoveEvent) {}
     fn on_mouse_button(&self, window: &Window, event: &MouseButtonEvent) {}
     fn on_mouse_wheel(&self, window: &Window, event: &MouseWheelEvent) {}
+    fn on_touch(&self, window: &Window, event: &TouchEvent) {}
+    /// Called for mouse, pen and touch input alike, in addition to the mouse and touch
+    /// callbacks. Pen input is only reported here and as emulated mouse input.
+    fn on_pointer(&self, window: &Window, event: &PointerEvent) {}
+    fn on_gesture(&self, window: &Window, event: &GestureEvent) {}
+
+    /// Called when a drag enters the window. Returns the effect dropping the data would have,
+    /// with no effect rejecting the drag, which is the default.
+    fn on_drag_enter(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        DropEffect::empty()
+    }
+
+    /// Called as a drag moves over the window or its modifiers change. By default, the effect
+    /// chosen when the drag entered the window is kept.
+    fn on_drag_over(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        event.effect()
+    }
+
+    /// Called when a drag leaves the window or is cancelled.
+    fn on_drag_leave(&self, window: &Window, event: &DragLeaveEvent) {}
+
+    /// Called when data is dropped onto the window. Returns the effect the drop had, which tells
+    /// the source whether to remove moved data. By default, drops are rejected.
+    fn on_drop(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        DropEffect::empty()
+    }
 }
 
+#[cfg(target_os = "windows")]
 #[allow(unused_variables)]
 pub trait ApplicationController {
     type ExitCode;
@@ -104,8 +221,57 @@ pub trait ApplicationController {
     fn on_init(&self, app: &Application);
     fn on_exit(&self, app: &Application) -> Self::ExitCode;
 
+    /// Called when a global hotkey registered with `Application::register_global_hotkey` is
+    /// pressed, whether or not the application has focus.
+    fn on_global_hotkey(&self, app: &Application, event: &GlobalHotkeyEvent) {}
+
+    /// Called when the contents of the clipboard change, including changes made through
+    /// `Application::clipboard`.
+    fn on_clipboard_changed(&self, app: &Application) {}
+
     fn before_window_events(&self, app: &Application) {}
     fn after_window_events(&self, app: &Application) {
         app.wait_for_events();
     }
 }
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_config_serde_round_trip() {
+        let config = WindowConfig {
+            title: "Editor".to_string(),
+            size: Some(Size {
+                width: 1280.0,
+                height: 720.0,
+            }),
+            pos: None,
+            decorated: false,
+            resizable: true,
+            system_keys: SystemKeys::ALT_F4_CLOSE,
+            touch_emulates_mouse: false,
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed = serde_json::from_str::<WindowConfig>(&json).unwrap();
+        assert_eq!(parsed.title, config.title);
+        assert_eq!(parsed.size, config.size);
+        assert_eq!(parsed.pos, config.pos);
+        assert_eq!(parsed.decorated, config.decorated);
+        assert_eq!(parsed.system_keys, config.system_keys);
+        assert_eq!(parsed.touch_emulates_mouse, config.touch_emulates_mouse);
+    }
+
+    #[test]
+    fn window_config_serde_defaults() {
+        let parsed = serde_json::from_str::<WindowConfig>(r#"{ "title": "Editor" }"#).unwrap();
+        let default = WindowConfig::default();
+        assert_eq!(parsed.title, "Editor");
+        assert_eq!(parsed.size, default.size);
+        assert_eq!(parsed.decorated, default.decorated);
+        assert_eq!(parsed.resizable, default.resizable);
+        assert_eq!(parsed.system_keys, default.system_keys);
+        assert_eq!(parsed.touch_emulates_mouse, default.touch_emulates_mouse);
+    }
+}
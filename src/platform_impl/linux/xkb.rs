@@ -0,0 +1,864 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Keymap handling for the Linux backends. Keymaps are compiled by xkbcommon, either from the
+//! local xkb data or from a keymap string such as the one a Wayland compositor sends, and key
+//! codes are translated into the `KeyEvent` and `CharEvent` types used on every platform.
+
+use crate::*;
+
+use std::fmt;
+
+use xkbcommon::xkb;
+use xkbcommon::xkb::keysyms;
+
+/// The offset between evdev key codes and XKB key codes. X11 reports XKB key codes, Wayland
+/// reports evdev key codes.
+pub const EVDEV_OFFSET: u32 = 8;
+
+const LETTERS: [VirtualKey; 26] = [
+    VirtualKey::A,
+    VirtualKey::B,
+    VirtualKey::C,
+    VirtualKey::D,
+    VirtualKey::E,
+    VirtualKey::F,
+    VirtualKey::G,
+    VirtualKey::H,
+    VirtualKey::I,
+    VirtualKey::J,
+    VirtualKey::K,
+    VirtualKey::L,
+    VirtualKey::M,
+    VirtualKey::N,
+    VirtualKey::O,
+    VirtualKey::P,
+    VirtualKey::Q,
+    VirtualKey::R,
+    VirtualKey::S,
+    VirtualKey::T,
+    VirtualKey::U,
+    VirtualKey::V,
+    VirtualKey::W,
+    VirtualKey::X,
+    VirtualKey::Y,
+    VirtualKey::Z,
+];
+
+const DIGITS: [VirtualKey; 10] = [
+    VirtualKey::Num0,
+    VirtualKey::Num1,
+    VirtualKey::Num2,
+    VirtualKey::Num3,
+    VirtualKey::Num4,
+    VirtualKey::Num5,
+    VirtualKey::Num6,
+    VirtualKey::Num7,
+    VirtualKey::Num8,
+    VirtualKey::Num9,
+];
+
+const NUMPAD_DIGITS: [VirtualKey; 10] = [
+    VirtualKey::Numpad0,
+    VirtualKey::Numpad1,
+    VirtualKey::Numpad2,
+    VirtualKey::Numpad3,
+    VirtualKey::Numpad4,
+    VirtualKey::Numpad5,
+    VirtualKey::Numpad6,
+    VirtualKey::Numpad7,
+    VirtualKey::Numpad8,
+    VirtualKey::Numpad9,
+];
+
+const FUNCTION_KEYS: [VirtualKey; 24] = [
+    VirtualKey::F1,
+    VirtualKey::F2,
+    VirtualKey::F3,
+    VirtualKey::F4,
+    VirtualKey::F5,
+    VirtualKey::F6,
+    VirtualKey::F7,
+    VirtualKey::F8,
+    VirtualKey::F9,
+    VirtualKey::F10,
+    VirtualKey::F11,
+    VirtualKey::F12,
+    VirtualKey::F13,
+    VirtualKey::F14,
+    VirtualKey::F15,
+    VirtualKey::F16,
+    VirtualKey::F17,
+    VirtualKey::F18,
+    VirtualKey::F19,
+    VirtualKey::F20,
+    VirtualKey::F21,
+    VirtualKey::F22,
+    VirtualKey::F23,
+    VirtualKey::F24,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XkbError {
+    /// xkbcommon rejected the keymap names or source.
+    Compile,
+}
+
+impl fmt::Display for XkbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Compile => write!(f, "failed to compile the xkb keymap"),
+        }
+    }
+}
+
+impl std::error::Error for XkbError {}
+
+/// A compiled XKB keymap.
+#[derive(Clone)]
+pub struct XkbKeymap {
+    keymap: xkb::Keymap,
+}
+
+impl XkbKeymap {
+    /// Compiles a keymap from the local xkb data. Empty names select the defaults, which can be
+    /// overridden through the `XKB_DEFAULT_*` environment variables.
+    pub fn from_names(
+        rules: &str,
+        model: &str,
+        layout: &str,
+        variant: &str,
+        options: Option<&str>,
+    ) -> Result<Self, XkbError> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        xkb::Keymap::new_from_names(
+            &context,
+            rules,
+            model,
+            layout,
+            variant,
+            options.map(str::to_string),
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .map(|keymap| Self { keymap })
+        .ok_or(XkbError::Compile)
+    }
+
+    /// Compiles a keymap in the text v1 format, as sent by `wl_keyboard.keymap`.
+    pub fn from_string(source: &str) -> Result<Self, XkbError> {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        xkb::Keymap::new_from_string(
+            &context,
+            source.trim_end_matches('\0').to_string(),
+            xkb::KEYMAP_FORMAT_TEXT_V1,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        )
+        .map(|keymap| Self { keymap })
+        .ok_or(XkbError::Compile)
+    }
+
+    /// Returns the names of the layouts (groups) in the keymap, e.g. `"German"`.
+    pub fn layout_names(&self) -> Vec<String> {
+        (0..self.keymap.num_layouts())
+            .map(|layout| self.keymap.layout_get_name(layout).to_string())
+            .collect()
+    }
+
+    /// Returns whether the key should repeat while held, for backends that repeat keys
+    /// themselves.
+    pub fn key_repeats(&self, keycode: u32) -> bool {
+        self.keymap.key_repeats(xkb::Keycode::new(keycode))
+    }
+}
+
+/// The events produced by a single key code.
+#[derive(Debug, Clone)]
+pub struct XkbKeyEvents {
+    key: KeyEvent,
+    keysym: u32,
+    keysym_name: String,
+    chars: Vec<CharEvent>,
+    modifiers_changed: Option<ModifiersChangedEvent>,
+}
+
+impl XkbKeyEvents {
+    pub const fn key(&self) -> &KeyEvent {
+        &self.key
+    }
+
+    /// Returns the keysym the key was mapped from. It identifies keys without a virtual-key
+    /// code, which are reported as `VirtualKey::Unknown(0xFF)`.
+    pub const fn keysym(&self) -> u32 {
+        self.keysym
+    }
+
+    /// Returns the name of the keysym the key produced with the active modifiers, for feeding a
+    /// `Composer`.
+    pub fn keysym_name(&self) -> &str {
+        &self.keysym_name
+    }
+
+    pub fn chars(&self) -> &[CharEvent] {
+        &self.chars
+    }
+
+    pub const fn modifiers_changed(&self) -> Option<&ModifiersChangedEvent> {
+        self.modifiers_changed.as_ref()
+    }
+}
+
+/// The keyboard state of a seat: pressed keys, the active group and latched and locked
+/// modifiers.
+pub struct XkbState {
+    keymap: XkbKeymap,
+    state: xkb::State,
+    held: Modifiers,
+    modifiers: Modifiers,
+}
+
+impl XkbState {
+    pub fn new(keymap: &XkbKeymap) -> Self {
+        Self {
+            keymap: keymap.clone(),
+            state: xkb::State::new(&keymap.keymap),
+            held: Modifiers::empty(),
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    pub fn keymap(&self) -> &XkbKeymap {
+        &self.keymap
+    }
+
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the index of the active layout (group).
+    pub fn layout(&self) -> u32 {
+        self.state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE)
+    }
+
+    /// Returns the name of the active layout, e.g. `"English (US)"`.
+    pub fn layout_name(&self) -> String {
+        self.keymap
+            .keymap
+            .layout_get_name(self.layout())
+            .to_string()
+    }
+
+    /// Processes a key press, repeat or release. `keycode` is an XKB key code, i.e. the evdev
+    /// key code plus `EVDEV_OFFSET`. `timestamp` is the time the server sent the key.
+    pub fn key(&mut self, keycode: u32, state: KeyState, timestamp: Timestamp) -> XkbKeyEvents {
+        let xkb_keycode = xkb::Keycode::new(keycode);
+
+        // The key and text are looked up before the key updates the state, so that a modifier
+        // key doesn't affect its own symbol.
+        let layout = self.state.key_get_layout(xkb_keycode);
+        let base = self
+            .keymap
+            .keymap
+            .key_get_syms_by_level(xkb_keycode, layout, 0)
+            .first()
+            .map_or(keysyms::KEY_NoSymbol, |keysym| keysym.raw());
+        let effective = self.state.key_get_one_sym(xkb_keycode).raw();
+
+        // Like Win32 virtual keys, shift levels don't change the key, but Num Lock does.
+        let keysym = if is_keypad_keysym(effective) {
+            effective
+        } else {
+            base
+        };
+        let (key, location) = keysym_to_vkey(keysym);
+        let dead_key = dead_keysym_to_char(effective);
+
+        let text = match state {
+            KeyState::Release => String::new(),
+            _ if dead_key.is_some() => String::new(),
+            _ => self.state.key_get_utf8(xkb_keycode),
+        };
+
+        match state {
+            KeyState::Press => {
+                self.state.update_key(xkb_keycode, xkb::KeyDirection::Down);
+                self.held |= held_modifier(key);
+            }
+            KeyState::Release => {
+                self.state.update_key(xkb_keycode, xkb::KeyDirection::Up);
+                self.held &= !held_modifier(key);
+            }
+            KeyState::Repeat => {}
+        }
+
+        let modifiers_changed = self.update_modifiers(timestamp);
+        let scan_code = evdev_to_scan_code(keycode.saturating_sub(EVDEV_OFFSET));
+        let repeat = state == KeyState::Repeat;
+
+        XkbKeyEvents {
+            key: KeyEvent::new(
+                key,
+                location,
+                scan_code,
+                state,
+                self.modifiers,
+                dead_key,
+                timestamp,
+            ),
+            keysym,
+            keysym_name: xkb::keysym_get_name(effective.into()),
+            chars: text
+                .chars()
+                .map(|c| CharEvent::new(c, repeat, timestamp))
+                .collect(),
+            modifiers_changed,
+        }
+    }
+
+    /// Applies the serialized modifier and group state sent by `wl_keyboard.modifiers` or an
+    /// X11 `XkbStateNotify` event.
+    pub fn update_mask(
+        &mut self,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+        group: u32,
+        timestamp: Timestamp,
+    ) -> Option<ModifiersChangedEvent> {
+        self.state
+            .update_mask(depressed, latched, locked, 0, 0, group);
+        self.update_modifiers(timestamp)
+    }
+
+    /// Releases all keys, e.g. after the window lost keyboard focus.
+    pub fn reset(&mut self, timestamp: Timestamp) -> Option<ModifiersChangedEvent> {
+        let layout = self.layout();
+        let locked = self.state.serialize_mods(xkb::STATE_MODS_LOCKED);
+        self.state = xkb::State::new(&self.keymap.keymap);
+        self.state.update_mask(0, 0, locked, 0, 0, layout);
+        self.held = Modifiers::empty();
+        self.update_modifiers(timestamp)
+    }
+
+    fn update_modifiers(&mut self, timestamp: Timestamp) -> Option<ModifiersChangedEvent> {
+        let modifiers = self.current_modifiers();
+        if modifiers == self.modifiers {
+            return None;
+        }
+
+        self.modifiers = modifiers;
+        Some(ModifiersChangedEvent::new(modifiers, timestamp))
+    }
+
+    fn current_modifiers(&self) -> Modifiers {
+        let sided = [
+            (
+                xkb::MOD_NAME_SHIFT,
+                Modifiers::LEFT_SHIFT,
+                Modifiers::RIGHT_SHIFT,
+            ),
+            (
+                xkb::MOD_NAME_CTRL,
+                Modifiers::LEFT_CONTROL,
+                Modifiers::RIGHT_CONTROL,
+            ),
+            (xkb::MOD_NAME_ALT, Modifiers::LEFT_ALT, Modifiers::RIGHT_ALT),
+            (
+                xkb::MOD_NAME_LOGO,
+                Modifiers::LEFT_SUPER,
+                Modifiers::RIGHT_SUPER,
+            ),
+        ];
+
+        let mut modifiers = Modifiers::empty();
+        for (name, left, right) in sided {
+            if self.is_mod_active(name) {
+                // The state only knows the modifier, not which key set it, so the left key is
+                // assumed when the modifier was set by a latch, a lock or the compositor.
+                let sides = self.held & (left | right);
+                modifiers |= if sides.is_empty() { left } else { sides };
+            }
+        }
+
+        // AltGr usually sets the level three modifier rather than Alt.
+        if self.is_mod_active(xkb::MOD_NAME_ISO_LEVEL3_SHIFT) {
+            modifiers |= Modifiers::RIGHT_ALT;
+        }
+        if self.is_mod_active(xkb::MOD_NAME_CAPS) {
+            modifiers |= Modifiers::CAPS_LOCK;
+        }
+        if self.is_mod_active(xkb::MOD_NAME_NUM) {
+            modifiers |= Modifiers::NUM_LOCK;
+        }
+
+        modifiers
+    }
+
+    fn is_mod_active(&self, name: &str) -> bool {
+        self.state
+            .mod_name_is_active(name, xkb::STATE_MODS_EFFECTIVE)
+    }
+}
+
+/// Returns the modifier tracked while the key is held.
+const fn held_modifier(key: VirtualKey) -> Modifiers {
+    match key {
+        VirtualKey::LeftShift => Modifiers::LEFT_SHIFT,
+        VirtualKey::RightShift => Modifiers::RIGHT_SHIFT,
+        VirtualKey::LeftControl => Modifiers::LEFT_CONTROL,
+        VirtualKey::RightControl => Modifiers::RIGHT_CONTROL,
+        VirtualKey::LeftAlt => Modifiers::LEFT_ALT,
+        VirtualKey::RightAlt => Modifiers::RIGHT_ALT,
+        VirtualKey::LeftSuper => Modifiers::LEFT_SUPER,
+        VirtualKey::RightSuper => Modifiers::RIGHT_SUPER,
+        _ => Modifiers::empty(),
+    }
+}
+
+const fn is_keypad_keysym(keysym: u32) -> bool {
+    keysym >= keysyms::KEY_KP_Space && keysym <= keysyms::KEY_KP_Equal
+}
+
+/// Returns the character a dead keysym stands for, using the same characters as Win32 reports
+/// for dead keys.
+const fn dead_keysym_to_char(keysym: u32) -> Option<char> {
+    let c = match keysym {
+        keysyms::KEY_dead_grave => '`',
+        keysyms::KEY_dead_acute => '´',
+        keysyms::KEY_dead_circumflex => '^',
+        keysyms::KEY_dead_tilde => '~',
+        keysyms::KEY_dead_macron => '¯',
+        keysyms::KEY_dead_breve => '˘',
+        keysyms::KEY_dead_abovedot => '˙',
+        keysyms::KEY_dead_diaeresis => '¨',
+        keysyms::KEY_dead_abovering => '˚',
+        keysyms::KEY_dead_doubleacute => '˝',
+        keysyms::KEY_dead_caron => 'ˇ',
+        keysyms::KEY_dead_cedilla => '¸',
+        keysyms::KEY_dead_ogonek => '˛',
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Maps a keysym to a `VirtualKey`, falling back to `VirtualKey::Unknown(0xFF)` like Windows
+/// does for keys without a virtual-key code. Punctuation is mapped to the key that produces it
+/// on the US layout, as Win32 does for the OEM keys.
+const fn keysym_to_vkey(keysym: u32) -> (VirtualKey, KeyLocation) {
+    let key = match keysym {
+        keysyms::KEY_a..=keysyms::KEY_z => LETTERS[(keysym - keysyms::KEY_a) as usize],
+        keysyms::KEY_A..=keysyms::KEY_Z => LETTERS[(keysym - keysyms::KEY_A) as usize],
+        keysyms::KEY_0..=keysyms::KEY_9 => DIGITS[(keysym - keysyms::KEY_0) as usize],
+        keysyms::KEY_F1..=keysyms::KEY_F24 => FUNCTION_KEYS[(keysym - keysyms::KEY_F1) as usize],
+
+        keysyms::KEY_KP_0..=keysyms::KEY_KP_9 => {
+            return (
+                NUMPAD_DIGITS[(keysym - keysyms::KEY_KP_0) as usize],
+                KeyLocation::Numpad,
+            )
+        }
+        keysyms::KEY_KP_Multiply => return (VirtualKey::NumpadMultiply, KeyLocation::Numpad),
+        keysyms::KEY_KP_Add => return (VirtualKey::NumpadAdd, KeyLocation::Numpad),
+        keysyms::KEY_KP_Separator => return (VirtualKey::NumpadSeparator, KeyLocation::Numpad),
+        keysyms::KEY_KP_Subtract => return (VirtualKey::NumpadSubtract, KeyLocation::Numpad),
+        keysyms::KEY_KP_Decimal => return (VirtualKey::NumpadDecimal, KeyLocation::Numpad),
+        keysyms::KEY_KP_Divide => return (VirtualKey::NumpadDivide, KeyLocation::Numpad),
+        keysyms::KEY_KP_Enter => return (VirtualKey::NumpadEnter, KeyLocation::Numpad),
+        keysyms::KEY_KP_Home => return (VirtualKey::Home, KeyLocation::Numpad),
+        keysyms::KEY_KP_Left => return (VirtualKey::Left, KeyLocation::Numpad),
+        keysyms::KEY_KP_Up => return (VirtualKey::Up, KeyLocation::Numpad),
+        keysyms::KEY_KP_Right => return (VirtualKey::Right, KeyLocation::Numpad),
+        keysyms::KEY_KP_Down => return (VirtualKey::Down, KeyLocation::Numpad),
+        keysyms::KEY_KP_Page_Up => return (VirtualKey::PageUp, KeyLocation::Numpad),
+        keysyms::KEY_KP_Page_Down => return (VirtualKey::PageDown, KeyLocation::Numpad),
+        keysyms::KEY_KP_End => return (VirtualKey::End, KeyLocation::Numpad),
+        keysyms::KEY_KP_Begin => return (VirtualKey::Clear, KeyLocation::Numpad),
+        keysyms::KEY_KP_Insert => return (VirtualKey::Insert, KeyLocation::Numpad),
+        keysyms::KEY_KP_Delete => return (VirtualKey::Delete, KeyLocation::Numpad),
+
+        keysyms::KEY_Shift_L => return (VirtualKey::LeftShift, KeyLocation::Left),
+        keysyms::KEY_Shift_R => return (VirtualKey::RightShift, KeyLocation::Right),
+        keysyms::KEY_Control_L => return (VirtualKey::LeftControl, KeyLocation::Left),
+        keysyms::KEY_Control_R => return (VirtualKey::RightControl, KeyLocation::Right),
+        keysyms::KEY_Alt_L | keysyms::KEY_Meta_L => {
+            return (VirtualKey::LeftAlt, KeyLocation::Left)
+        }
+        keysyms::KEY_Alt_R | keysyms::KEY_Meta_R | keysyms::KEY_ISO_Level3_Shift => {
+            return (VirtualKey::RightAlt, KeyLocation::Right)
+        }
+        keysyms::KEY_Super_L => return (VirtualKey::LeftSuper, KeyLocation::Left),
+        keysyms::KEY_Super_R => return (VirtualKey::RightSuper, KeyLocation::Right),
+
+        keysyms::KEY_BackSpace => VirtualKey::Backspace,
+        keysyms::KEY_Tab | keysyms::KEY_ISO_Left_Tab => VirtualKey::Tab,
+        keysyms::KEY_Return => VirtualKey::Enter,
+        keysyms::KEY_Menu => VirtualKey::Menu,
+        keysyms::KEY_Caps_Lock => VirtualKey::CapsLock,
+        keysyms::KEY_Num_Lock => VirtualKey::NumLock,
+        keysyms::KEY_Scroll_Lock => VirtualKey::ScrollLock,
+        keysyms::KEY_Pause | keysyms::KEY_Break => VirtualKey::Pause,
+        keysyms::KEY_Escape => VirtualKey::Escape,
+        keysyms::KEY_space => VirtualKey::Space,
+        keysyms::KEY_Insert => VirtualKey::Insert,
+        keysyms::KEY_Delete => VirtualKey::Delete,
+        keysyms::KEY_Page_Up => VirtualKey::PageUp,
+        keysyms::KEY_Page_Down => VirtualKey::PageDown,
+        keysyms::KEY_End => VirtualKey::End,
+        keysyms::KEY_Home => VirtualKey::Home,
+        keysyms::KEY_Left => VirtualKey::Left,
+        keysyms::KEY_Up => VirtualKey::Up,
+        keysyms::KEY_Right => VirtualKey::Right,
+        keysyms::KEY_Down => VirtualKey::Down,
+        keysyms::KEY_Clear => VirtualKey::Clear,
+        keysyms::KEY_Select => VirtualKey::Select,
+        keysyms::KEY_Execute => VirtualKey::Execute,
+        keysyms::KEY_Print | keysyms::KEY_Sys_Req => VirtualKey::PrintScreen,
+        keysyms::KEY_Help => VirtualKey::Help,
+        keysyms::KEY_Cancel => VirtualKey::Cancel,
+
+        keysyms::KEY_semicolon => VirtualKey::Colon,
+        keysyms::KEY_equal => VirtualKey::Plus,
+        keysyms::KEY_comma => VirtualKey::Comma,
+        keysyms::KEY_minus => VirtualKey::Minus,
+        keysyms::KEY_period => VirtualKey::Period,
+        keysyms::KEY_slash => VirtualKey::Slash,
+        keysyms::KEY_grave => VirtualKey::Tilde,
+        keysyms::KEY_bracketleft => VirtualKey::LeftBracket,
+        keysyms::KEY_backslash => VirtualKey::Backslash,
+        keysyms::KEY_bracketright => VirtualKey::RightBracket,
+        keysyms::KEY_apostrophe => VirtualKey::Quote,
+        keysyms::KEY_less => VirtualKey::Oem102,
+
+        keysyms::KEY_Kana_Lock | keysyms::KEY_Hiragana_Katakana => VirtualKey::Kana,
+        keysyms::KEY_Kanji => VirtualKey::Kanji,
+        keysyms::KEY_Henkan => VirtualKey::Convert,
+        keysyms::KEY_Muhenkan => VirtualKey::NonConvert,
+        keysyms::KEY_Mode_switch => VirtualKey::ModeChange,
+
+        keysyms::KEY_XF86AudioMute => VirtualKey::VolumeMute,
+        keysyms::KEY_XF86AudioLowerVolume => VirtualKey::VolumeDown,
+        keysyms::KEY_XF86AudioRaiseVolume => VirtualKey::VolumeUp,
+        keysyms::KEY_XF86AudioNext => VirtualKey::MediaNextTrack,
+        keysyms::KEY_XF86AudioPrev => VirtualKey::MediaPreviousTrack,
+        keysyms::KEY_XF86AudioStop => VirtualKey::MediaStop,
+        keysyms::KEY_XF86AudioPlay | keysyms::KEY_XF86AudioPause => VirtualKey::MediaPlayPause,
+        keysyms::KEY_XF86Back => VirtualKey::BrowserBack,
+        keysyms::KEY_XF86Forward => VirtualKey::BrowserForward,
+        keysyms::KEY_XF86Refresh => VirtualKey::BrowserRefresh,
+        keysyms::KEY_XF86Stop => VirtualKey::BrowserStop,
+        keysyms::KEY_XF86Search => VirtualKey::BrowserSearch,
+        keysyms::KEY_XF86Favorites => VirtualKey::BrowserFavorites,
+        keysyms::KEY_XF86HomePage => VirtualKey::BrowserHome,
+        keysyms::KEY_XF86Mail => VirtualKey::LaunchMail,
+        keysyms::KEY_XF86AudioMedia => VirtualKey::LaunchMediaSelect,
+        keysyms::KEY_XF86MyComputer => VirtualKey::LaunchApp1,
+        keysyms::KEY_XF86Calculator => VirtualKey::LaunchApp2,
+        keysyms::KEY_XF86Sleep => VirtualKey::Sleep,
+
+        _ => VirtualKey::Unknown(0xFF),
+    };
+    (key, KeyLocation::Standard)
+}
+
+/// Converts an evdev key code into the PC scan code `PhysicalKey` is defined in terms of.
+/// Returns 0 for keys without a scan code.
+pub(crate) const fn evdev_to_scan_code(code: u32) -> ScanCode {
+    match code {
+        // KEY_NUMLOCK shares 0x45 with Pause, which is sent without the prefix.
+        69 => 0xE045,
+        // KEY_ESC to KEY_KPDOT match scan codes 0x01 to 0x53.
+        0x01..=0x53 => code,
+        86 => 0x0056,                     // KEY_102ND
+        87 => 0x0057,                     // KEY_F11
+        88 => 0x0058,                     // KEY_F12
+        89 => 0x0073,                     // KEY_RO
+        92 => 0x0079,                     // KEY_HENKAN
+        93 => 0x0070,                     // KEY_KATAKANAHIRAGANA
+        94 => 0x007B,                     // KEY_MUHENKAN
+        96 => 0xE01C,                     // KEY_KPENTER
+        97 => 0xE01D,                     // KEY_RIGHTCTRL
+        98 => 0xE035,                     // KEY_KPSLASH
+        99 => 0xE037,                     // KEY_SYSRQ
+        100 => 0xE038,                    // KEY_RIGHTALT
+        102 => 0xE047,                    // KEY_HOME
+        103 => 0xE048,                    // KEY_UP
+        104 => 0xE049,                    // KEY_PAGEUP
+        105 => 0xE04B,                    // KEY_LEFT
+        106 => 0xE04D,                    // KEY_RIGHT
+        107 => 0xE04F,                    // KEY_END
+        108 => 0xE050,                    // KEY_DOWN
+        109 => 0xE051,                    // KEY_PAGEDOWN
+        110 => 0xE052,                    // KEY_INSERT
+        111 => 0xE053,                    // KEY_DELETE
+        113 => 0xE020,                    // KEY_MUTE
+        114 => 0xE02E,                    // KEY_VOLUMEDOWN
+        115 => 0xE030,                    // KEY_VOLUMEUP
+        116 => 0xE05E,                    // KEY_POWER
+        117 => 0x0059,                    // KEY_KPEQUAL
+        119 => 0x0045,                    // KEY_PAUSE
+        121 => 0x007E,                    // KEY_KPCOMMA
+        122 => 0x0072,                    // KEY_HANGEUL
+        123 => 0x0071,                    // KEY_HANJA
+        124 => 0x007D,                    // KEY_YEN
+        125 => 0xE05B,                    // KEY_LEFTMETA
+        126 => 0xE05C,                    // KEY_RIGHTMETA
+        127 => 0xE05D,                    // KEY_COMPOSE
+        128 => 0xE068,                    // KEY_STOP
+        140 => 0xE021,                    // KEY_CALC
+        142 => 0xE05F,                    // KEY_SLEEP
+        143 => 0xE063,                    // KEY_WAKEUP
+        155 => 0xE06C,                    // KEY_MAIL
+        156 => 0xE066,                    // KEY_BOOKMARKS
+        157 => 0xE06B,                    // KEY_COMPUTER
+        158 => 0xE06A,                    // KEY_BACK
+        159 => 0xE069,                    // KEY_FORWARD
+        163 => 0xE019,                    // KEY_NEXTSONG
+        164 => 0xE022,                    // KEY_PLAYPAUSE
+        165 => 0xE010,                    // KEY_PREVIOUSSONG
+        166 => 0xE024,                    // KEY_STOPCD
+        172 => 0xE032,                    // KEY_HOMEPAGE
+        173 => 0xE067,                    // KEY_REFRESH
+        183..=193 => code - 183 + 0x0064, // KEY_F13 to KEY_F23
+        194 => 0x0076,                    // KEY_F24
+        217 => 0xE065,                    // KEY_SEARCH
+        226 => 0xE06D,                    // KEY_MEDIA
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    // XKB key codes of the keys in the fixture keymap.
+    const ESC: u32 = 9;
+    const AE12: u32 = 21;
+    const AD03: u32 = 26;
+    const AC01: u32 = 38;
+    const LFSH: u32 = 50;
+    const RTSH: u32 = 62;
+    const CAPS: u32 = 66;
+    const NMLK: u32 = 77;
+    const KP7: u32 = 79;
+    const RALT: u32 = 108;
+    const I208: u32 = 208;
+
+    fn state() -> XkbState {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/xkb/test.xkb");
+        let source = std::fs::read_to_string(path).unwrap();
+        XkbState::new(&XkbKeymap::from_string(&source).unwrap())
+    }
+
+    fn timestamp() -> Timestamp {
+        Timestamp::from_duration(Duration::from_millis(10))
+    }
+
+    fn press(state: &mut XkbState, keycode: u32) -> XkbKeyEvents {
+        state.key(keycode, KeyState::Press, timestamp())
+    }
+
+    fn release(state: &mut XkbState, keycode: u32) -> XkbKeyEvents {
+        state.key(keycode, KeyState::Release, timestamp())
+    }
+
+    fn chars(events: &XkbKeyEvents) -> String {
+        events.chars.iter().map(CharEvent::char).collect()
+    }
+
+    #[test]
+    fn invalid_keymap() {
+        assert_eq!(
+            XkbKeymap::from_string("xkb_keymap {").err(),
+            Some(XkbError::Compile)
+        );
+    }
+
+    #[test]
+    fn layout_names() {
+        let state = state();
+        assert_eq!(state.keymap().layout_names(), ["Test"]);
+        assert_eq!(state.layout_name(), "Test");
+        assert!(state.keymap().key_repeats(AC01));
+    }
+
+    #[test]
+    fn letter() {
+        let mut state = state();
+        let events = press(&mut state, AC01);
+        assert_eq!(events.key.key(), VirtualKey::A);
+        assert_eq!(events.key.physical_key(), PhysicalKey::KeyA);
+        assert_eq!(events.keysym_name, "a");
+        assert_eq!(chars(&events), "a");
+        assert!(events.modifiers_changed.is_none());
+
+        let events = release(&mut state, AC01);
+        assert!(events.key.is_up());
+        assert!(events.chars.is_empty());
+    }
+
+    #[test]
+    fn shift_keeps_the_key() {
+        let mut state = state();
+        let events = press(&mut state, RTSH);
+        assert_eq!(events.key.key(), VirtualKey::RightShift);
+        assert_eq!(events.key.location(), KeyLocation::Right);
+        assert_eq!(
+            events.modifiers_changed.map(|event| event.modifiers()),
+            Some(Modifiers::RIGHT_SHIFT)
+        );
+
+        let events = press(&mut state, AC01);
+        assert_eq!(events.key.key(), VirtualKey::A);
+        assert_eq!(events.key.modifiers(), Modifiers::RIGHT_SHIFT);
+        assert_eq!(events.keysym_name, "A");
+        assert_eq!(chars(&events), "A");
+
+        let events = release(&mut state, RTSH);
+        assert_eq!(
+            events.modifiers_changed.map(|event| event.modifiers()),
+            Some(Modifiers::empty())
+        );
+    }
+
+    #[test]
+    fn dead_keys() {
+        let mut state = state();
+        let events = press(&mut state, AE12);
+        assert_eq!(events.key.dead_key(), Some('´'));
+        assert_eq!(events.keysym_name, "dead_acute");
+        assert!(events.chars.is_empty());
+        release(&mut state, AE12);
+
+        press(&mut state, LFSH);
+        let events = press(&mut state, AE12);
+        assert_eq!(events.key.dead_key(), Some('`'));
+        assert_eq!(events.keysym_name, "dead_grave");
+    }
+
+    #[test]
+    fn alt_gr() {
+        let mut state = state();
+        let events = press(&mut state, RALT);
+        assert_eq!(events.key.key(), VirtualKey::RightAlt);
+        assert_eq!(
+            events.modifiers_changed.map(|event| event.modifiers()),
+            Some(Modifiers::RIGHT_ALT)
+        );
+
+        let events = press(&mut state, AD03);
+        assert_eq!(events.key.key(), VirtualKey::E);
+        assert_eq!(chars(&events), "€");
+    }
+
+    #[test]
+    fn num_lock() {
+        let mut state = state();
+        let events = press(&mut state, KP7);
+        assert_eq!(events.key.key(), VirtualKey::Home);
+        assert_eq!(events.key.location(), KeyLocation::Numpad);
+        assert!(events.chars.is_empty());
+        release(&mut state, KP7);
+
+        let events = press(&mut state, NMLK);
+        assert_eq!(events.key.key(), VirtualKey::NumLock);
+        assert_eq!(events.key.physical_key(), PhysicalKey::NumLock);
+        assert_eq!(state.modifiers(), Modifiers::NUM_LOCK);
+        release(&mut state, NMLK);
+
+        let events = press(&mut state, KP7);
+        assert_eq!(events.key.key(), VirtualKey::Numpad7);
+        assert_eq!(events.key.physical_key(), PhysicalKey::Numpad7);
+        assert_eq!(chars(&events), "7");
+    }
+
+    #[test]
+    fn unknown_key_carries_the_keysym() {
+        let mut state = state();
+        let events = press(&mut state, I208);
+        assert_eq!(events.key.key(), VirtualKey::Unknown(0xFF));
+        assert_eq!(events.keysym, keysyms::KEY_XF86Launch5);
+        assert_eq!(events.keysym_name, "XF86Launch5");
+    }
+
+    #[test]
+    fn repeat() {
+        let mut state = state();
+        press(&mut state, AC01);
+        let events = state.key(AC01, KeyState::Repeat, timestamp());
+        assert_eq!(events.chars.len(), 1);
+        assert!(events.chars[0].repeat());
+    }
+
+    #[test]
+    fn reset_keeps_locks() {
+        let mut state = state();
+        press(&mut state, CAPS);
+        release(&mut state, CAPS);
+        press(&mut state, LFSH);
+        assert_eq!(
+            state.modifiers(),
+            Modifiers::LEFT_SHIFT | Modifiers::CAPS_LOCK
+        );
+
+        let event = state.reset(timestamp());
+        assert_eq!(
+            event.map(|event| event.modifiers()),
+            Some(Modifiers::CAPS_LOCK)
+        );
+        assert_eq!(chars(&press(&mut state, AC01)), "A");
+    }
+
+    #[test]
+    fn update_mask() {
+        let mut state = state();
+        // Shift depressed, as a compositor would send it.
+        let event = state.update_mask(1, 0, 0, 0, timestamp());
+        assert_eq!(
+            event.map(|event| event.modifiers()),
+            Some(Modifiers::LEFT_SHIFT)
+        );
+        assert_eq!(chars(&press(&mut state, AD03)), "E");
+        let events = press(&mut state, ESC);
+        assert_eq!(events.key.key(), VirtualKey::Escape);
+    }
+
+    #[test]
+    fn scan_codes() {
+        let cases = [
+            (1, PhysicalKey::Escape),
+            (30, PhysicalKey::KeyA),
+            (69, PhysicalKey::NumLock),
+            (71, PhysicalKey::Numpad7),
+            (83, PhysicalKey::NumpadDecimal),
+            (96, PhysicalKey::NumpadEnter),
+            (99, PhysicalKey::PrintScreen),
+            (119, PhysicalKey::Pause),
+            (183, PhysicalKey::F13),
+            (194, PhysicalKey::F24),
+            (0, PhysicalKey::Unidentified),
+            (240, PhysicalKey::Unidentified),
+        ];
+        for (code, key) in cases {
+            assert_eq!(
+                PhysicalKey::from_scan_code(evdev_to_scan_code(code)),
+                key,
+                "{}",
+                code
+            );
+        }
+    }
+}
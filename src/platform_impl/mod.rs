@@ -18,3 +18,10 @@
 mod windows;
 #[cfg(target_os = "windows")]
 pub use windows::*;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub(crate) use linux::*;
+#[cfg(all(target_os = "linux", feature = "xkb"))]
+pub use linux::{XkbError, XkbKeyEvents, XkbKeymap, XkbState, EVDEV_OFFSET};
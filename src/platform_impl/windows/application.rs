@@ -15,22 +15,40 @@
  */
 
 use crate::*;
+use super::keyboard_layout::*;
+use super::time::*;
 use super::win32;
 use super::window::*;
 
 use std::ffi::c_void;
 use std::pin::Pin;
 use std::sync::{Arc, RwLock};
+use std::thread::{self, ThreadId};
 use std::ops::Deref;
 
+/// The first id used for global hotkeys. Ids from 0xC000 are reserved for shared libraries.
+const FIRST_HOTKEY_ID: i32 = 1;
+
 pub struct Application {
     windows: RwLock<Vec<Pin<Arc<Window>>>>,
+    hotkeys: RwLock<Vec<(HotkeyId, Shortcut)>>,
+    thread: ThreadId,
+    clipboard: Clipboard,
+    ole_initialized: bool,
 }
 
 impl Application {
     fn default() -> Self {
+        // OLE provides drag and drop. It fails if COM was initialized for multithreading on the
+        // thread, in which case windows don't accept drops.
+        let ole_initialized = unsafe { win32::OleInitialize(None) }.is_ok();
+
         Self {
             windows: RwLock::new(Vec::new()),
+            hotkeys: RwLock::new(Vec::new()),
+            thread: thread::current().id(),
+            clipboard: Clipboard::new(),
+            ole_initialized,
         }
     }
 
@@ -40,6 +58,73 @@ impl Application {
         }
     }
 
+    /// Returns the keyboard layout that is currently active.
+    pub fn keyboard_layout(&self) -> KeyboardLayout {
+        current_keyboard_layout()
+    }
+
+    pub fn clipboard(&self) -> &Clipboard {
+        &self.clipboard
+    }
+
+    /// Registers a shortcut that is reported to `ApplicationController::on_global_hotkey` while
+    /// any application has focus. Holding the keys doesn't repeat the hotkey.
+    ///
+    /// The system posts hotkeys to the thread that registered them, so they can only be
+    /// registered from the thread running the application, e.g. from a controller callback.
+    pub fn register_global_hotkey(&self, shortcut: Shortcut) -> Result<HotkeyId, HotkeyError> {
+        self.check_hotkey_thread()?;
+        let vkey = hotkey_virtual_key(shortcut.key())?;
+
+        let mut hotkeys = self.hotkeys.write().unwrap();
+        let id = (FIRST_HOTKEY_ID..)
+            .map(HotkeyId::new)
+            .find(|id| hotkeys.iter().all(|(used, _)| used != id))
+            .unwrap();
+
+        unsafe {
+            win32::RegisterHotKey(
+                win32::HWND::default(),
+                id.native(),
+                shortcut_to_hotkey_modifiers(shortcut.modifiers()),
+                vkey,
+            )
+        }
+        .map_err(|error| {
+            if error.code() == win32::ERROR_HOTKEY_ALREADY_REGISTERED.to_hresult() {
+                HotkeyError::AlreadyRegistered(shortcut)
+            } else {
+                HotkeyError::Os(error.code().0)
+            }
+        })?;
+
+        hotkeys.push((id, shortcut));
+        Ok(id)
+    }
+
+    /// Unregisters a hotkey, which like registering it has to be done from the thread running
+    /// the application.
+    pub fn unregister_global_hotkey(&self, id: HotkeyId) -> Result<(), HotkeyError> {
+        self.check_hotkey_thread()?;
+        let mut hotkeys = self.hotkeys.write().unwrap();
+        let index = hotkeys
+            .iter()
+            .position(|(used, _)| *used == id)
+            .ok_or(HotkeyError::NotRegistered(id))?;
+
+        unsafe { win32::UnregisterHotKey(win32::HWND::default(), id.native()) }
+            .map_err(|error| HotkeyError::Os(error.code().0))?;
+        hotkeys.remove(index);
+        Ok(())
+    }
+
+    fn check_hotkey_thread(&self) -> Result<(), HotkeyError> {
+        if thread::current().id() != self.thread {
+            return Err(HotkeyError::WrongThread);
+        }
+        Ok(())
+    }
+
     pub fn wait_for_events(&self) {
         unsafe { win32::WaitMessage().unwrap() };
     }
@@ -51,15 +136,24 @@ impl Application {
         let mut msg = win32::MSG::default();
         loop {
             controller.before_window_events(&app);
-            while unsafe { win32::PeekMessageA(&mut msg, None, 0, 0, win32::PM_REMOVE).as_bool() } {
+            while unsafe { win32::PeekMessageW(&mut msg, None, 0, 0, win32::PM_REMOVE).as_bool() } {
+                // Hotkeys registered without a window are posted to the thread.
+                if msg.message == win32::WM_HOTKEY && msg.hwnd.0 == 0 {
+                    app.dispatch_global_hotkey(&controller, &msg);
+                    continue;
+                }
+
                 unsafe {
                     win32::TranslateMessage(&msg);
-                    win32::DispatchMessageA(&msg);
+                    win32::DispatchMessageW(&msg);
                 }
             }
             if msg.message == win32::WM_QUIT {
                 break;
             }
+            if app.clipboard.take_changed() {
+                controller.on_clipboard_changed(&app);
+            }
 
             controller.after_window_events(&app);
         }
@@ -67,16 +161,28 @@ impl Application {
         controller.on_exit(&app)
     }
 
+    fn dispatch_global_hotkey<C: ApplicationController>(&self, controller: &C, msg: &win32::MSG) {
+        let id = HotkeyId::new(msg.wParam.0 as i32);
+        let shortcut = self
+            .hotkeys
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(used, _)| *used == id)
+            .map(|(_, shortcut)| *shortcut);
+
+        if let Some(shortcut) = shortcut {
+            let timestamp = Timestamp::from_wrapping_millis(msg.time, current_timestamp());
+            controller.on_global_hotkey(self, &GlobalHotkeyEvent::new(id, shortcut, timestamp));
+        }
+    }
+
     pub fn create_window<C: WindowController + 'static>(&self, controller: C) -> Result<(), ()> {
         let config = controller.get_config();
         let (style, style_ex) = create_window_style(&config);
-        let title = std::ffi::CString::new(config.title).unwrap();
+        let title = win32::HSTRING::from(config.title.as_str());
 
-        let controller = Box::new(controller);
-        let window = Arc::pin(Window {
-            hwnd: win32::HWND::default(),
-            controller,
-        });
+        let window = Arc::pin(Window::new(Box::new(controller), &config));
 
         let (width, height) = {
             let size = config.size.unwrap_or(Size {
@@ -95,10 +201,10 @@ impl Application {
         };
 
         let hwnd = unsafe {
-            win32::CreateWindowExA(
+            win32::CreateWindowExW(
                 style_ex,
-                win32::PCSTR::from_raw(WINDOW_CLASS.name().to_bytes_with_nul().as_ptr()),
-                win32::PCSTR::from_raw(title.as_bytes().as_ptr()),
+                WINDOW_CLASS.name(),
+                &title,
                 style,
                 config
                     .pos
@@ -124,3 +230,83 @@ impl Application {
         Ok(())
     }
 }
+
+impl Drop for Application {
+    fn drop(&mut self) {
+        for (id, _) in self.hotkeys.get_mut().unwrap().drain(..) {
+            let _ = unsafe { win32::UnregisterHotKey(win32::HWND::default(), id.native()) };
+        }
+        if self.ole_initialized {
+            unsafe { win32::OleUninitialize() };
+        }
+    }
+}
+
+/// Returns the virtual-key code to register a hotkey for. The keys that share a code with another
+/// key, such as `NumpadEnter`, can't be told apart by `RegisterHotKey`.
+fn hotkey_virtual_key(key: VirtualKey) -> Result<u32, HotkeyError> {
+    let vkey = key.to_native();
+    if vkey > 0xFF {
+        return Err(HotkeyError::UnsupportedKey(key));
+    }
+    Ok(vkey)
+}
+
+fn shortcut_to_hotkey_modifiers(modifiers: Modifiers) -> win32::HOT_KEY_MODIFIERS {
+    let mut hotkey_modifiers = win32::MOD_NOREPEAT;
+    if modifiers.control() {
+        hotkey_modifiers |= win32::MOD_CONTROL;
+    }
+    if modifiers.alt() {
+        hotkey_modifiers |= win32::MOD_ALT;
+    }
+    if modifiers.shift() {
+        hotkey_modifiers |= win32::MOD_SHIFT;
+    }
+    if modifiers.super_key() {
+        hotkey_modifiers |= win32::MOD_WIN;
+    }
+    hotkey_modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkey_modifiers() {
+        assert_eq!(
+            shortcut_to_hotkey_modifiers(Modifiers::empty()),
+            win32::MOD_NOREPEAT
+        );
+        assert_eq!(
+            shortcut_to_hotkey_modifiers(Modifiers::CONTROL | Modifiers::SHIFT),
+            win32::MOD_NOREPEAT | win32::MOD_CONTROL | win32::MOD_SHIFT
+        );
+        assert_eq!(
+            shortcut_to_hotkey_modifiers(Modifiers::ALT | Modifiers::SUPER),
+            win32::MOD_NOREPEAT | win32::MOD_ALT | win32::MOD_WIN
+        );
+
+        // Either side registers the modifier, and the lock keys are ignored.
+        assert_eq!(
+            shortcut_to_hotkey_modifiers(Modifiers::RIGHT_CONTROL | Modifiers::CAPS_LOCK),
+            win32::MOD_NOREPEAT | win32::MOD_CONTROL
+        );
+        assert_eq!(
+            shortcut_to_hotkey_modifiers(Modifiers::LEFT_ALT | Modifiers::NUM_LOCK),
+            win32::MOD_NOREPEAT | win32::MOD_ALT
+        );
+    }
+
+    #[test]
+    fn hotkey_keys() {
+        assert_eq!(hotkey_virtual_key(VirtualKey::A), Ok(0x41));
+        assert_eq!(hotkey_virtual_key(VirtualKey::F12), Ok(0x7B));
+        assert_eq!(hotkey_virtual_key(VirtualKey::Enter), Ok(0x0D));
+        assert_eq!(
+            hotkey_virtual_key(VirtualKey::NumpadEnter),
+            Err(HotkeyError::UnsupportedKey(VirtualKey::NumpadEnter))
+        );
+    }
+}
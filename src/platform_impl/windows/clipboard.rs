@@ -0,0 +1,640 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+use super::win32;
+
+use std::iter;
+use std::mem::size_of;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// How often opening the clipboard is attempted, since it fails while another application has
+/// it open.
+const OPEN_ATTEMPTS: u32 = 5;
+
+pub(crate) const CF_UNICODETEXT: u32 = win32::CF_UNICODETEXT.0 as u32;
+const CF_DIBV5: u32 = win32::CF_DIBV5.0 as u32;
+const LCS_SRGB: u32 = 0x7352_4742;
+
+/// The system clipboard, shared with other applications.
+///
+/// Changes, including the ones made by the application itself, are reported to
+/// `ApplicationController::on_clipboard_changed`.
+pub struct Clipboard {
+    hwnd: win32::HWND,
+    sequence: AtomicU32,
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Self {
+        // A message-only window owns the data the application puts on the clipboard. It also
+        // receives WM_CLIPBOARDUPDATE, which wakes the message loop when the clipboard changes.
+        let hwnd = unsafe {
+            win32::CreateWindowExW(
+                win32::WINDOW_EX_STYLE::default(),
+                win32::w!("STATIC"),
+                win32::PCWSTR::null(),
+                win32::WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                win32::HWND_MESSAGE,
+                win32::HMENU::default(),
+                win32::HINSTANCE::default(),
+                None,
+            )
+        };
+        if hwnd.0 != 0 {
+            let _ = unsafe { win32::AddClipboardFormatListener(hwnd) };
+        }
+
+        Self {
+            hwnd,
+            sequence: AtomicU32::new(unsafe { win32::GetClipboardSequenceNumber() }),
+        }
+    }
+
+    /// Returns whether the clipboard changed since the last call.
+    pub(crate) fn take_changed(&self) -> bool {
+        let sequence = unsafe { win32::GetClipboardSequenceNumber() };
+        self.sequence.swap(sequence, Ordering::Relaxed) != sequence
+    }
+
+    pub fn text(&self) -> Result<Option<String>, ClipboardError> {
+        let _open = self.open()?;
+        Ok(get_data(CF_UNICODETEXT)?.map(|data| utf16_to_text(&data)))
+    }
+
+    pub fn image(&self) -> Result<Option<RgbaImage>, ClipboardError> {
+        // Bitmaps put on the clipboard in other formats are converted to CF_DIBV5 by the system.
+        let _open = self.open()?;
+        match get_data(CF_DIBV5)? {
+            Some(dib) => dib_to_image(&dib).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the data in a custom format, see `ClipboardData::Custom`.
+    pub fn data(&self, format: &str) -> Result<Option<Vec<u8>>, ClipboardError> {
+        let (format, encoding) = register_format(format)?;
+        let _open = self.open()?;
+        match get_data(format)? {
+            Some(data) => decode_data(encoding, &data).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Replaces the contents of the clipboard with data in one or more formats, letting other
+    /// applications pick the format they understand best.
+    pub fn set(&self, contents: &[ClipboardData]) -> Result<(), ClipboardError> {
+        // The data is converted first, to keep the clipboard open as briefly as possible.
+        let contents = contents
+            .iter()
+            .map(|data| {
+                Ok(match data {
+                    ClipboardData::Text(text) => (CF_UNICODETEXT, text_to_utf16(text)),
+                    ClipboardData::Image(image) => (CF_DIBV5, image_to_dib(image)),
+                    ClipboardData::Custom(format, data) => {
+                        let (format, encoding) = register_format(format)?;
+                        (format, encode_data(encoding, data))
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, ClipboardError>>()?;
+
+        let _open = self.open()?;
+        unsafe { win32::EmptyClipboard() }.map_err(os_error)?;
+        for (format, data) in contents {
+            set_data(format, &data)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_text(&self, text: &str) -> Result<(), ClipboardError> {
+        self.set(&[ClipboardData::Text(text.to_string())])
+    }
+
+    pub fn set_image(&self, image: &RgbaImage) -> Result<(), ClipboardError> {
+        self.set(&[ClipboardData::Image(image.clone())])
+    }
+
+    pub fn set_data(&self, format: &str, data: &[u8]) -> Result<(), ClipboardError> {
+        self.set(&[ClipboardData::Custom(format.to_string(), data.to_vec())])
+    }
+
+    pub fn clear(&self) -> Result<(), ClipboardError> {
+        let _open = self.open()?;
+        unsafe { win32::EmptyClipboard() }.map_err(os_error)
+    }
+
+    fn open(&self) -> Result<OpenClipboard, ClipboardError> {
+        for attempt in 0..OPEN_ATTEMPTS {
+            if attempt > 0 {
+                thread::sleep(Duration::from_millis(5 << attempt));
+            }
+            if unsafe { win32::OpenClipboard(self.hwnd) }.is_ok() {
+                return Ok(OpenClipboard);
+            }
+        }
+        Err(ClipboardError::Unavailable)
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        if self.hwnd.0 != 0 {
+            unsafe {
+                let _ = win32::RemoveClipboardFormatListener(self.hwnd);
+                let _ = win32::DestroyWindow(self.hwnd);
+            }
+        }
+    }
+}
+
+/// Closes the clipboard when dropped.
+struct OpenClipboard;
+
+impl Drop for OpenClipboard {
+    fn drop(&mut self) {
+        let _ = unsafe { win32::CloseClipboard() };
+    }
+}
+
+fn os_error(error: win32::Error) -> ClipboardError {
+    ClipboardError::Os(error.code().0)
+}
+
+/// How the data of a custom format is laid out on the clipboard. The system rounds the size of
+/// clipboard data up, so the length of the data is kept in the data itself.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DataEncoding {
+    /// The CF_HTML layout, a header holding the offsets of an HTML document and the fragment
+    /// within it that was copied.
+    Html,
+    /// Text that ends at the first nul byte.
+    NulTerminated,
+    /// A format such as PNG that records its own length, so that trailing bytes are ignored.
+    Raw,
+    /// The data preceded by its length as a little-endian `u64`, for formats registered by the
+    /// application.
+    LengthPrefixed,
+}
+
+/// Registers a custom format, using the names and layouts other Windows applications use for
+/// well-known MIME types.
+fn register_format(format: &str) -> Result<(u32, DataEncoding), ClipboardError> {
+    let (name, encoding) = match format {
+        "text/html" => ("HTML Format", DataEncoding::Html),
+        "text/rtf" => ("Rich Text Format", DataEncoding::NulTerminated),
+        "image/png" => ("PNG", DataEncoding::Raw),
+        name => (name, DataEncoding::LengthPrefixed),
+    };
+    match unsafe { win32::RegisterClipboardFormatW(&win32::HSTRING::from(name)) } {
+        0 => Err(os_error(win32::Error::from_win32())),
+        format => Ok((format, encoding)),
+    }
+}
+
+fn encode_data(encoding: DataEncoding, data: &[u8]) -> Vec<u8> {
+    match encoding {
+        DataEncoding::Html => html_to_cf_html(data),
+        DataEncoding::NulTerminated => data.iter().copied().chain(iter::once(0)).collect(),
+        DataEncoding::Raw => data.to_vec(),
+        DataEncoding::LengthPrefixed => (data.len() as u64)
+            .to_le_bytes()
+            .into_iter()
+            .chain(data.iter().copied())
+            .collect(),
+    }
+}
+
+fn decode_data(encoding: DataEncoding, data: &[u8]) -> Result<Vec<u8>, ClipboardError> {
+    match encoding {
+        DataEncoding::Html => cf_html_to_html(data),
+        DataEncoding::NulTerminated => Ok(data.split(|&b| b == 0).next().unwrap().to_vec()),
+        DataEncoding::Raw => Ok(data.to_vec()),
+        DataEncoding::LengthPrefixed => {
+            if data.len() < 8 {
+                return Err(ClipboardError::MalformedData);
+            }
+            let (len, data) = data.split_at(8);
+            usize::try_from(u64::from_le_bytes(len.try_into().unwrap()))
+                .ok()
+                .and_then(|len| data.get(..len))
+                .map(<[u8]>::to_vec)
+                .ok_or(ClipboardError::MalformedData)
+        }
+    }
+}
+
+const CF_HTML_FRAGMENT_START: &str = "<html><body>\r\n<!--StartFragment-->";
+const CF_HTML_FRAGMENT_END: &str = "<!--EndFragment-->\r\n</body></html>";
+
+/// Wraps an HTML fragment in a document with the CF_HTML header in front of it.
+fn html_to_cf_html(fragment: &[u8]) -> Vec<u8> {
+    // The offsets are written with a fixed width, so the header's length doesn't depend on them.
+    let header_len = cf_html_header(0, 0, 0, 0).len();
+    let start_fragment = header_len + CF_HTML_FRAGMENT_START.len();
+    let end_fragment = start_fragment + fragment.len();
+    let end_html = end_fragment + CF_HTML_FRAGMENT_END.len();
+
+    let mut data = cf_html_header(header_len, end_html, start_fragment, end_fragment).into_bytes();
+    data.extend_from_slice(CF_HTML_FRAGMENT_START.as_bytes());
+    data.extend_from_slice(fragment);
+    data.extend_from_slice(CF_HTML_FRAGMENT_END.as_bytes());
+    data
+}
+
+fn cf_html_header(
+    start_html: usize,
+    end_html: usize,
+    start_fragment: usize,
+    end_fragment: usize,
+) -> String {
+    format!(
+        "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\n\
+         EndFragment:{:010}\r\n",
+        start_html, end_html, start_fragment, end_fragment
+    )
+}
+
+/// Returns the fragment of CF_HTML data, or the whole document for data without one.
+fn cf_html_to_html(data: &[u8]) -> Result<Vec<u8>, ClipboardError> {
+    // The header is ASCII and ends where the document starts, but documents may leave out the
+    // offsets of the document, which are optional since version 1.0.
+    let offset = |name: &str| {
+        data.split(|&b| b == b'\n')
+            .map_while(|line| std::str::from_utf8(line).ok())
+            .take_while(|line| !line.trim_start().starts_with('<'))
+            .find_map(|line| line.trim_end().strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.parse::<usize>().ok())
+    };
+    let range = match (offset("StartFragment"), offset("EndFragment")) {
+        (Some(start), Some(end)) => start..end,
+        _ => match (offset("StartHTML"), offset("EndHTML")) {
+            (Some(start), Some(end)) => start..end,
+            _ => return Err(ClipboardError::MalformedData),
+        },
+    };
+    data.get(range)
+        .map(<[u8]>::to_vec)
+        .ok_or(ClipboardError::MalformedData)
+}
+
+/// Copies the data in a format off the open clipboard.
+fn get_data(format: u32) -> Result<Option<Vec<u8>>, ClipboardError> {
+    if unsafe { win32::IsClipboardFormatAvailable(format) }.is_err() {
+        return Ok(None);
+    }
+
+    let handle = unsafe { win32::GetClipboardData(format) }.map_err(os_error)?;
+    read_global(win32::HGLOBAL(handle.0 as _))
+        .map(Some)
+        .map_err(os_error)
+}
+
+/// Copies the contents of global memory, as used by the clipboard and OLE data transfers.
+pub(crate) fn read_global(global: win32::HGLOBAL) -> Result<Vec<u8>, win32::Error> {
+    unsafe {
+        let data = win32::GlobalLock(global);
+        if data.is_null() {
+            return Err(win32::Error::from_win32());
+        }
+        let copy = slice::from_raw_parts(data as *const u8, win32::GlobalSize(global)).to_vec();
+        let _ = win32::GlobalUnlock(global);
+        Ok(copy)
+    }
+}
+
+/// Puts data in a format on the open clipboard, which takes ownership of the memory.
+fn set_data(format: u32, data: &[u8]) -> Result<(), ClipboardError> {
+    let global = write_global(data).map_err(os_error)?;
+    if let Err(error) = unsafe { win32::SetClipboardData(format, win32::HANDLE(global.0 as isize)) }
+    {
+        let _ = unsafe { win32::GlobalFree(global) };
+        return Err(os_error(error));
+    }
+    Ok(())
+}
+
+/// Copies data to newly allocated global memory, which the caller must free unless its
+/// ownership is passed on.
+pub(crate) fn write_global(data: &[u8]) -> Result<win32::HGLOBAL, win32::Error> {
+    unsafe {
+        let global = win32::GlobalAlloc(win32::GMEM_MOVEABLE, data.len())?;
+        let memory = win32::GlobalLock(global);
+        if memory.is_null() {
+            let error = win32::Error::from_win32();
+            let _ = win32::GlobalFree(global);
+            return Err(error);
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), memory as *mut u8, data.len());
+        let _ = win32::GlobalUnlock(global);
+        Ok(global)
+    }
+}
+
+/// Decodes nul-terminated UTF-16 text.
+pub(crate) fn utf16_to_text(data: &[u8]) -> String {
+    let text = data
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .take_while(|&c| c != 0)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&text)
+}
+
+pub(crate) fn text_to_utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16()
+        .chain(iter::once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+/// Converts an image to a bottom-up 32-bit DIB with an alpha channel. Top-down bitmaps would be
+/// smaller to write, but some applications paste them upside down.
+fn image_to_dib(image: &RgbaImage) -> Vec<u8> {
+    let header = win32::BITMAPV5HEADER {
+        bV5Size: size_of::<win32::BITMAPV5HEADER>() as u32,
+        bV5Width: image.width() as i32,
+        bV5Height: image.height() as i32,
+        bV5Planes: 1,
+        bV5BitCount: 32,
+        bV5Compression: win32::BI_BITFIELDS,
+        bV5SizeImage: image.pixels().len() as u32,
+        bV5RedMask: 0x00FF_0000,
+        bV5GreenMask: 0x0000_FF00,
+        bV5BlueMask: 0x0000_00FF,
+        bV5AlphaMask: 0xFF00_0000,
+        bV5CSType: LCS_SRGB,
+        bV5Intent: win32::LCS_GM_IMAGES as u32,
+        ..Default::default()
+    };
+
+    let mut dib = Vec::with_capacity(header.bV5Size as usize + image.pixels().len());
+    dib.extend_from_slice(unsafe {
+        slice::from_raw_parts(&header as *const _ as *const u8, header.bV5Size as usize)
+    });
+
+    let stride = image.width() as usize * 4;
+    if stride > 0 {
+        for row in image.pixels().chunks_exact(stride).rev() {
+            for pixel in row.chunks_exact(4) {
+                dib.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        }
+    }
+    dib
+}
+
+/// Converts a 24 or 32-bit DIB, with any of the header versions, to an image.
+fn dib_to_image(dib: &[u8]) -> Result<RgbaImage, ClipboardError> {
+    if dib.len() < size_of::<win32::BITMAPINFOHEADER>() {
+        return Err(ClipboardError::UnsupportedImage);
+    }
+    let header = unsafe { ptr::read_unaligned(dib.as_ptr() as *const win32::BITMAPINFOHEADER) };
+    let read_u32 = |offset: usize| {
+        dib.get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(ClipboardError::UnsupportedImage)
+    };
+
+    let header_size = header.biSize as usize;
+    let bit_count = header.biBitCount as usize;
+    if header.biWidth <= 0 || !(bit_count == 24 || bit_count == 32) {
+        return Err(ClipboardError::UnsupportedImage);
+    }
+
+    // The masks of bitfield bitmaps follow the original header, or are part of later versions.
+    // The sizes come from another application, so arithmetic on them is checked.
+    let mut data_offset = (header.biClrUsed as usize)
+        .checked_mul(4)
+        .and_then(|palette| palette.checked_add(header_size))
+        .ok_or(ClipboardError::UnsupportedImage)?;
+    let masks = if header.biCompression == win32::BI_BITFIELDS.0 && bit_count == 32 {
+        let offset = size_of::<win32::BITMAPINFOHEADER>();
+        if header_size == offset {
+            data_offset = data_offset
+                .checked_add(12)
+                .ok_or(ClipboardError::UnsupportedImage)?;
+        }
+        let alpha = match header_size >= offset + 16 {
+            true => read_u32(offset + 12)?,
+            false => 0,
+        };
+        [
+            read_u32(offset)?,
+            read_u32(offset + 4)?,
+            read_u32(offset + 8)?,
+            alpha,
+        ]
+    } else if header.biCompression == win32::BI_RGB.0 {
+        [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0]
+    } else {
+        return Err(ClipboardError::UnsupportedImage);
+    };
+
+    let width = header.biWidth as usize;
+    let height = header.biHeight.unsigned_abs() as usize;
+    let stride = width
+        .checked_mul(bit_count)
+        .map(|bits| bits.div_ceil(32) * 4)
+        .ok_or(ClipboardError::UnsupportedImage)?;
+    let data_end = stride
+        .checked_mul(height)
+        .and_then(|len| len.checked_add(data_offset))
+        .ok_or(ClipboardError::UnsupportedImage)?;
+    let data = dib
+        .get(data_offset..data_end)
+        .ok_or(ClipboardError::UnsupportedImage)?;
+
+    let channel = |value: u32, mask: u32| match mask {
+        0 => 0xFF,
+        mask => {
+            let shift = mask.trailing_zeros();
+            ((value & mask) >> shift) as u64 * 0xFF / (mask >> shift) as u64
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        // Bitmaps are bottom-up unless their height is negative.
+        let row = if header.biHeight < 0 {
+            y
+        } else {
+            height - 1 - y
+        };
+        let row = &data[row * stride..][..width * bit_count / 8];
+        for pixel in row.chunks_exact(bit_count / 8) {
+            let value = match pixel {
+                [b, g, r] => u32::from_le_bytes([*b, *g, *r, 0]),
+                _ => u32::from_le_bytes(pixel.try_into().unwrap()),
+            };
+            pixels.extend(masks.map(|mask| channel(value, mask) as u8));
+        }
+    }
+
+    // Applications often leave the alpha of bitmaps without transparency at zero.
+    if pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+        pixels.chunks_exact_mut(4).for_each(|pixel| pixel[3] = 0xFF);
+    }
+    Ok(RgbaImage::new(width as u32, height as u32, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a `BITMAPINFOHEADER` followed by `pixels`.
+    fn dib(width: i32, height: i32, bit_count: u16, colors_used: u32, pixels: &[u8]) -> Vec<u8> {
+        let header = win32::BITMAPINFOHEADER {
+            biSize: size_of::<win32::BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: height,
+            biPlanes: 1,
+            biBitCount: bit_count,
+            biCompression: win32::BI_RGB.0,
+            biClrUsed: colors_used,
+            ..Default::default()
+        };
+        let mut dib = unsafe {
+            slice::from_raw_parts(&header as *const _ as *const u8, header.biSize as usize)
+        }
+        .to_vec();
+        dib.extend_from_slice(pixels);
+        dib
+    }
+
+    #[test]
+    fn image_round_trip() {
+        let image = RgbaImage::new(
+            2,
+            2,
+            vec![
+                255, 0, 0, 255, 0, 255, 0, 128, //
+                0, 0, 255, 0, 255, 255, 255, 255,
+            ],
+        );
+        assert_eq!(dib_to_image(&image_to_dib(&image)), Ok(image));
+    }
+
+    #[test]
+    fn bottom_up_24_bit() {
+        // Rows are padded to four bytes and stored from the bottom.
+        let pixels = [
+            0, 0, 255, 0, 255, 0, 0, 0, // bottom row: red, green
+            255, 0, 0, 255, 255, 255, 0, 0, // top row: blue, white
+        ];
+        let image = dib_to_image(&dib(2, 2, 24, 0, &pixels)).unwrap();
+        assert_eq!(
+            image.pixels(),
+            [
+                0, 0, 255, 255, 255, 255, 255, 255, //
+                255, 0, 0, 255, 0, 255, 0, 255,
+            ]
+        );
+    }
+
+    #[test]
+    fn html_round_trip() {
+        let fragment = "<b>caf\u{e9}</b>".as_bytes();
+        let data = html_to_cf_html(fragment);
+        assert_eq!(cf_html_to_html(&data), Ok(fragment.to_vec()));
+
+        // The offsets point at the document and the fragment.
+        let text = std::str::from_utf8(&data).unwrap();
+        assert!(text.starts_with("Version:0.9\r\nStartHTML:0000000105\r\n"));
+        assert_eq!(&text[105..117], "<html><body>");
+        assert!(text.ends_with("</html>"));
+
+        // The system may pad the data.
+        let mut padded = data.clone();
+        padded.extend_from_slice(&[0; 16]);
+        assert_eq!(cf_html_to_html(&padded), Ok(fragment.to_vec()));
+    }
+
+    #[test]
+    fn html_from_other_applications() {
+        // Version 1.0 lets the document offsets be left out.
+        let html = "<html><body><!--StartFragment--><p>Hi</p><!--EndFragment--></body></html>";
+        let header = |start: usize, end: usize| {
+            format!(
+                "Version:1.0\r\nStartHTML:-1\r\nEndHTML:-1\r\nStartFragment:{start:03}\r\n\
+                 EndFragment:{end:03}\r\n"
+            )
+        };
+        let start = header(0, 0).len() + html.find("<p>").unwrap();
+        let end = start + "<p>Hi</p>".len();
+        let data = header(start, end) + html;
+        assert_eq!(cf_html_to_html(data.as_bytes()), Ok(b"<p>Hi</p>".to_vec()));
+
+        let malformed = Err(ClipboardError::MalformedData);
+        assert_eq!(cf_html_to_html(b"<html></html>"), malformed);
+        assert_eq!(
+            cf_html_to_html(b"Version:0.9\r\nStartFragment:5\r\nEndFragment:500\r\n"),
+            malformed
+        );
+    }
+
+    #[test]
+    fn custom_data_lengths() {
+        let encoded = encode_data(DataEncoding::LengthPrefixed, b"abc");
+        assert_eq!(encoded, b"\x03\0\0\0\0\0\0\0abc");
+        // The system rounds the size of clipboard data up.
+        let mut padded = encoded.clone();
+        padded.extend_from_slice(&[0; 13]);
+        assert_eq!(
+            decode_data(DataEncoding::LengthPrefixed, &padded),
+            Ok(b"abc".to_vec())
+        );
+        assert_eq!(
+            decode_data(DataEncoding::LengthPrefixed, &encoded[..9]),
+            Err(ClipboardError::MalformedData)
+        );
+        assert_eq!(
+            decode_data(DataEncoding::LengthPrefixed, &[1, 2]),
+            Err(ClipboardError::MalformedData)
+        );
+
+        let rtf = encode_data(DataEncoding::NulTerminated, b"{\\rtf1}");
+        assert_eq!(rtf, b"{\\rtf1}\0");
+        assert_eq!(
+            decode_data(DataEncoding::NulTerminated, &[&rtf[..], &[0; 7]].concat()),
+            Ok(b"{\\rtf1}".to_vec())
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let unsupported = Err(ClipboardError::UnsupportedImage);
+        assert_eq!(dib_to_image(&[0; 8]), unsupported);
+        assert_eq!(dib_to_image(&dib(1, 1, 8, 0, &[0; 4])), unsupported);
+        assert_eq!(dib_to_image(&dib(0, 1, 32, 0, &[])), unsupported);
+        // Too little pixel data for the size.
+        assert_eq!(dib_to_image(&dib(2, 2, 32, 0, &[0; 12])), unsupported);
+        // A palette and image so large that the end of the pixel data overflows.
+        assert_eq!(dib_to_image(&dib(1, 1, 32, u32::MAX, &[0; 4])), unsupported);
+        assert_eq!(
+            dib_to_image(&dib(i32::MAX, i32::MIN, 32, u32::MAX, &[0; 4])),
+            unsupported
+        );
+    }
+}
@@ -0,0 +1,499 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+use super::clipboard::*;
+use super::time::*;
+use super::win32;
+use super::window::*;
+
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::mem::{size_of, ManuallyDrop};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+use std::ptr;
+use std::slice;
+
+/// The key state flag for the alt key, which the drag and drop functions use in place of
+/// `MK_XBUTTON1`.
+const MK_ALT: u32 = 0x20;
+
+/// The format browsers drag URLs in.
+const URI_FORMAT: win32::PCWSTR = win32::w!("UniformResourceLocatorW");
+
+/// The color key of drag images that have an alpha channel instead.
+const CLR_NONE: u32 = 0xFFFF_FFFF;
+
+/// Receives drags over a window and reports them to its controller.
+#[win32::implement(win32::IDropTarget)]
+struct DropTarget {
+    hwnd: win32::HWND,
+    /// The data of the drag over the window, which is only passed to `DragEnter` and `Drop`.
+    data: RefCell<Option<DragData>>,
+    effect: Cell<DropEffect>,
+}
+
+/// Registers a window as a drop target. Requires OLE to be initialized on the thread.
+pub(crate) fn register_drop_target(hwnd: win32::HWND) {
+    let target: win32::IDropTarget = DropTarget {
+        hwnd,
+        data: RefCell::new(None),
+        effect: Cell::new(DropEffect::empty()),
+    }
+    .into();
+    let _ = unsafe { win32::RegisterDragDrop(hwnd, &target) };
+}
+
+pub(crate) fn revoke_drop_target(hwnd: win32::HWND) {
+    let _ = unsafe { win32::RevokeDragDrop(hwnd) };
+}
+
+impl DropTarget {
+    /// Reports a drag event to the controller and returns the effect it chose, limited to the
+    /// effects the source allows.
+    fn dispatch(
+        &self,
+        key_state: win32::MODIFIERKEYS_FLAGS,
+        pt: &win32::POINTL,
+        effect: *mut win32::DROPEFFECT,
+        callback: impl FnOnce(&Window, &DragEvent) -> DropEffect,
+    ) -> win32::Result<()> {
+        let effect = unsafe { effect.as_mut() }.ok_or(win32::Error::from(win32::E_INVALIDARG))?;
+        let allowed = DropEffect::from_bits_truncate(effect.0);
+        let data = self.data.borrow().clone().unwrap_or_default();
+
+        let chosen = match window_from_hwnd(self.hwnd) {
+            Some(window) => {
+                let mut pos = win32::POINT { x: pt.x, y: pt.y };
+                unsafe { win32::ScreenToClient(self.hwnd, &mut pos) };
+                let event = DragEvent::new(
+                    data,
+                    pos.x as f32,
+                    pos.y as f32,
+                    key_state_to_modifiers(key_state),
+                    allowed,
+                    self.effect.get(),
+                    current_timestamp(),
+                );
+                callback(window, &event) & allowed
+            }
+            None => DropEffect::empty(),
+        };
+
+        self.effect.set(chosen);
+        *effect = win32::DROPEFFECT(chosen.bits());
+        Ok(())
+    }
+}
+
+impl win32::IDropTarget_Impl for DropTarget {
+    fn DragEnter(
+        &self,
+        data: Option<&win32::IDataObject>,
+        key_state: win32::MODIFIERKEYS_FLAGS,
+        pt: &win32::POINTL,
+        effect: *mut win32::DROPEFFECT,
+    ) -> win32::Result<()> {
+        self.data.replace(data.map(read_drag_data));
+        self.effect.set(DropEffect::empty());
+        self.dispatch(key_state, pt, effect, |window, event| {
+            window.controller.on_drag_enter(window, event)
+        })
+    }
+
+    fn DragOver(
+        &self,
+        key_state: win32::MODIFIERKEYS_FLAGS,
+        pt: &win32::POINTL,
+        effect: *mut win32::DROPEFFECT,
+    ) -> win32::Result<()> {
+        self.dispatch(key_state, pt, effect, |window, event| {
+            window.controller.on_drag_over(window, event)
+        })
+    }
+
+    fn DragLeave(&self) -> win32::Result<()> {
+        self.data.replace(None);
+        if let Some(window) = window_from_hwnd(self.hwnd) {
+            window
+                .controller
+                .on_drag_leave(window, &DragLeaveEvent::new(current_timestamp()));
+        }
+        Ok(())
+    }
+
+    fn Drop(
+        &self,
+        data: Option<&win32::IDataObject>,
+        key_state: win32::MODIFIERKEYS_FLAGS,
+        pt: &win32::POINTL,
+        effect: *mut win32::DROPEFFECT,
+    ) -> win32::Result<()> {
+        // The data may only be complete once it is dropped.
+        self.data.replace(data.map(read_drag_data));
+        let result = self.dispatch(key_state, pt, effect, |window, event| {
+            window.controller.on_drop(window, event)
+        });
+        self.data.replace(None);
+        result
+    }
+}
+
+fn key_state_to_modifiers(key_state: win32::MODIFIERKEYS_FLAGS) -> Modifiers {
+    // The key state doesn't tell the sides of the modifiers apart.
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::LEFT_SHIFT, key_state.0 & win32::MK_SHIFT.0 != 0);
+    modifiers.set(
+        Modifiers::LEFT_CONTROL,
+        key_state.0 & win32::MK_CONTROL.0 != 0,
+    );
+    modifiers.set(Modifiers::LEFT_ALT, key_state.0 & MK_ALT != 0);
+    modifiers
+}
+
+/// Reads the files, text and URIs of the formats a data object offers.
+fn read_drag_data(data: &win32::IDataObject) -> DragData {
+    let uri_format = unsafe { win32::RegisterClipboardFormatW(URI_FORMAT) };
+
+    DragData {
+        files: read_data(data, win32::CF_HDROP.0 as u32, read_files).unwrap_or_default(),
+        text: read_data(data, CF_UNICODETEXT, |global| {
+            read_global(global).ok().map(|text| utf16_to_text(&text))
+        }),
+        uris: read_data(data, uri_format, |global| {
+            read_global(global).ok().map(|uri| utf16_to_text(&uri))
+        })
+        .into_iter()
+        .collect(),
+    }
+}
+
+/// Reads a format of a data object that is transferred in global memory.
+fn read_data<T>(
+    data: &win32::IDataObject,
+    format: u32,
+    read: impl FnOnce(win32::HGLOBAL) -> Option<T>,
+) -> Option<T> {
+    unsafe {
+        let mut medium = data.GetData(&hglobal_format(format)).ok()?;
+        let value = read(medium.u.hGlobal);
+        win32::ReleaseStgMedium(&mut medium);
+        value
+    }
+}
+
+/// Describes a format transferred in global memory.
+fn hglobal_format(format: u32) -> win32::FORMATETC {
+    win32::FORMATETC {
+        cfFormat: format as u16,
+        ptd: ptr::null_mut(),
+        dwAspect: win32::DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: win32::TYMED_HGLOBAL.0 as u32,
+    }
+}
+
+fn read_files(global: win32::HGLOBAL) -> Option<Vec<PathBuf>> {
+    let hdrop = win32::HDROP(global.0 as isize);
+    let count = unsafe { win32::DragQueryFileW(hdrop, u32::MAX, None) };
+
+    let files = (0..count)
+        .map(|index| {
+            let len = unsafe { win32::DragQueryFileW(hdrop, index, None) } as usize;
+            let mut path = vec![0u16; len + 1];
+            unsafe { win32::DragQueryFileW(hdrop, index, Some(&mut path)) };
+            PathBuf::from(OsString::from_wide(&path[..len]))
+        })
+        .collect();
+    Some(files)
+}
+
+/// Tells `DoDragDrop` when a drag started by the application ends.
+#[win32::implement(win32::IDropSource)]
+struct DropSource;
+
+impl win32::IDropSource_Impl for DropSource {
+    fn QueryContinueDrag(
+        &self,
+        escape_pressed: win32::BOOL,
+        key_state: win32::MODIFIERKEYS_FLAGS,
+    ) -> win32::HRESULT {
+        if escape_pressed.as_bool() {
+            win32::DRAGDROP_S_CANCEL
+        } else if key_state.0 & (win32::MK_LBUTTON.0 | win32::MK_RBUTTON.0) == 0 {
+            win32::DRAGDROP_S_DROP
+        } else {
+            win32::S_OK
+        }
+    }
+
+    fn GiveFeedback(&self, _effect: win32::DROPEFFECT) -> win32::HRESULT {
+        win32::DRAGDROP_S_USEDEFAULTCURSORS
+    }
+}
+
+/// Runs the drag loop for data dragged out of the application, returning the effect of the
+/// drop. `cursor_offset` is the position of the cursor in the drag image.
+pub(crate) fn do_drag_drop(
+    data: &DragData,
+    allowed: DropEffect,
+    image: Option<(&RgbaImage, (u32, u32))>,
+) -> DropEffect {
+    let Ok(object) = create_data_object(data) else {
+        return DropEffect::empty();
+    };
+    if let Some((image, cursor_offset)) = image {
+        set_drag_image(&object, image, cursor_offset);
+    }
+
+    let source: win32::IDropSource = DropSource.into();
+    let mut effect = win32::DROPEFFECT::default();
+    let result = unsafe {
+        win32::DoDragDrop(
+            &object,
+            &source,
+            win32::DROPEFFECT(allowed.bits()),
+            &mut effect,
+        )
+    };
+    match result {
+        win32::DRAGDROP_S_DROP => DropEffect::from_bits_truncate(effect.0),
+        _ => DropEffect::empty(),
+    }
+}
+
+/// Creates a data object holding the files, text and URIs of a drag. The shell's data object is
+/// used since the drag image helper stores the image in it.
+fn create_data_object(data: &DragData) -> win32::Result<win32::IDataObject> {
+    let object: win32::IDataObject =
+        unsafe { win32::SHCreateDataObject(None, None, None::<&win32::IDataObject>) }?;
+
+    if !data.files.is_empty() {
+        set_object_data(
+            &object,
+            win32::CF_HDROP.0 as u32,
+            &files_to_hdrop(&data.files),
+        )?;
+    }
+    if let Some(text) = &data.text {
+        set_object_data(&object, CF_UNICODETEXT, &text_to_utf16(text))?;
+    }
+    // Only a single URI can be dragged in the format browsers use.
+    if let Some(uri) = data.uris.first() {
+        let uri_format = unsafe { win32::RegisterClipboardFormatW(URI_FORMAT) };
+        set_object_data(&object, uri_format, &text_to_utf16(uri))?;
+    }
+    Ok(object)
+}
+
+fn set_object_data(object: &win32::IDataObject, format: u32, data: &[u8]) -> win32::Result<()> {
+    let global = write_global(data)?;
+    let medium = win32::STGMEDIUM {
+        tymed: win32::TYMED_HGLOBAL.0 as u32,
+        u: win32::STGMEDIUM_0 { hGlobal: global },
+        pUnkForRelease: ManuallyDrop::new(None),
+    };
+
+    // The data object takes ownership of the memory if it accepts it.
+    let result = unsafe { object.SetData(&hglobal_format(format), &medium, true) };
+    if result.is_err() {
+        let _ = unsafe { win32::GlobalFree(global) };
+    }
+    result
+}
+
+/// Encodes paths as a `DROPFILES` structure followed by the nul-terminated paths.
+fn files_to_hdrop(files: &[PathBuf]) -> Vec<u8> {
+    let header = win32::DROPFILES {
+        pFiles: size_of::<win32::DROPFILES>() as u32,
+        fWide: true.into(),
+        ..Default::default()
+    };
+
+    let mut paths = Vec::new();
+    for file in files {
+        paths.extend(file.as_os_str().encode_wide());
+        paths.push(0);
+    }
+    paths.push(0);
+
+    let mut hdrop =
+        unsafe { slice::from_raw_parts(&header as *const _ as *const u8, header.pFiles as usize) }
+            .to_vec();
+    hdrop.extend(paths.into_iter().flat_map(u16::to_le_bytes));
+    hdrop
+}
+
+/// Sets the image shown under the cursor during a drag, leaving the default image if the
+/// image can't be set.
+fn set_drag_image(object: &win32::IDataObject, image: &RgbaImage, cursor_offset: (u32, u32)) {
+    let Some(bitmap) = image_to_bitmap(image) else {
+        return;
+    };
+    let drag_image = win32::SHDRAGIMAGE {
+        sizeDragImage: win32::SIZE {
+            cx: image.width() as i32,
+            cy: image.height() as i32,
+        },
+        ptOffset: win32::POINT {
+            x: cursor_offset.0 as i32,
+            y: cursor_offset.1 as i32,
+        },
+        hbmpDragImage: bitmap,
+        crColorKey: win32::COLORREF(CLR_NONE),
+    };
+
+    // The helper owns the bitmap once it is initialized with it.
+    let result = unsafe {
+        win32::CoCreateInstance::<_, win32::IDragSourceHelper>(
+            &win32::CLSID_DragDropHelper,
+            None::<&win32::IUnknown>,
+            win32::CLSCTX_INPROC_SERVER,
+        )
+        .and_then(|helper| helper.InitializeFromBitmap(&drag_image, object))
+    };
+    if result.is_err() {
+        unsafe { win32::DeleteObject(bitmap) };
+    }
+}
+
+/// Converts an image to a top-down bitmap with premultiplied alpha, as drag images use.
+fn image_to_bitmap(image: &RgbaImage) -> Option<win32::HBITMAP> {
+    let info = win32::BITMAPINFO {
+        bmiHeader: win32::BITMAPINFOHEADER {
+            biSize: size_of::<win32::BITMAPINFOHEADER>() as u32,
+            biWidth: image.width() as i32,
+            biHeight: -(image.height() as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: win32::BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let mut bits = ptr::null_mut();
+    let bitmap = unsafe {
+        win32::CreateDIBSection(
+            win32::HDC::default(),
+            &info,
+            win32::DIB_RGB_COLORS,
+            &mut bits,
+            win32::HANDLE::default(),
+            0,
+        )
+    }
+    .ok()?;
+
+    let pixels = unsafe { slice::from_raw_parts_mut(bits as *mut u8, image.pixels().len()) };
+    pixels.copy_from_slice(&image_to_premultiplied_bgra(image));
+    Some(bitmap)
+}
+
+/// Converts RGBA pixels to BGRA pixels with the color channels multiplied by alpha.
+fn image_to_premultiplied_bgra(image: &RgbaImage) -> Vec<u8> {
+    image
+        .pixels()
+        .chunks_exact(4)
+        .flat_map(|rgba| {
+            let premultiply = |channel: u8| (channel as u32 * rgba[3] as u32 / 0xFF) as u8;
+            [
+                premultiply(rgba[2]),
+                premultiply(rgba[1]),
+                premultiply(rgba[0]),
+                rgba[3],
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use windows::Win32::System::Ole::{
+        DROPEFFECT_COPY, DROPEFFECT_LINK, DROPEFFECT_MOVE, DROPEFFECT_SCROLL,
+    };
+
+    #[test]
+    fn drop_effect_bits() {
+        // Drop effects are passed to and from OLE by their bits.
+        assert_eq!(DropEffect::COPY.bits(), DROPEFFECT_COPY.0);
+        assert_eq!(DropEffect::MOVE.bits(), DROPEFFECT_MOVE.0);
+        assert_eq!(DropEffect::LINK.bits(), DROPEFFECT_LINK.0);
+        assert_eq!(
+            DropEffect::from_bits_truncate(DROPEFFECT_SCROLL.0 | DROPEFFECT_MOVE.0),
+            DropEffect::MOVE
+        );
+    }
+
+    #[test]
+    fn key_state() {
+        let key_state = win32::MODIFIERKEYS_FLAGS(
+            win32::MK_SHIFT.0 | win32::MK_CONTROL.0 | win32::MK_LBUTTON.0,
+        );
+        assert_eq!(
+            key_state_to_modifiers(key_state),
+            Modifiers::LEFT_SHIFT | Modifiers::LEFT_CONTROL
+        );
+        assert_eq!(
+            key_state_to_modifiers(win32::MODIFIERKEYS_FLAGS(MK_ALT)),
+            Modifiers::LEFT_ALT
+        );
+    }
+
+    #[test]
+    fn drag_image_pixels() {
+        let image = RgbaImage::new(
+            3,
+            1,
+            vec![
+                255, 128, 0, 255, // opaque
+                255, 128, 0, 128, // half transparent
+                255, 255, 255, 0, // transparent
+            ],
+        );
+        assert_eq!(
+            image_to_premultiplied_bgra(&image),
+            [
+                0, 128, 255, 255, //
+                0, 64, 128, 128, //
+                0, 0, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn hdrop_layout() {
+        let files = [PathBuf::from("C:\\a.txt"), PathBuf::from("C:\\dir\\b")];
+        let hdrop = files_to_hdrop(&files);
+
+        let header = unsafe { ptr::read_unaligned(hdrop.as_ptr() as *const win32::DROPFILES) };
+        assert_eq!(header.pFiles as usize, size_of::<win32::DROPFILES>());
+        assert!(header.fWide.as_bool());
+
+        // The paths follow the header as a list of nul-terminated strings ending with a nul.
+        let paths = hdrop[header.pFiles as usize..]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        let expected = "C:\\a.txt\0C:\\dir\\b\0\0"
+            .encode_utf16()
+            .collect::<Vec<_>>();
+        assert_eq!(paths, expected);
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+use super::virtual_key;
+use super::win32;
+
+const LOCALE_NAME_MAX_LENGTH: usize = 85;
+
+/// Prevents ToUnicodeEx from changing the dead key state of the keyboard.
+const TO_UNICODE_NO_STATE_CHANGE: u32 = 1 << 2;
+
+/// Returns the layout that is active for the calling thread.
+pub(crate) fn current_keyboard_layout() -> KeyboardLayout {
+    let hkl = unsafe { win32::GetKeyboardLayout(0) };
+
+    let mut klid = [0u16; 9];
+    let id = match unsafe { win32::GetKeyboardLayoutNameW(&mut klid) } {
+        Ok(()) => utf16_until_nul(&klid),
+        Err(_) => format!("{:08X}", hkl.0 as u32),
+    };
+
+    // The low word of the layout handle is the language identifier.
+    let lang_id = hkl.0 as u32 & 0xFFFF;
+    let mut name = [0u16; LOCALE_NAME_MAX_LENGTH];
+    let len = unsafe { win32::LCIDToLocaleName(lang_id, Some(&mut name), 0) };
+    let language = if len > 0 {
+        utf16_until_nul(&name)
+    } else {
+        String::new()
+    };
+
+    KeyboardLayout::new(id, language, hkl.0)
+}
+
+pub(crate) fn translate_key(
+    layout: &KeyboardLayout,
+    key: VirtualKey,
+    modifiers: Modifiers,
+) -> Option<char> {
+    const DOWN: u8 = 0x80;
+    const TOGGLED: u8 = 0x01;
+
+    // Deserialized layouts have no handle.
+    let hkl = match layout.native {
+        0 => unsafe { win32::GetKeyboardLayout(0) },
+        native => win32::HKL(native),
+    };
+    let vkey = match key {
+        VirtualKey::NumpadEnter => virtual_key::ENTER,
+        key => key.to_native(),
+    };
+
+    let mut key_state = [0u8; 256];
+    let mut set = |vkey: win32::VIRTUAL_KEY, state: u8| key_state[vkey.0 as usize] |= state;
+    if modifiers.contains(Modifiers::LEFT_SHIFT) {
+        set(win32::VK_LSHIFT, DOWN);
+    }
+    if modifiers.contains(Modifiers::RIGHT_SHIFT) {
+        set(win32::VK_RSHIFT, DOWN);
+    }
+    if modifiers.contains(Modifiers::LEFT_CONTROL) {
+        set(win32::VK_LCONTROL, DOWN);
+    }
+    if modifiers.contains(Modifiers::RIGHT_CONTROL) {
+        set(win32::VK_RCONTROL, DOWN);
+    }
+    if modifiers.contains(Modifiers::LEFT_ALT) {
+        set(win32::VK_LMENU, DOWN);
+    }
+    if modifiers.contains(Modifiers::RIGHT_ALT) {
+        // AltGr is reported by Windows as control and alt held together.
+        set(win32::VK_RMENU, DOWN);
+        set(win32::VK_LCONTROL, DOWN);
+        set(win32::VK_CONTROL, DOWN);
+    }
+    if modifiers.shift() {
+        set(win32::VK_SHIFT, DOWN);
+    }
+    if modifiers.control() {
+        set(win32::VK_CONTROL, DOWN);
+    }
+    if modifiers.alt() {
+        set(win32::VK_MENU, DOWN);
+    }
+    if modifiers.caps_lock() {
+        set(win32::VK_CAPITAL, TOGGLED);
+    }
+    if modifiers.num_lock() {
+        set(win32::VK_NUMLOCK, TOGGLED);
+    }
+
+    let scan_code = unsafe { win32::MapVirtualKeyExW(vkey, win32::MAPVK_VK_TO_VSC, hkl) };
+    let mut buffer = [0u16; 8];
+    let len = unsafe {
+        win32::ToUnicodeEx(
+            vkey,
+            scan_code,
+            &key_state,
+            &mut buffer,
+            TO_UNICODE_NO_STATE_CHANGE,
+            hkl,
+        )
+    };
+
+    // A negative length indicates a dead key, with its diacritic written to the buffer.
+    let len = match len {
+        0 => return None,
+        len if len < 0 => 1,
+        len => len as usize,
+    };
+    char::decode_utf16(buffer[..len].iter().copied())
+        .next()?
+        .ok()
+        .filter(|c| !c.is_control())
+}
+
+/// Returns the diacritic of a dead key for the current keyboard state, which includes the dead
+/// keys on the shift and AltGr levels, without changing the dead key state of the keyboard.
+/// `vkey` is the virtual key code delivered with the key message.
+pub(crate) fn dead_key_char(vkey: u32, scan_code: ScanCode) -> Option<char> {
+    let mut key_state = [0u8; 256];
+    unsafe { win32::GetKeyboardState(&mut key_state) }.ok()?;
+    let hkl = unsafe { win32::GetKeyboardLayout(0) };
+
+    // The extended-key prefix is left out, as bit 15 of the scan code marks a key release.
+    let mut buffer = [0u16; 8];
+    let len = unsafe {
+        win32::ToUnicodeEx(
+            vkey,
+            scan_code & 0xFF,
+            &key_state,
+            &mut buffer,
+            TO_UNICODE_NO_STATE_CHANGE,
+            hkl,
+        )
+    };
+    if len >= 0 {
+        return None;
+    }
+    char::decode_utf16(buffer[..1].iter().copied()).next()?.ok()
+}
+
+fn utf16_until_nul(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
@@ -16,8 +16,15 @@
 
 mod win32;
 mod application;
+mod clipboard;
+mod drag_drop;
+mod keyboard_layout;
+mod time;
 mod window;
 pub mod virtual_key;
 
 pub use application::*;
+pub use clipboard::*;
+pub(crate) use keyboard_layout::*;
+pub(crate) use time::*;
 pub use window::*;
@@ -0,0 +1,32 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::*;
+use super::win32;
+
+use std::time::Duration;
+
+pub(crate) fn current_timestamp() -> Timestamp {
+    Timestamp::from_duration(Duration::from_millis(unsafe { win32::GetTickCount64() }))
+}
+
+/// Returns the time the message being processed was posted. Sent messages, such as `WM_SIZE`,
+/// `WM_SETFOCUS` and the `WM_IME_*` messages, are not stamped and would get the time of an
+/// earlier posted message, so they are stamped with `current_timestamp` instead.
+pub(crate) fn message_timestamp() -> Timestamp {
+    let time = unsafe { win32::GetMessageTime() };
+    Timestamp::from_wrapping_millis(time as u32, current_timestamp())
+}
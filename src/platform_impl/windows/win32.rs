@@ -16,8 +16,38 @@
 
 pub use windows::core::*;
 pub use windows::Win32::Foundation::*;
+pub use windows::Win32::Globalization::{LCIDToLocaleName, HIMC};
 pub use windows::Win32::Graphics::Gdi::*;
+pub use windows::Win32::System::Com::{
+    CoCreateInstance, IDataObject, CLSCTX_INPROC_SERVER, DVASPECT_CONTENT, FORMATETC, STGMEDIUM,
+    STGMEDIUM_0, TYMED_HGLOBAL,
+};
+pub use windows::Win32::System::DataExchange::*;
+pub use windows::Win32::System::Memory::{
+    GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+};
+pub use windows::Win32::System::Ole::{
+    DoDragDrop, IDropSource, IDropSource_Impl, IDropTarget, IDropTarget_Impl, OleInitialize,
+    OleUninitialize, RegisterDragDrop, ReleaseStgMedium, RevokeDragDrop, CF_DIBV5, CF_HDROP,
+    CF_UNICODETEXT, DROPEFFECT,
+};
+pub use windows::Win32::System::SystemServices::{
+    GC_PAN, GC_PAN_WITH_GUTTER, GC_PAN_WITH_INERTIA, GC_PAN_WITH_SINGLE_FINGER_HORIZONTALLY,
+    GC_PAN_WITH_SINGLE_FINGER_VERTICALLY, GC_ROTATE, GC_ZOOM, MK_CONTROL, MK_LBUTTON, MK_RBUTTON,
+    MK_SHIFT, MODIFIERKEYS_FLAGS,
+};
+pub use windows::Win32::System::SystemInformation::GetTickCount64;
+pub use windows::Win32::UI::Input::Ime::*;
+pub use windows::Win32::UI::Input::GetCurrentInputMessageSource;
+pub use windows::Win32::UI::Input::{IMDT_TOUCHPAD, INPUT_MESSAGE_SOURCE};
 pub use windows::Win32::UI::Input::KeyboardAndMouse::*;
+pub use windows::Win32::UI::Input::Pointer::*;
+pub use windows::Win32::UI::Input::Touch::*;
+pub use windows::Win32::UI::Shell::{
+    CLSID_DragDropHelper, DragQueryFileW, IDragSourceHelper, SHCreateDataObject, DROPFILES, HDROP,
+    SHDRAGIMAGE,
+};
+pub use windows::Win32::UI::TextServices::HKL;
 pub use windows::Win32::UI::WindowsAndMessaging::*;
 
 pub const fn lparam_loword(value: LPARAM) -> u16 {
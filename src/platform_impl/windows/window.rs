@@ -15,17 +15,41 @@
  */
 
 use crate::*;
+use super::drag_drop::*;
+use super::keyboard_layout::*;
+use super::time::*;
+use super::virtual_key;
 use super::win32;
 
-use std::ffi::CStr;
+use std::cell::{Cell, RefCell};
+use std::ffi::c_void;
 use std::ptr::NonNull;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use eventify::event::*;
 
 use lazy_static::lazy_static;
 
-static WINDOW_CLASS_NAME: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"winman\0") };
+const WINDOW_CLASS_NAME: win32::PCWSTR = win32::w!("winman");
+
+/// The pressure reported by touchscreens and pens when pressed as firmly as they can detect.
+const POINTER_PRESSURE_MAX: f32 = 1024.0;
+
+/// Marks the extra info of mouse messages synthesized from pen or touch input.
+const MI_WP_SIGNATURE: u32 = 0xFF515700;
+const MI_WP_SIGNATURE_MASK: u32 = 0xFFFFFF00;
+/// Set in the extra info of mouse messages synthesized from touch rather than pen input.
+const MI_WP_TOUCH: u32 = 0x80;
+
+/// The lines scrolled per wheel notch if the system setting can't be used.
+const DEFAULT_WHEEL_LINES: u32 = 3;
+/// The wheel scroll setting that scrolls a page per notch.
+const WHEEL_PAGESCROLL: u32 = u32::MAX;
+/// The factor a touchpad pinch scales by for each notch's worth of wheel delta.
+const PINCH_SCALE_PER_NOTCH: f32 = 1.25;
+/// How long the wheel rests before the next wheel message starts a new gesture.
+const WHEEL_GESTURE_TIMEOUT: Duration = Duration::from_millis(300);
 
 type WindowCloseEvent = Event<()>;
 type WindowCloseHook = EventHook<()>;
@@ -37,8 +61,8 @@ pub(crate) struct WindowClass {
 impl WindowClass {
     fn register() -> Result<Self, ()> {
         let atom = unsafe {
-            win32::RegisterClassExA(&win32::WNDCLASSEXA {
-                cbSize: std::mem::size_of::<win32::WNDCLASSEXA>() as u32,
+            win32::RegisterClassExW(&win32::WNDCLASSEXW {
+                cbSize: std::mem::size_of::<win32::WNDCLASSEXW>() as u32,
                 style: win32::CS_HREDRAW | win32::CS_VREDRAW,
                 lpfnWndProc: Some(window_proc),
                 cbClsExtra: 0,
@@ -47,10 +71,8 @@ impl WindowClass {
                 hIcon: Default::default(),
                 hCursor: Default::default(),
                 hbrBackground: Default::default(),
-                lpszMenuName: win32::PCSTR::null(),
-                lpszClassName: win32::PCSTR::from_raw(
-                    WINDOW_CLASS_NAME.to_bytes_with_nul().as_ptr(),
-                ),
+                lpszMenuName: win32::PCWSTR::null(),
+                lpszClassName: WINDOW_CLASS_NAME,
                 hIconSm: Default::default(),
             })
         };
@@ -60,20 +82,14 @@ impl WindowClass {
         Ok(Self { _atom: atom })
     }
 
-    pub fn name(&self) -> &CStr {
+    pub fn name(&self) -> win32::PCWSTR {
         WINDOW_CLASS_NAME
     }
 }
 
 impl Drop for WindowClass {
     fn drop(&mut self) {
-        unsafe {
-            win32::UnregisterClassA(
-                win32::PCSTR::from_raw(WINDOW_CLASS_NAME.to_bytes_with_nul().as_ptr()),
-                win32::HINSTANCE::default(),
-            )
-        }
-        .unwrap();
+        unsafe { win32::UnregisterClassW(WINDOW_CLASS_NAME, win32::HINSTANCE::default()) }.unwrap();
     }
 }
 
@@ -101,12 +117,58 @@ pub(crate) fn create_window_style(
     (style, style_ex)
 }
 
+/// The last reported state of a gesture. The system reports gestures with absolute values, which
+/// are turned into deltas against this state.
+#[derive(Debug, Default, Copy, Clone)]
+struct GestureProgress {
+    argument: u64,
+    x: i16,
+    y: i16,
+}
+
+/// Whether the wheel messages of the gesture in progress are precise, so that a touchpad or high
+/// resolution wheel that happens to report a whole notch doesn't switch to scrolling lines.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct WheelLatch {
+    precise: bool,
+    timestamp: Timestamp,
+}
+
 pub struct Window {
     pub(crate) hwnd: win32::HWND,
     pub(crate) controller: Box<dyn WindowController>,
+    modifiers: Cell<Modifiers>,
+    focused: Cell<bool>,
+    system_keys: Cell<SystemKeys>,
+    touch_emulates_mouse: Cell<bool>,
+    high_surrogate: Cell<Option<u16>>,
+    ime_cursor_area: Cell<Option<Rect>>,
+    gesture: Cell<GestureProgress>,
+    wheel: Cell<Option<WheelLatch>>,
+    input_state: RefCell<Option<Arc<Mutex<InputState>>>>,
 }
 
 impl Window {
+    pub(crate) fn new(controller: Box<dyn WindowController>, config: &WindowConfig) -> Self {
+        Self {
+            hwnd: win32::HWND::default(),
+            controller,
+            modifiers: Cell::new(Modifiers::empty()),
+            focused: Cell::new(false),
+            system_keys: Cell::new(config.system_keys),
+            touch_emulates_mouse: Cell::new(config.touch_emulates_mouse),
+            high_surrogate: Cell::new(None),
+            ime_cursor_area: Cell::new(None),
+            gesture: Cell::new(GestureProgress::default()),
+            wheel: Cell::new(None),
+            input_state: RefCell::new(None),
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        WindowId::from_raw(self.hwnd.0 as u64)
+    }
+
     pub fn show(&self) {
         unsafe {
             win32::ShowWindow(self.hwnd, win32::SW_SHOWDEFAULT);
@@ -140,11 +202,80 @@ impl Window {
     }
 
     pub fn set_title(&self, title: &str) {
-        let title = std::ffi::CString::new(title).unwrap();
+        unsafe { win32::SetWindowTextW(self.hwnd, &win32::HSTRING::from(title)) }.unwrap();
+    }
+
+    pub fn system_keys(&self) -> SystemKeys {
+        self.system_keys.get()
+    }
+
+    /// Sets which system key behaviours are handled by the platform. System keys are always
+    /// reported to the controller, regardless of this setting.
+    pub fn set_system_keys(&self, system_keys: SystemKeys) {
+        self.system_keys.set(system_keys);
+    }
+
+    pub fn touch_emulates_mouse(&self) -> bool {
+        self.touch_emulates_mouse.get()
+    }
+
+    /// Sets whether touches are also reported as mouse events. Touches are always reported to
+    /// `on_touch`, regardless of this setting.
+    pub fn set_touch_emulates_mouse(&self, emulate: bool) {
+        self.touch_emulates_mouse.set(emulate);
+    }
+
+    /// Returns the label of the key in the user's current keyboard layout, e.g. `"Ö"` for
+    /// `VirtualKey::Colon` on a German layout. Falls back to the canonical key name if the layout
+    /// does not provide one.
+    pub fn key_label(&self, key: VirtualKey) -> String {
+        vkey_to_label(key).unwrap_or_else(|| key.to_string())
+    }
+
+    /// Allows or disallows input method editors for the window. Input methods are allowed by
+    /// default.
+    pub fn set_ime_allowed(&self, allowed: bool) {
+        let flags = if allowed { win32::IACE_DEFAULT } else { 0 };
         unsafe {
-            win32::SetWindowTextA(self.hwnd, win32::PCSTR::from_raw(title.as_bytes().as_ptr()))
+            win32::ImmAssociateContextEx(self.hwnd, win32::HIMC::default(), flags);
         }
-        .unwrap();
+    }
+
+    /// Sets the area of the text cursor in client coordinates, so the input method can place its
+    /// candidate window next to it without covering it.
+    pub fn set_ime_cursor_area(&self, area: Rect) {
+        self.ime_cursor_area.set(Some(area));
+        apply_ime_cursor_area(self.hwnd, area);
+    }
+
+    /// Attaches an input state that the window keeps up to date before dispatching input
+    /// events to the controller. Replaces any previously attached state.
+    pub fn attach_input_state(&self, state: Arc<Mutex<InputState>>) {
+        self.input_state.replace(Some(state));
+    }
+
+    pub fn detach_input_state(&self) -> Option<Arc<Mutex<InputState>>> {
+        self.input_state.take()
+    }
+
+    /// Starts dragging data out of the window, usually after the mouse moved some distance with
+    /// a button held. Returns once the data is dropped, with the effect the drop target chose,
+    /// or once the drag is cancelled, with no effect. Data moved out of the window should only be
+    /// removed once the drop reports `DropEffect::MOVE`.
+    pub fn start_drag(&self, data: DragData, allowed: DropEffect) -> DropEffect {
+        do_drag_drop(&data, allowed, None)
+    }
+
+    /// Starts a drag like `start_drag`, showing an image under the cursor. `cursor_offset` is
+    /// the position of the cursor in the image.
+    pub fn start_drag_with_image(
+        &self,
+        data: DragData,
+        allowed: DropEffect,
+        image: &RgbaImage,
+        cursor_offset: (u32, u32),
+    ) -> DropEffect {
+        do_drag_drop(&data, allowed, Some((image, cursor_offset)))
     }
 
     pub fn bind_close_event(&self, event: &WindowCloseEvent) {
@@ -170,15 +301,16 @@ extern "system" fn window_proc(
     match msg {
         // Window Events
         win32::WM_CREATE => unsafe {
-            let create_struct = NonNull::new(lparam.0 as *mut win32::CREATESTRUCTA).unwrap();
+            let create_struct = NonNull::new(lparam.0 as *mut win32::CREATESTRUCTW).unwrap();
             let mut window =
                 NonNull::new(create_struct.as_ref().lpCreateParams as *mut Window).unwrap();
             window.as_mut().hwnd = hwnd;
-            win32::SetWindowLongPtrA(
+            win32::SetWindowLongPtrW(
                 hwnd,
                 win32::GWLP_USERDATA,
                 window.as_ref() as *const _ as isize,
             );
+            register_drop_target(hwnd);
 
             window.as_ref().controller.on_init(window.as_ref());
             win32::LRESULT::default()
@@ -192,6 +324,7 @@ extern "system" fn window_proc(
         }
         win32::WM_DESTROY => unsafe {
             // todo remove window from application
+            revoke_drop_target(hwnd);
             win32::PostQuitMessage(0);
             win32::LRESULT::default()
         },
@@ -201,16 +334,17 @@ extern "system" fn window_proc(
             let (x, y) = lparam_to_point(lparam);
             window
                 .controller
-                .on_moved(window, &WindowMovedEvent::new(x, y));
+                .on_moved(window, &WindowMovedEvent::new(x, y, current_timestamp()));
             win32::LRESULT::default()
         }
 
         win32::WM_SIZE => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (width, height) = lparam_to_size(lparam);
-            window
-                .controller
-                .on_resized(window, &WindowResizedEvent::new(width, height));
+            window.controller.on_resized(
+                window,
+                &WindowResizedEvent::new(width, height, current_timestamp()),
+            );
             win32::LRESULT::default()
         }
 
@@ -226,51 +360,116 @@ extern "system" fn window_proc(
             win32::LRESULT::default()
 
             //
-            // win32::DefWindowProcA(hwnd, msg, wparam, lparam)
+            // win32::DefWindowProcW(hwnd, msg, wparam, lparam)
         },
 
         // Keyboard Input
         win32::WM_KEYDOWN => {
             let window = window_from_hwnd(hwnd).unwrap();
-            let key = wparam_to_vkey(wparam).unwrap();
-            let repeat = lparam_to_prev_key_state(lparam);
-            let state = if !repeat {
-                KeyState::Press
-            } else {
-                KeyState::Repeat
-            };
-            window.controller.on_key(window, &KeyEvent::new(key, state));
+            handle_key(window, wparam, lparam, true);
             win32::LRESULT::default()
         }
         win32::WM_KEYUP => {
             let window = window_from_hwnd(hwnd).unwrap();
-            let key = wparam_to_vkey(wparam).unwrap();
-            window
-                .controller
-                .on_key(window, &KeyEvent::new(key, KeyState::Release));
+            handle_key(window, wparam, lparam, false);
             win32::LRESULT::default()
         }
         win32::WM_CHAR => {
             let window = window_from_hwnd(hwnd).unwrap();
-            let c = wparam_char_code(wparam).unwrap();
-            let repeat = lparam_to_prev_key_state(lparam);
+            handle_char(window, wparam, lparam, true);
+            win32::LRESULT::default()
+        }
+
+        // System keys are delivered for alt combinations and F10. They are passed on to the
+        // default window procedure afterwards, with the behaviours the window has opted out of
+        // filtered here and in WM_SYSCOMMAND.
+        win32::WM_SYSKEYDOWN => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            let key = handle_key(window, wparam, lparam, true);
+            if key == VirtualKey::F4 && !window.system_keys().contains(SystemKeys::ALT_F4_CLOSE) {
+                return win32::LRESULT::default();
+            }
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        win32::WM_SYSKEYUP => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            handle_key(window, wparam, lparam, false);
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        win32::WM_SYSCHAR => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            handle_char(window, wparam, lparam, false);
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        win32::WM_SYSCOMMAND => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            if !is_system_command_allowed(window.system_keys(), wparam.0, lparam.0) {
+                return win32::LRESULT::default();
+            }
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+
+        // Input Method Editor
+        win32::WM_IME_SETCONTEXT => {
+            // The composition is reported to the controller, which draws it itself, so the
+            // default composition window is hidden.
+            let lparam = win32::LPARAM(lparam.0 & !(win32::ISC_SHOWUICOMPOSITIONWINDOW as isize));
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        win32::WM_IME_STARTCOMPOSITION => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            if let Some(area) = window.ime_cursor_area.get() {
+                apply_ime_cursor_area(hwnd, area);
+            }
             window
                 .controller
-                .on_char(window, &CharEvent::new(c, repeat));
+                .on_ime(window, &ImeEvent::new(Ime::Enabled, current_timestamp()));
             win32::LRESULT::default()
         }
-        win32::WM_SETFOCUS => {
+        win32::WM_IME_COMPOSITION => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            handle_ime_composition(window, lparam);
+            win32::LRESULT::default()
+        }
+        win32::WM_IME_ENDCOMPOSITION => {
             let window = window_from_hwnd(hwnd).unwrap();
+            let timestamp = current_timestamp();
+            let preedit = Ime::Preedit {
+                text: String::new(),
+                cursor_range: None,
+            };
+            window
+                .controller
+                .on_ime(window, &ImeEvent::new(preedit, timestamp));
             window
                 .controller
-                .on_focus(window, &FocusChangedEvent::new(true));
+                .on_ime(window, &ImeEvent::new(Ime::Disabled, timestamp));
+            win32::LRESULT::default()
+        }
+
+        win32::WM_INPUTLANGCHANGE => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            window.controller.on_keyboard_layout_changed(
+                window,
+                &KeyboardLayoutChangedEvent::new(current_keyboard_layout(), current_timestamp()),
+            );
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+
+        win32::WM_SETFOCUS => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            let timestamp = current_timestamp();
+            window.focused.set(true);
+            dispatch_focus(window, &FocusChangedEvent::new(true, timestamp));
+            update_modifiers_at(window, timestamp);
             win32::LRESULT::default()
         }
         win32::WM_KILLFOCUS => {
             let window = window_from_hwnd(hwnd).unwrap();
-            window
-                .controller
-                .on_focus(window, &FocusChangedEvent::new(false));
+            let timestamp = current_timestamp();
+            window.focused.set(false);
+            dispatch_focus(window, &FocusChangedEvent::new(false, timestamp));
+            update_modifiers_at(window, timestamp);
             win32::LRESULT::default()
         }
 
@@ -278,26 +477,44 @@ extern "system" fn window_proc(
         win32::WM_MOUSEMOVE => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window
-                .controller
-                .on_mouse_move(window, &MouseMoveEvent::new(x, y));
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_move(
+                window,
+                &MouseMoveEvent::new(x, y, modifiers, message_timestamp()),
+            );
             win32::LRESULT::default()
         }
         win32::WM_LBUTTONDOWN => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Left, ButtonState::Press, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Left,
+                    ButtonState::Press,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
         win32::WM_LBUTTONUP => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Left, ButtonState::Release, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Left,
+                    ButtonState::Release,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
@@ -310,18 +527,34 @@ extern "system" fn window_proc(
         win32::WM_RBUTTONDOWN => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Right, ButtonState::Press, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Right,
+                    ButtonState::Press,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
         win32::WM_RBUTTONUP => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Right, ButtonState::Release, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Right,
+                    ButtonState::Release,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
@@ -334,18 +567,34 @@ extern "system" fn window_proc(
         win32::WM_MBUTTONDOWN => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Middle, ButtonState::Press, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Middle,
+                    ButtonState::Press,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
         win32::WM_MBUTTONUP => {
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(MouseButton::Middle, ButtonState::Release, x, y),
+                &MouseButtonEvent::new(
+                    MouseButton::Middle,
+                    ButtonState::Release,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
@@ -359,9 +608,17 @@ extern "system" fn window_proc(
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
             let button = wparam_to_xkey(wparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(button, ButtonState::Press, x, y),
+                &MouseButtonEvent::new(
+                    button,
+                    ButtonState::Press,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
@@ -369,9 +626,17 @@ extern "system" fn window_proc(
             let window = window_from_hwnd(hwnd).unwrap();
             let (x, y) = lparam_to_point(lparam);
             let button = wparam_to_xkey(wparam);
-            window.controller.on_mouse_button(
+            let modifiers = update_modifiers(window);
+            dispatch_mouse_button(
                 window,
-                &MouseButtonEvent::new(button, ButtonState::Release, x, y),
+                &MouseButtonEvent::new(
+                    button,
+                    ButtonState::Release,
+                    x,
+                    y,
+                    modifiers,
+                    message_timestamp(),
+                ),
             );
             win32::LRESULT::default()
         }
@@ -382,32 +647,846 @@ extern "system" fn window_proc(
         //     println!("XButtonDblClick: {:?} ({}, {})", button, x, y);
         //     win32::LRESULT::default()
         // }
-        win32::WM_MOUSEWHEEL => {
+        win32::WM_MOUSEWHEEL | win32::WM_MOUSEHWHEEL => {
             let window = window_from_hwnd(hwnd).unwrap();
-            let delta = wparam_to_wheel_delta(wparam);
-            let (x, y) = lparam_to_point(lparam);
-            window
-                .controller
-                .on_mouse_wheel(window, &MouseWheelEvent::new(delta as f32, 0.0, x, y));
+            handle_wheel(window, msg == win32::WM_MOUSEHWHEEL, wparam, lparam);
             win32::LRESULT::default()
         }
-        win32::WM_MOUSEHWHEEL => {
+
+        // Pointer Input
+        win32::WM_POINTERDOWN
+        | win32::WM_POINTERUPDATE
+        | win32::WM_POINTERUP
+        | win32::WM_POINTERCAPTURECHANGED => {
             let window = window_from_hwnd(hwnd).unwrap();
-            let delta = wparam_to_wheel_delta(wparam);
-            let (x, y) = lparam_to_point(lparam);
+            handle_pointer(window, msg, wparam);
+            // The default window procedure turns pen and touch input into gestures and mouse
+            // messages. Mouse messages from touches are dropped when emulation is off.
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+
+        // Gestures
+        win32::WM_GESTURENOTIFY => {
+            configure_gestures(hwnd);
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+        win32::WM_GESTURE => {
+            let window = window_from_hwnd(hwnd).unwrap();
+            if handle_gesture(window, lparam) {
+                return win32::LRESULT::default();
+            }
+            unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) }
+        }
+
+        _ => unsafe { win32::DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+}
+
+fn update_input_state(window: &Window, update: impl FnOnce(&mut InputState)) {
+    if let Some(state) = window.input_state.borrow().as_ref() {
+        update(&mut state.lock().unwrap());
+    }
+}
+
+fn dispatch_key(window: &Window, event: &KeyEvent) {
+    update_input_state(window, |state| state.handle_key(event));
+    window.controller.on_key(window, event);
+}
+
+fn dispatch_focus(window: &Window, event: &FocusChangedEvent) {
+    update_input_state(window, |state| state.handle_focus(event));
+    window.controller.on_focus(window, event);
+}
+
+fn dispatch_modifiers_changed(window: &Window, event: &ModifiersChangedEvent) {
+    update_input_state(window, |state| state.handle_modifiers_changed(event));
+    window.controller.on_modifiers_changed(window, event);
+}
+
+fn dispatch_mouse_move(window: &Window, event: &MouseMoveEvent) {
+    let source = emulated_pointer_type();
+    if source == Some(PointerType::Touch) && !window.touch_emulates_mouse() {
+        return;
+    }
+    update_input_state(window, |state| state.handle_mouse_move(event));
+    window.controller.on_mouse_move(window, event);
+    dispatch_mouse_pointer(
+        window,
+        source,
+        None,
+        event.pos(),
+        event.modifiers(),
+        event.timestamp(),
+    );
+}
+
+fn dispatch_mouse_button(window: &Window, event: &MouseButtonEvent) {
+    let source = emulated_pointer_type();
+    if source == Some(PointerType::Touch) && !window.touch_emulates_mouse() {
+        return;
+    }
+    update_input_state(window, |state| state.handle_mouse_button(event));
+    window.controller.on_mouse_button(window, event);
+    dispatch_mouse_pointer(
+        window,
+        source,
+        Some(event.state()),
+        event.pos(),
+        event.modifiers(),
+        event.timestamp(),
+    );
+}
+
+fn dispatch_mouse_wheel(window: &Window, event: &MouseWheelEvent) {
+    update_input_state(window, |state| state.handle_mouse_wheel(event));
+    window.controller.on_mouse_wheel(window, event);
+}
+
+pub(crate) fn window_from_hwnd<'a>(hwnd: win32::HWND) -> Option<&'a Window> {
+    NonNull::new(unsafe { win32::GetWindowLongPtrW(hwnd, win32::GWLP_USERDATA) } as *mut Window)
+        .map(|ptr| unsafe { ptr.as_ref() })
+}
+
+/// Queries the current modifier state and notifies the controller if it differs from the last
+/// state seen by the window.
+fn update_modifiers(window: &Window) -> Modifiers {
+    update_modifiers_at(window, message_timestamp())
+}
+
+fn update_modifiers_at(window: &Window, timestamp: Timestamp) -> Modifiers {
+    let modifiers = window_modifiers(current_modifiers(), window.focused.get());
+    if window.modifiers.replace(modifiers) != modifiers {
+        dispatch_modifiers_changed(window, &ModifiersChangedEvent::new(modifiers, timestamp));
+    }
+    modifiers
+}
+
+/// Returns the modifiers a window reports. The key state isn't updated while another window
+/// has focus, so keys held when the window lost focus, such as alt after Alt+Tab, would stay
+/// down until it gets focus back. Only the lock keys are kept for an unfocused window.
+fn window_modifiers(modifiers: Modifiers, focused: bool) -> Modifiers {
+    if focused {
+        modifiers
+    } else {
+        modifiers & (Modifiers::CAPS_LOCK | Modifiers::NUM_LOCK)
+    }
+}
+
+/// Returns whether the default window procedure may carry out a system command, which the
+/// window can opt out of through its `SystemKeys`.
+fn is_system_command_allowed(system_keys: SystemKeys, wparam: usize, lparam: isize) -> bool {
+    if wparam as u32 & 0xFFF0 != win32::SC_KEYMENU {
+        return true;
+    }
+
+    // lparam holds the character that triggered the menu: a space for the system menu, zero
+    // for alt or F10 on their own, or the letter of an alt mnemonic.
+    let behaviour = if lparam == ' ' as isize {
+        SystemKeys::SYSTEM_MENU
+    } else {
+        SystemKeys::MENU_ACTIVATION
+    };
+    system_keys.contains(behaviour)
+}
+
+fn current_modifiers() -> Modifiers {
+    let key_state = |vk: win32::VIRTUAL_KEY| unsafe { win32::GetKeyState(vk.0 as i32) };
+    let is_down = |vk| key_state(vk) < 0;
+    let is_toggled = |vk| key_state(vk) & 1 != 0;
+
+    let mut modifiers = Modifiers::empty();
+    modifiers.set(Modifiers::LEFT_SHIFT, is_down(win32::VK_LSHIFT));
+    modifiers.set(Modifiers::RIGHT_SHIFT, is_down(win32::VK_RSHIFT));
+    modifiers.set(Modifiers::LEFT_CONTROL, is_down(win32::VK_LCONTROL));
+    modifiers.set(Modifiers::RIGHT_CONTROL, is_down(win32::VK_RCONTROL));
+    modifiers.set(Modifiers::LEFT_ALT, is_down(win32::VK_LMENU));
+    modifiers.set(Modifiers::RIGHT_ALT, is_down(win32::VK_RMENU));
+    modifiers.set(Modifiers::LEFT_SUPER, is_down(win32::VK_LWIN));
+    modifiers.set(Modifiers::RIGHT_SUPER, is_down(win32::VK_RWIN));
+    modifiers.set(Modifiers::CAPS_LOCK, is_toggled(win32::VK_CAPITAL));
+    modifiers.set(Modifiers::NUM_LOCK, is_toggled(win32::VK_NUMLOCK));
+    modifiers
+}
+
+/// Dispatches a key message to the controller and returns the key it was for.
+fn handle_key(
+    window: &Window,
+    wparam: win32::WPARAM,
+    lparam: win32::LPARAM,
+    down: bool,
+) -> VirtualKey {
+    let scan_code = lparam_to_scan_code(lparam);
+    let (key, location) = wparam_to_vkey(wparam, scan_code);
+    let state = if !down {
+        KeyState::Release
+    } else if lparam_to_prev_key_state(lparam) {
+        KeyState::Repeat
+    } else {
+        KeyState::Press
+    };
+
+    let mut modifiers = update_modifiers(window);
+    if lparam_to_context_code(lparam) && !modifiers.alt() {
+        modifiers |= Modifiers::LEFT_ALT;
+    }
+
+    let dead_key = dead_key_char(wparam.0 as u32, scan_code);
+
+    dispatch_key(
+        window,
+        &KeyEvent::new(
+            key,
+            location,
+            scan_code,
+            state,
+            modifiers,
+            dead_key,
+            message_timestamp(),
+        ),
+    );
+    key
+}
+
+/// Dispatches a character message to the controller. Characters from system key messages are
+/// not text input, so they are only reported through `on_char`.
+fn handle_char(window: &Window, wparam: win32::WPARAM, lparam: win32::LPARAM, text_input: bool) {
+    let Some(c) = wparam_char_code(window, wparam) else {
+        return;
+    };
+    let repeat = lparam_to_prev_key_state(lparam);
+    window
+        .controller
+        .on_char(window, &CharEvent::new(c, repeat, message_timestamp()));
+
+    // Backspace, escape, enter and ctrl+letter combinations arrive as control characters.
+    if text_input && !c.is_control() {
+        window.controller.on_text_input(
+            window,
+            &TextInputEvent::new(c.to_string(), message_timestamp()),
+        );
+    }
+}
+
+/// Dispatches a pen or touch pointer message to the controller. Mouse pointers are reported from
+/// the mouse messages instead.
+fn handle_pointer(window: &Window, msg: u32, wparam: win32::WPARAM) -> Option<()> {
+    let pointer_id = wparam_to_pointer_id(wparam);
+    let mut pointer_type = win32::POINTER_INPUT_TYPE::default();
+    unsafe { win32::GetPointerType(pointer_id, &mut pointer_type) }.ok()?;
+
+    match pointer_type {
+        win32::PT_TOUCH => {
+            let mut info = win32::POINTER_TOUCH_INFO::default();
+            unsafe { win32::GetPointerTouchInfo(pointer_id, &mut info) }.ok()?;
+            let force = (info.touchMask & win32::TOUCH_MASK_PRESSURE != 0)
+                .then(|| info.pressure as f32 / POINTER_PRESSURE_MAX);
+
+            let pointer = &info.pointerInfo;
+            let phase = pointer_phase(msg, pointer);
+            let (x, y) = pointer_pos(window, pointer);
+            let modifiers = update_modifiers(window);
+            let timestamp = message_timestamp();
+
+            let touch_phase = match phase {
+                PointerPhase::Down => TouchPhase::Started,
+                PointerPhase::Move => TouchPhase::Moved,
+                PointerPhase::Up => TouchPhase::Ended,
+                PointerPhase::Cancel => TouchPhase::Cancelled,
+            };
+            window.controller.on_touch(
+                window,
+                &TouchEvent::new(
+                    pointer_id as u64,
+                    touch_phase,
+                    x,
+                    y,
+                    force,
+                    modifiers,
+                    timestamp,
+                ),
+            );
+
+            let buttons = contact_buttons(pointer);
+            window.controller.on_pointer(
+                window,
+                &PointerEvent::new(
+                    pointer_id as u64,
+                    PointerType::Touch,
+                    phase,
+                    x,
+                    y,
+                    force.unwrap_or_else(|| default_pressure(buttons)),
+                    (0.0, 0.0),
+                    0.0,
+                    buttons,
+                    is_primary_pointer(pointer),
+                    modifiers,
+                    timestamp,
+                ),
+            );
+            Some(())
+        }
+        win32::PT_PEN => {
+            let mut info = win32::POINTER_PEN_INFO::default();
+            unsafe { win32::GetPointerPenInfo(pointer_id, &mut info) }.ok()?;
+            let has = |mask| info.penMask & mask != 0;
+
+            let pointer = &info.pointerInfo;
+            let mut buttons = contact_buttons(pointer);
+            buttons.set(
+                PointerButtons::SECONDARY,
+                info.penFlags & win32::PEN_FLAG_BARREL != 0,
+            );
+            buttons.set(
+                PointerButtons::ERASER,
+                info.penFlags & (win32::PEN_FLAG_ERASER | win32::PEN_FLAG_INVERTED) != 0,
+            );
+
+            let pressure = if has(win32::PEN_MASK_PRESSURE) {
+                info.pressure as f32 / POINTER_PRESSURE_MAX
+            } else {
+                default_pressure(buttons)
+            };
+            let tilt_x = if has(win32::PEN_MASK_TILT_X) {
+                info.tiltX as f32
+            } else {
+                0.0
+            };
+            let tilt_y = if has(win32::PEN_MASK_TILT_Y) {
+                info.tiltY as f32
+            } else {
+                0.0
+            };
+            let twist = if has(win32::PEN_MASK_ROTATION) {
+                info.rotation as f32
+            } else {
+                0.0
+            };
+
+            let (x, y) = pointer_pos(window, pointer);
+            let modifiers = update_modifiers(window);
+            window.controller.on_pointer(
+                window,
+                &PointerEvent::new(
+                    pointer_id as u64,
+                    PointerType::Pen,
+                    pointer_phase(msg, pointer),
+                    x,
+                    y,
+                    pressure,
+                    (tilt_x, tilt_y),
+                    twist,
+                    buttons,
+                    is_primary_pointer(pointer),
+                    modifiers,
+                    message_timestamp(),
+                ),
+            );
+            Some(())
+        }
+        _ => None,
+    }
+}
+
+fn pointer_phase(msg: u32, pointer: &win32::POINTER_INFO) -> PointerPhase {
+    if msg == win32::WM_POINTERCAPTURECHANGED
+        || pointer.pointerFlags.0 & win32::POINTER_FLAG_CANCELED.0 != 0
+    {
+        return PointerPhase::Cancel;
+    }
+    match msg {
+        win32::WM_POINTERDOWN => PointerPhase::Down,
+        win32::WM_POINTERUP => PointerPhase::Up,
+        _ => PointerPhase::Move,
+    }
+}
+
+fn pointer_pos(window: &Window, pointer: &win32::POINTER_INFO) -> (f32, f32) {
+    let mut pos = pointer.ptPixelLocation;
+    unsafe { win32::ScreenToClient(window.hwnd, &mut pos) };
+    (pos.x as f32, pos.y as f32)
+}
+
+fn is_primary_pointer(pointer: &win32::POINTER_INFO) -> bool {
+    pointer.pointerFlags.0 & win32::POINTER_FLAG_PRIMARY.0 != 0
+}
+
+/// Returns the buttons of a pen or touch, with contact with the surface as the primary button.
+fn contact_buttons(pointer: &win32::POINTER_INFO) -> PointerButtons {
+    let mut buttons = PointerButtons::empty();
+    buttons.set(
+        PointerButtons::PRIMARY,
+        pointer.pointerFlags.0 & win32::POINTER_FLAG_INCONTACT.0 != 0,
+    );
+    buttons
+}
+
+/// Returns the pressure W3C pointer events report for devices without pressure support.
+fn default_pressure(buttons: PointerButtons) -> f32 {
+    if buttons.is_empty() {
+        0.0
+    } else {
+        0.5
+    }
+}
+
+/// Returns the type of pointer the system synthesized the current mouse message from, or `None`
+/// for messages from a mouse.
+fn emulated_pointer_type() -> Option<PointerType> {
+    let extra_info = unsafe { win32::GetMessageExtraInfo() }.0 as u32;
+    if extra_info & MI_WP_SIGNATURE_MASK != MI_WP_SIGNATURE {
+        None
+    } else if extra_info & MI_WP_TOUCH != 0 {
+        Some(PointerType::Touch)
+    } else {
+        Some(PointerType::Pen)
+    }
+}
+
+/// Reports mouse input as a pointer event. Mouse messages the system synthesized from pen or
+/// touch input are skipped, as those pointers are reported by `handle_pointer`.
+fn dispatch_mouse_pointer(
+    window: &Window,
+    source: Option<PointerType>,
+    state: Option<ButtonState>,
+    (x, y): (f32, f32),
+    modifiers: Modifiers,
+    timestamp: Timestamp,
+) {
+    if source.is_some() {
+        return;
+    }
+
+    let buttons = current_mouse_buttons();
+    let phase = match state {
+        Some(ButtonState::Press) if buttons.bits().count_ones() == 1 => PointerPhase::Down,
+        Some(ButtonState::Release) if buttons.is_empty() => PointerPhase::Up,
+        _ => PointerPhase::Move,
+    };
+
+    window.controller.on_pointer(
+        window,
+        &PointerEvent::new(
+            MOUSE_POINTER_ID,
+            PointerType::Mouse,
+            phase,
+            x,
+            y,
+            default_pressure(buttons),
+            (0.0, 0.0),
+            0.0,
+            buttons,
+            true,
+            modifiers,
+            timestamp,
+        ),
+    );
+}
+
+fn current_mouse_buttons() -> PointerButtons {
+    let is_down = |vk: win32::VIRTUAL_KEY| unsafe { win32::GetKeyState(vk.0 as i32) } < 0;
+
+    let mut buttons = PointerButtons::empty();
+    buttons.set(PointerButtons::PRIMARY, is_down(win32::VK_LBUTTON));
+    buttons.set(PointerButtons::SECONDARY, is_down(win32::VK_RBUTTON));
+    buttons.set(PointerButtons::MIDDLE, is_down(win32::VK_MBUTTON));
+    buttons.set(PointerButtons::X1, is_down(win32::VK_XBUTTON1));
+    buttons.set(PointerButtons::X2, is_down(win32::VK_XBUTTON2));
+    buttons
+}
+
+/// Dispatches a wheel message to the controller. Precision touchpads send wheel messages too,
+/// with Ctrl held while pinching, which is reported as a gesture instead.
+fn handle_wheel(window: &Window, horizontal: bool, wparam: win32::WPARAM, lparam: win32::LPARAM) {
+    let delta = wparam_to_wheel_delta(wparam);
+    let (x, y) = lparam_to_point(lparam);
+    let modifiers = update_modifiers(window);
+    let timestamp = message_timestamp();
+    let touchpad = is_touchpad_message();
+    let latch = latch_wheel_precision(window.wheel.get(), delta, touchpad, timestamp);
+    window.wheel.set(Some(latch));
+
+    // Only a touchpad pinches. Mouse wheels, high resolution ones included, turned with Ctrl held
+    // keep scrolling, and applications zoom on those themselves.
+    if touchpad && !horizontal && modifiers.control() {
+        let notches = delta as f32 / win32::WHEEL_DELTA as f32;
+        window.controller.on_gesture(
+            window,
+            &GestureEvent::new(
+                Gesture::Pinch {
+                    scale_delta: PINCH_SCALE_PER_NOTCH.powf(notches) - 1.0,
+                },
+                GesturePhase::Changed,
+                x,
+                y,
+                modifiers,
+                timestamp,
+            ),
+        );
+        return;
+    }
+
+    let lines_per_notch = wheel_scroll_setting(horizontal);
+    let Some(delta) = wheel_scroll_delta(delta, latch.precise, horizontal, lines_per_notch) else {
+        return;
+    };
+    dispatch_mouse_wheel(
+        window,
+        &MouseWheelEvent::new(delta, GesturePhase::Changed, x, y, modifiers, timestamp),
+    );
+}
+
+/// Returns whether the message being processed comes from a touchpad. Touchpads with drivers
+/// older than precision touchpads pass for mice.
+fn is_touchpad_message() -> bool {
+    let mut source = win32::INPUT_MESSAGE_SOURCE::default();
+    unsafe { win32::GetCurrentInputMessageSource(&mut source) }.is_ok()
+        && source.deviceType == win32::IMDT_TOUCHPAD
+}
+
+/// Updates the precision of the wheel gesture in progress with a wheel message. Touchpads are
+/// always precise, and once a wheel reports a fraction of a notch, it stays precise until it
+/// rests for `WHEEL_GESTURE_TIMEOUT`.
+fn latch_wheel_precision(
+    latch: Option<WheelLatch>,
+    delta: i16,
+    touchpad: bool,
+    timestamp: Timestamp,
+) -> WheelLatch {
+    let continued = latch.filter(|latch| {
+        latch.precise && timestamp.duration_since(latch.timestamp) <= WHEEL_GESTURE_TIMEOUT
+    });
+    WheelLatch {
+        precise: touchpad || is_precise_wheel_delta(delta) || continued.is_some(),
+        timestamp,
+    }
+}
+
+/// Returns how many lines, or columns for horizontal wheels, the user chose to scroll per notch.
+fn wheel_scroll_setting(horizontal: bool) -> u32 {
+    let setting = if horizontal {
+        win32::SPI_GETWHEELSCROLLCHARS
+    } else {
+        win32::SPI_GETWHEELSCROLLLINES
+    };
+    let mut lines_per_notch = DEFAULT_WHEEL_LINES;
+    let _ = unsafe {
+        win32::SystemParametersInfoW(
+            setting,
+            0,
+            Some(&mut lines_per_notch as *mut u32 as *mut c_void),
+            win32::SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    lines_per_notch
+}
+
+/// Returns whether a wheel delta comes from a precision touchpad or a high resolution wheel,
+/// which report fractions of a notch, rather than from a notched wheel.
+fn is_precise_wheel_delta(delta: i16) -> bool {
+    delta as i32 % win32::WHEEL_DELTA as i32 != 0
+}
+
+/// Converts the delta of a wheel message into the distance to scroll, given the number of lines
+/// per notch from `wheel_scroll_setting`. Notched wheels scroll in lines, or pages if the user
+/// chose to, and precise wheels, as latched by `latch_wheel_precision`, in pixels. Turning the
+/// wheel away from the user and tilting it right are positive, as in `ScrollDelta`.
+fn wheel_scroll_delta(
+    delta: i16,
+    precise: bool,
+    horizontal: bool,
+    lines_per_notch: u32,
+) -> Option<ScrollDelta> {
+    if delta == 0 || lines_per_notch == 0 {
+        return None;
+    }
+
+    let notches = delta as f32 / win32::WHEEL_DELTA as f32;
+    let axes = |amount| {
+        if horizontal {
+            (amount, 0.0)
+        } else {
+            (0.0, amount)
+        }
+    };
+    if precise {
+        // Pixels have no page size, so precise scrolling falls back to the default line count.
+        let lines = if lines_per_notch == WHEEL_PAGESCROLL {
+            DEFAULT_WHEEL_LINES
+        } else {
+            lines_per_notch
+        };
+        let (x, y) = axes(notches * lines as f32 * PIXELS_PER_LINE);
+        Some(ScrollDelta::Pixels(x, y))
+    } else if lines_per_notch == WHEEL_PAGESCROLL {
+        let (x, y) = axes(notches);
+        Some(ScrollDelta::Pages(x, y))
+    } else {
+        let (x, y) = axes(notches * lines_per_notch as f32);
+        Some(ScrollDelta::Lines(x, y))
+    }
+}
+
+/// Enables the gestures reported by `handle_gesture`, which include rotation and panning with
+/// inertia on top of the gestures enabled by default.
+fn configure_gestures(hwnd: win32::HWND) {
+    let pan = win32::GC_PAN.0
+        | win32::GC_PAN_WITH_SINGLE_FINGER_VERTICALLY.0
+        | win32::GC_PAN_WITH_SINGLE_FINGER_HORIZONTALLY.0
+        | win32::GC_PAN_WITH_INERTIA.0;
+    let configs = [
+        win32::GESTURECONFIG {
+            dwID: win32::GID_ZOOM,
+            dwWant: win32::GC_ZOOM.0,
+            dwBlock: 0,
+        },
+        win32::GESTURECONFIG {
+            dwID: win32::GID_ROTATE,
+            dwWant: win32::GC_ROTATE.0,
+            dwBlock: 0,
+        },
+        win32::GESTURECONFIG {
+            dwID: win32::GID_PAN,
+            dwWant: pan,
+            dwBlock: win32::GC_PAN_WITH_GUTTER.0,
+        },
+    ];
+    let size = std::mem::size_of::<win32::GESTURECONFIG>() as u32;
+    let _ = unsafe { win32::SetGestureConfig(hwnd, 0, &configs, size) };
+}
+
+/// Dispatches a zoom, rotate or pan gesture to the controller, returning whether the gesture was
+/// handled. Pans are reported as precise mouse wheel events.
+fn handle_gesture(window: &Window, lparam: win32::LPARAM) -> bool {
+    let handle = win32::HGESTUREINFO(lparam.0);
+    let mut info = win32::GESTUREINFO {
+        cbSize: std::mem::size_of::<win32::GESTUREINFO>() as u32,
+        ..Default::default()
+    };
+    if unsafe { win32::GetGestureInfo(handle, &mut info) }.is_err() {
+        return false;
+    }
+
+    let id = win32::GESTURECONFIG_ID(info.dwID);
+    if !matches!(id, win32::GID_ZOOM | win32::GID_ROTATE | win32::GID_PAN) {
+        return false;
+    }
+
+    let begin = info.dwFlags & win32::GF_BEGIN != 0;
+    let phase = if info.dwFlags & win32::GF_END != 0 {
+        GesturePhase::Ended
+    } else if info.dwFlags & win32::GF_INERTIA != 0 {
+        GesturePhase::Momentum
+    } else if begin {
+        GesturePhase::Began
+    } else {
+        GesturePhase::Changed
+    };
+
+    let current = GestureProgress {
+        argument: info.ullArguments,
+        x: info.ptsLocation.x,
+        y: info.ptsLocation.y,
+    };
+    let last = if begin { current } else { window.gesture.get() };
+    window.gesture.set(current);
+
+    let mut pos = win32::POINT {
+        x: current.x as i32,
+        y: current.y as i32,
+    };
+    unsafe { win32::ScreenToClient(window.hwnd, &mut pos) };
+    let (x, y) = (pos.x as f32, pos.y as f32);
+    let modifiers = update_modifiers(window);
+    let timestamp = message_timestamp();
+
+    match id {
+        win32::GID_ZOOM => {
+            // The argument holds the distance between the fingers.
+            let distance = current.argument as u32 as f32;
+            let last_distance = last.argument as u32 as f32;
+            let scale_delta = if last_distance > 0.0 {
+                distance / last_distance - 1.0
+            } else {
+                0.0
+            };
+            window.controller.on_gesture(
+                window,
+                &GestureEvent::new(
+                    Gesture::Pinch { scale_delta },
+                    phase,
+                    x,
+                    y,
+                    modifiers,
+                    timestamp,
+                ),
+            );
+        }
+        win32::GID_ROTATE => {
+            // Counterclockwise angles are positive, while events report clockwise angles.
+            let angle_delta = gesture_angle(last.argument) - gesture_angle(current.argument);
+            window.controller.on_gesture(
+                window,
+                &GestureEvent::new(
+                    Gesture::Rotate { angle_delta },
+                    phase,
+                    x,
+                    y,
+                    modifiers,
+                    timestamp,
+                ),
+            );
+        }
+        _ => {
+            // Fingers moving down drag the content down, which scrolls up like turning the
+            // wheel away from the user.
+            let vert = (current.y as i32 - last.y as i32) as f32;
+            let horz = (last.x as i32 - current.x as i32) as f32;
+            dispatch_mouse_wheel(
+                window,
+                &MouseWheelEvent::new(
+                    ScrollDelta::Pixels(horz, vert),
+                    phase,
+                    x,
+                    y,
+                    modifiers,
+                    timestamp,
+                ),
+            );
+        }
+    }
+
+    unsafe { win32::CloseGestureInfoHandle(handle) }.ok();
+    true
+}
+
+/// Returns the angle in degrees of a rotate gesture argument, as `GID_ROTATE_ANGLE_FROM_ARGUMENT`
+/// does in radians.
+fn gesture_angle(argument: u64) -> f32 {
+    (argument as u16 as f32 / 65535.0) * 720.0 - 360.0
+}
+
+/// Reports the result and composition strings of the input method. The message is not passed on
+/// to the default window procedure, which would deliver the result again as character messages.
+fn handle_ime_composition(window: &Window, lparam: win32::LPARAM) {
+    let flags = lparam.0 as u32;
+    let timestamp = current_timestamp();
+    let himc = unsafe { win32::ImmGetContext(window.hwnd) };
+    if himc.0 == 0 {
+        return;
+    }
+
+    if flags & win32::GCS_RESULTSTR.0 != 0 {
+        if let Some(text) = ime_composition_string(himc, win32::GCS_RESULTSTR) {
+            let preedit = Ime::Preedit {
+                text: String::new(),
+                cursor_range: None,
+            };
             window
                 .controller
-                .on_mouse_wheel(window, &MouseWheelEvent::new(0.0, delta as f32, x, y));
-            win32::LRESULT::default()
+                .on_ime(window, &ImeEvent::new(preedit, timestamp));
+            window
+                .controller
+                .on_ime(window, &ImeEvent::new(Ime::Commit(text), timestamp));
+        }
+    }
+
+    if flags & win32::GCS_COMPSTR.0 != 0 {
+        if let Some(text) = ime_composition_string(himc, win32::GCS_COMPSTR) {
+            let cursor_range = if flags & win32::GCS_CURSORPOS.0 != 0 {
+                let cursor =
+                    unsafe { win32::ImmGetCompositionStringW(himc, win32::GCS_CURSORPOS, None, 0) };
+                usize::try_from(cursor)
+                    .ok()
+                    .and_then(|cursor| utf16_to_byte_offset(&text, cursor))
+                    .map(|cursor| (cursor, cursor))
+            } else {
+                None
+            };
+            let preedit = Ime::Preedit { text, cursor_range };
+            window
+                .controller
+                .on_ime(window, &ImeEvent::new(preedit, timestamp));
         }
+    }
 
-        _ => unsafe { win32::DefWindowProcA(hwnd, msg, wparam, lparam) },
+    unsafe {
+        win32::ImmReleaseContext(window.hwnd, himc);
     }
 }
 
-fn window_from_hwnd<'a>(hwnd: win32::HWND) -> Option<&'a Window> {
-    NonNull::new(unsafe { win32::GetWindowLongPtrA(hwnd, win32::GWLP_USERDATA) } as *mut Window)
-        .map(|ptr| unsafe { ptr.as_ref() })
+fn ime_composition_string(
+    himc: win32::HIMC,
+    kind: win32::IME_COMPOSITION_STRING,
+) -> Option<String> {
+    // The length is returned in bytes, not in UTF-16 code units.
+    let len = unsafe { win32::ImmGetCompositionStringW(himc, kind, None, 0) };
+    if len < 0 {
+        return None;
+    }
+
+    let mut buffer = vec![0u16; len as usize / 2];
+    unsafe {
+        win32::ImmGetCompositionStringW(
+            himc,
+            kind,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            len as u32,
+        )
+    };
+    String::from_utf16(&buffer).ok()
+}
+
+/// Converts an offset in UTF-16 code units into a byte offset into `text`. An offset inside a
+/// surrogate pair is moved to the start of its character. Returns `None` for offsets past the
+/// end of the text.
+fn utf16_to_byte_offset(text: &str, utf16_offset: usize) -> Option<usize> {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        units += c.len_utf16();
+        if units > utf16_offset {
+            return Some(offset);
+        }
+    }
+    (units == utf16_offset).then_some(text.len())
+}
+
+fn apply_ime_cursor_area(hwnd: win32::HWND, area: Rect) {
+    let rect = win32::RECT {
+        left: area.pos().x as i32,
+        top: area.pos().y as i32,
+        right: (area.pos().x + area.size().width) as i32,
+        bottom: (area.pos().y + area.size().height) as i32,
+    };
+    let pos = win32::POINT {
+        x: rect.left,
+        y: rect.bottom,
+    };
+
+    unsafe {
+        let himc = win32::ImmGetContext(hwnd);
+        if himc.0 == 0 {
+            return;
+        }
+        win32::ImmSetCompositionWindow(
+            himc,
+            &win32::COMPOSITIONFORM {
+                dwStyle: win32::CFS_POINT,
+                ptCurrentPos: pos,
+                rcArea: win32::RECT::default(),
+            },
+        );
+        win32::ImmSetCandidateWindow(
+            himc,
+            &win32::CANDIDATEFORM {
+                dwIndex: 0,
+                dwStyle: win32::CFS_EXCLUDE,
+                ptCurrentPos: pos,
+                rcArea: rect,
+            },
+        );
+        win32::ImmReleaseContext(hwnd, himc);
+    }
 }
 
 fn lparam_to_point(lparam: win32::LPARAM) -> (f32, f32) {
@@ -422,22 +1501,90 @@ fn lparam_to_size(lparam: win32::LPARAM) -> (f32, f32) {
     (width, height)
 }
 
-fn wparam_to_vkey(wparam: win32::WPARAM) -> Option<VirtualKey> {
-    Some(unsafe { VirtualKey::from_raw(wparam.0 as u32) })
+fn wparam_to_vkey(wparam: win32::WPARAM, scan_code: ScanCode) -> (VirtualKey, KeyLocation) {
+    let (vkey, location) = virtual_key::resolve(wparam.0 as VirtualKeyT, scan_code);
+    (VirtualKey::from_native(vkey), location)
+}
+
+fn vkey_to_label(key: VirtualKey) -> Option<String> {
+    let vkey = match key {
+        VirtualKey::NumpadEnter => virtual_key::ENTER,
+        key => key.to_native(),
+    };
+    let scan_code = unsafe { win32::MapVirtualKeyW(vkey, win32::MAPVK_VK_TO_VSC_EX) };
+    if scan_code == 0 {
+        return None;
+    }
+
+    let mut lparam = ((scan_code & 0xFF) << 16) as i32;
+    if scan_code & 0xE000 == 0xE000 || key == VirtualKey::NumpadEnter {
+        lparam |= 1 << 24;
+    }
+
+    let mut buffer = [0u16; 64];
+    let len = unsafe { win32::GetKeyNameTextW(lparam, &mut buffer) };
+    if len <= 0 {
+        return None;
+    }
+    String::from_utf16(&buffer[..len as usize]).ok()
+}
+
+/// Decodes the scan code from bits 16-23 of the key message, adding the `0xE0` prefix if the
+/// extended-key flag (bit 24) is set.
+fn lparam_to_scan_code(lparam: win32::LPARAM) -> ScanCode {
+    let scan_code = ((lparam.0 >> 16) & 0xFF) as ScanCode;
+    if lparam_to_extended_key(lparam) {
+        0xE000 | scan_code
+    } else {
+        scan_code
+    }
+}
+
+fn lparam_to_extended_key(lparam: win32::LPARAM) -> bool {
+    lparam.0 & (1 << 24) != 0
+}
+
+/// Returns true if the alt key was held when the key message was generated.
+fn lparam_to_context_code(lparam: win32::LPARAM) -> bool {
+    lparam.0 & (1 << 29) != 0
 }
 
 fn lparam_to_prev_key_state(lparam: win32::LPARAM) -> bool {
     lparam.0 & (1 << 30) != 0
 }
 
-fn wparam_char_code(wparam: win32::WPARAM) -> Option<char> {
-    char::from_u32(wparam.0 as u32)
+/// Decodes the UTF-16 code unit of a character message. Characters outside the basic
+/// multilingual plane arrive as two messages, so a high surrogate is held back until the low
+/// surrogate that completes it arrives. Unpaired surrogates are dropped.
+fn wparam_char_code(window: &Window, wparam: win32::WPARAM) -> Option<char> {
+    decode_char_code(&window.high_surrogate, wparam.0 as u16)
+}
+
+fn decode_char_code(high_surrogate: &Cell<Option<u16>>, unit: u16) -> Option<char> {
+    match unit {
+        0xD800..=0xDBFF => {
+            high_surrogate.set(Some(unit));
+            None
+        }
+        0xDC00..=0xDFFF => {
+            let high = high_surrogate.take()?;
+            char::decode_utf16([high, unit]).next()?.ok()
+        }
+        _ => {
+            high_surrogate.set(None);
+            char::from_u32(unit as u32)
+        }
+    }
 }
 
 fn wparam_to_wheel_delta(wparam: win32::WPARAM) -> i16 {
     win32::wparam_hiword(wparam) as i16
 }
 
+fn wparam_to_pointer_id(wparam: win32::WPARAM) -> u32 {
+    win32::wparam_loword(wparam) as u32
+}
+
 fn wparam_to_xkey(wparam: win32::WPARAM) -> MouseButton {
     match win32::wparam_hiword(wparam) {
         win32::XBUTTON1 => MouseButton::X1,
@@ -445,3 +1592,185 @@ fn wparam_to_xkey(wparam: win32::WPARAM) -> MouseButton {
         _ => panic!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_commands() {
+        let keymenu = win32::SC_KEYMENU as usize;
+        let all = SystemKeys::all();
+        assert!(is_system_command_allowed(all, keymenu, ' ' as isize));
+        assert!(is_system_command_allowed(all, keymenu, 0));
+
+        // Alt+Space opens the system menu, anything else activates the menu bar.
+        let menu = SystemKeys::MENU_ACTIVATION;
+        assert!(!is_system_command_allowed(menu, keymenu, ' ' as isize));
+        assert!(is_system_command_allowed(menu, keymenu, 0));
+        assert!(is_system_command_allowed(menu, keymenu, 'f' as isize));
+        let system_menu = SystemKeys::SYSTEM_MENU;
+        assert!(is_system_command_allowed(
+            system_menu,
+            keymenu,
+            ' ' as isize
+        ));
+        assert!(!is_system_command_allowed(system_menu, keymenu, 0));
+        assert!(!is_system_command_allowed(
+            system_menu,
+            keymenu,
+            'f' as isize
+        ));
+
+        // The low four bits are used by the system.
+        let none = SystemKeys::empty();
+        assert!(!is_system_command_allowed(none, keymenu | 0x2, 0));
+
+        // Other commands are always carried out.
+        assert!(is_system_command_allowed(none, win32::SC_CLOSE as usize, 0));
+        assert!(is_system_command_allowed(
+            none,
+            win32::SC_MINIMIZE as usize,
+            0
+        ));
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        let high_surrogate = Cell::new(None);
+        let decode = |unit| decode_char_code(&high_surrogate, unit);
+
+        // U+1F600 is sent as 0xD83D 0xDE00.
+        assert_eq!(decode(0xD83D), None);
+        assert_eq!(decode(0xDE00), Some('\u{1F600}'));
+        assert_eq!(high_surrogate.get(), None);
+
+        // A lone high surrogate is dropped by the next character.
+        assert_eq!(decode(0xD83D), None);
+        assert_eq!(decode('a' as u16), Some('a'));
+        assert_eq!(decode(0xDE00), None);
+
+        // A lone low surrogate is dropped.
+        assert_eq!(decode(0xDC00), None);
+        assert_eq!(decode('b' as u16), Some('b'));
+
+        // A second high surrogate replaces the first.
+        assert_eq!(decode(0xD800), None);
+        assert_eq!(decode(0xD83D), None);
+        assert_eq!(decode(0xDE00), Some('\u{1F600}'));
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        // 'é' is two bytes and one unit, U+1F600 is four bytes and two units.
+        let text = "é\u{1F600}b";
+        assert_eq!(utf16_to_byte_offset(text, 0), Some(0));
+        assert_eq!(utf16_to_byte_offset(text, 1), Some(2));
+        assert_eq!(utf16_to_byte_offset(text, 2), Some(2));
+        assert_eq!(utf16_to_byte_offset(text, 3), Some(6));
+        assert_eq!(utf16_to_byte_offset(text, 4), Some(7));
+        assert_eq!(utf16_to_byte_offset(text, 5), None);
+
+        assert_eq!(utf16_to_byte_offset("", 0), Some(0));
+        assert_eq!(utf16_to_byte_offset("", 1), None);
+    }
+
+    #[test]
+    fn unfocused_windows_keep_only_locks() {
+        let modifiers = Modifiers::LEFT_ALT | Modifiers::RIGHT_SHIFT | Modifiers::CAPS_LOCK;
+        assert_eq!(window_modifiers(modifiers, true), modifiers);
+        assert_eq!(window_modifiers(modifiers, false), Modifiers::CAPS_LOCK);
+        assert_eq!(
+            window_modifiers(Modifiers::NUM_LOCK, false),
+            Modifiers::NUM_LOCK
+        );
+    }
+
+    #[test]
+    fn precise_wheel_deltas() {
+        assert!(!is_precise_wheel_delta(120));
+        assert!(!is_precise_wheel_delta(-240));
+        assert!(is_precise_wheel_delta(30));
+        assert!(is_precise_wheel_delta(-1));
+
+        assert_eq!(
+            wheel_scroll_delta(240, false, false, 3),
+            Some(ScrollDelta::Lines(0.0, 6.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(60, true, false, 3),
+            Some(ScrollDelta::Pixels(0.0, 20.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(-12, true, true, 3),
+            Some(ScrollDelta::Pixels(-4.0, 0.0))
+        );
+        assert_eq!(wheel_scroll_delta(0, false, false, 3), None);
+        assert_eq!(wheel_scroll_delta(120, false, false, 0), None);
+    }
+
+    #[test]
+    fn wheel_precision_latch() {
+        let at = |millis| Timestamp::from_duration(Duration::from_millis(millis));
+
+        // A notched wheel scrolls in lines.
+        let latch = latch_wheel_precision(None, 120, false, at(1000));
+        assert!(!latch.precise);
+        let latch = latch_wheel_precision(Some(latch), -120, false, at(1010));
+        assert!(!latch.precise);
+
+        // Once a fraction of a notch comes in, whole notches stay precise while the wheel moves.
+        let latch = latch_wheel_precision(Some(latch), 30, false, at(1020));
+        assert!(latch.precise);
+        let latch = latch_wheel_precision(Some(latch), 120, false, at(1320));
+        assert!(latch.precise);
+        assert_eq!(
+            wheel_scroll_delta(120, latch.precise, false, 3),
+            Some(ScrollDelta::Pixels(0.0, 40.0))
+        );
+        // Resting ends the gesture.
+        let latch = latch_wheel_precision(Some(latch), 120, false, at(1621));
+        assert!(!latch.precise);
+
+        // Touchpads are always precise.
+        assert!(latch_wheel_precision(None, 120, true, at(2000)).precise);
+    }
+
+    #[test]
+    fn page_scrolling() {
+        assert_eq!(
+            wheel_scroll_delta(-240, false, false, WHEEL_PAGESCROLL),
+            Some(ScrollDelta::Pages(0.0, -2.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(120, false, true, WHEEL_PAGESCROLL),
+            Some(ScrollDelta::Pages(1.0, 0.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(30, true, false, WHEEL_PAGESCROLL),
+            Some(ScrollDelta::Pixels(0.0, 10.0))
+        );
+    }
+
+    #[test]
+    fn wheel_sign_convention() {
+        // Turning the wheel away from the user scrolls up, towards the start of the content.
+        assert_eq!(
+            wheel_scroll_delta(120, false, false, 3),
+            Some(ScrollDelta::Lines(0.0, 3.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(-120, false, false, 3),
+            Some(ScrollDelta::Lines(0.0, -3.0))
+        );
+        // Tilting the wheel right scrolls right.
+        assert_eq!(
+            wheel_scroll_delta(120, false, true, 2),
+            Some(ScrollDelta::Lines(2.0, 0.0))
+        );
+        assert_eq!(
+            wheel_scroll_delta(-120, false, true, 2),
+            Some(ScrollDelta::Lines(-2.0, 0.0))
+        );
+    }
+}
@@ -0,0 +1,1403 @@
+/*
+ * Copyright 2023 Jacob R. Green
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ * http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Recording of the events delivered to a `WindowController` and their replay, to reproduce
+//! problems reported by users.
+//!
+//! Recordings start with the magic bytes `WINREC` and a format version, followed by one record
+//! per event. A record holds the time since the recording started in microseconds, the raw
+//! window id, an event tag and the event fields, ending with the event's timestamp in
+//! microseconds. Integers are LEB128 encoded, floats are little endian, strings are length
+//! prefixed UTF-8, and keys are stored by their `VirtualKey` name so that recordings are
+//! portable between backends.
+
+use crate::*;
+
+#[cfg(target_os = "windows")]
+use std::cell::RefCell;
+#[cfg(target_os = "windows")]
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+#[cfg(target_os = "windows")]
+use std::thread;
+use std::time::Duration;
+#[cfg(target_os = "windows")]
+use std::time::Instant;
+
+const MAGIC: &[u8; 6] = b"WINREC";
+const VERSION: u8 = 1;
+
+/// An event delivered to a `WindowController`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordedEvent {
+    Init,
+    Close,
+    Closing,
+    Closed,
+    Moved(WindowMovedEvent),
+    Resized(WindowResizedEvent),
+    Key(KeyEvent),
+    Char(CharEvent),
+    TextInput(TextInputEvent),
+    Focus(FocusChangedEvent),
+    ModifiersChanged(ModifiersChangedEvent),
+    KeyboardLayoutChanged(KeyboardLayoutChangedEvent),
+    Ime(ImeEvent),
+    MouseMove(MouseMoveEvent),
+    MouseButton(MouseButtonEvent),
+    MouseWheel(MouseWheelEvent),
+    DragEnter(DragEvent),
+    DragOver(DragEvent),
+    DragLeave(DragLeaveEvent),
+    Drop(DragEvent),
+    Touch(TouchEvent),
+    Pointer(PointerEvent),
+    Gesture(GestureEvent),
+}
+
+impl RecordedEvent {
+    /// Delivers the event to the controller through the matching callback.
+    #[cfg(target_os = "windows")]
+    pub fn dispatch(&self, window: &Window, controller: &dyn WindowController) {
+        match self {
+            Self::Init => controller.on_init(window),
+            Self::Close => {
+                controller.on_close(window);
+            }
+            Self::Closing => controller.on_closing(window),
+            Self::Closed => controller.on_closed(window),
+            Self::Moved(event) => controller.on_moved(window, event),
+            Self::Resized(event) => controller.on_resized(window, event),
+            Self::Key(event) => controller.on_key(window, event),
+            Self::Char(event) => controller.on_char(window, event),
+            Self::TextInput(event) => controller.on_text_input(window, event),
+            Self::Focus(event) => controller.on_focus(window, event),
+            Self::ModifiersChanged(event) => controller.on_modifiers_changed(window, event),
+            Self::KeyboardLayoutChanged(event) => {
+                controller.on_keyboard_layout_changed(window, event)
+            }
+            Self::Ime(event) => controller.on_ime(window, event),
+            Self::MouseMove(event) => controller.on_mouse_move(window, event),
+            Self::MouseButton(event) => controller.on_mouse_button(window, event),
+            Self::MouseWheel(event) => controller.on_mouse_wheel(window, event),
+            Self::DragEnter(event) => {
+                controller.on_drag_enter(window, event);
+            }
+            Self::DragOver(event) => {
+                controller.on_drag_over(window, event);
+            }
+            Self::DragLeave(event) => controller.on_drag_leave(window, event),
+            Self::Drop(event) => {
+                controller.on_drop(window, event);
+            }
+            Self::Touch(event) => controller.on_touch(window, event),
+            Self::Pointer(event) => controller.on_pointer(window, event),
+            Self::Gesture(event) => controller.on_gesture(window, event),
+        }
+    }
+
+    fn encode(&self, e: &mut Encoder) {
+        match self {
+            Self::Init => e.u8(0),
+            Self::Close => e.u8(1),
+            Self::Closing => e.u8(2),
+            Self::Closed => e.u8(3),
+            Self::Moved(event) => {
+                e.u8(4);
+                e.pos(event.pos());
+                e.timestamp(event.timestamp());
+            }
+            Self::Resized(event) => {
+                e.u8(5);
+                e.pos(event.size());
+                e.timestamp(event.timestamp());
+            }
+            Self::Key(event) => {
+                e.u8(6);
+                e.key(event.key());
+                e.key_location(event.location());
+                e.uint(event.scan_code() as u64);
+                e.key_state(event.state());
+                e.uint(event.modifiers().bits() as u64);
+                e.opt_char(event.dead_key());
+                e.timestamp(event.timestamp());
+            }
+            Self::Char(event) => {
+                e.u8(7);
+                e.uint(event.char() as u64);
+                e.bool(event.repeat());
+                e.timestamp(event.timestamp());
+            }
+            Self::TextInput(event) => {
+                e.u8(8);
+                e.str(event.text());
+                e.timestamp(event.timestamp());
+            }
+            Self::Focus(event) => {
+                e.u8(9);
+                e.bool(event.focus());
+                e.timestamp(event.timestamp());
+            }
+            Self::ModifiersChanged(event) => {
+                e.u8(10);
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::KeyboardLayoutChanged(event) => {
+                e.u8(11);
+                e.str(event.layout().id());
+                e.str(event.layout().language());
+                e.uint(event.layout().native as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::Ime(event) => {
+                e.u8(12);
+                match event.ime() {
+                    Ime::Enabled => e.u8(0),
+                    Ime::Preedit { text, cursor_range } => {
+                        e.u8(1);
+                        e.str(text);
+                        e.bool(cursor_range.is_some());
+                        if let Some((start, end)) = cursor_range {
+                            e.uint(*start as u64);
+                            e.uint(*end as u64);
+                        }
+                    }
+                    Ime::Commit(text) => {
+                        e.u8(2);
+                        e.str(text);
+                    }
+                    Ime::Disabled => e.u8(3),
+                }
+                e.timestamp(event.timestamp());
+            }
+            Self::MouseMove(event) => {
+                e.u8(13);
+                e.pos(event.pos());
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::MouseButton(event) => {
+                e.u8(14);
+                e.mouse_button(event.button());
+                e.button_state(event.state());
+                e.pos(event.pos());
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::MouseWheel(event) => {
+                e.u8(15);
+                match event.delta() {
+                    ScrollDelta::Lines(x, y) => {
+                        e.u8(0);
+                        e.pos((x, y));
+                    }
+                    ScrollDelta::Pixels(x, y) => {
+                        e.u8(1);
+                        e.pos((x, y));
+                    }
+                    ScrollDelta::Pages(x, y) => {
+                        e.u8(2);
+                        e.pos((x, y));
+                    }
+                }
+                e.gesture_phase(event.phase());
+                e.pos(event.pos());
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::DragEnter(event) => {
+                e.u8(16);
+                e.drag(event);
+            }
+            Self::DragOver(event) => {
+                e.u8(17);
+                e.drag(event);
+            }
+            Self::DragLeave(event) => {
+                e.u8(18);
+                e.timestamp(event.timestamp());
+            }
+            Self::Drop(event) => {
+                e.u8(19);
+                e.drag(event);
+            }
+            Self::Touch(event) => {
+                e.u8(20);
+                e.uint(event.id());
+                e.touch_phase(event.phase());
+                e.pos(event.pos());
+                e.bool(event.force().is_some());
+                if let Some(force) = event.force() {
+                    e.f32(force);
+                }
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::Pointer(event) => {
+                e.u8(21);
+                e.uint(event.id());
+                e.pointer_type(event.pointer_type());
+                e.pointer_phase(event.phase());
+                e.pos(event.pos());
+                e.f32(event.pressure());
+                e.pos(event.tilt());
+                e.f32(event.twist());
+                e.uint(event.buttons().bits() as u64);
+                e.bool(event.is_primary());
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+            Self::Gesture(event) => {
+                e.u8(22);
+                match event.gesture() {
+                    Gesture::Pinch { scale_delta } => {
+                        e.u8(0);
+                        e.f32(scale_delta);
+                    }
+                    Gesture::Rotate { angle_delta } => {
+                        e.u8(1);
+                        e.f32(angle_delta);
+                    }
+                    Gesture::Swipe { dx, dy } => {
+                        e.u8(2);
+                        e.pos((dx, dy));
+                    }
+                }
+                e.gesture_phase(event.phase());
+                e.pos(event.pos());
+                e.uint(event.modifiers().bits() as u64);
+                e.timestamp(event.timestamp());
+            }
+        }
+    }
+
+    fn decode(d: &mut Decoder<impl Read>) -> io::Result<Self> {
+        let event = match d.u8()? {
+            0 => Self::Init,
+            1 => Self::Close,
+            2 => Self::Closing,
+            3 => Self::Closed,
+            4 => {
+                let (x, y) = d.pos()?;
+                Self::Moved(WindowMovedEvent::new(x, y, d.timestamp()?))
+            }
+            5 => {
+                let (width, height) = d.pos()?;
+                Self::Resized(WindowResizedEvent::new(width, height, d.timestamp()?))
+            }
+            6 => {
+                let key = d.key()?;
+                let location = match d.u8()? {
+                    0 => KeyLocation::Standard,
+                    1 => KeyLocation::Left,
+                    2 => KeyLocation::Right,
+                    3 => KeyLocation::Numpad,
+                    _ => return Err(invalid_data("invalid key location")),
+                };
+                let scan_code = d.uint()? as ScanCode;
+                let state = match d.u8()? {
+                    0 => KeyState::Press,
+                    1 => KeyState::Release,
+                    2 => KeyState::Repeat,
+                    _ => return Err(invalid_data("invalid key state")),
+                };
+                let modifiers = d.modifiers()?;
+                let dead_key = d.opt_char()?;
+                Self::Key(KeyEvent::new(
+                    key,
+                    location,
+                    scan_code,
+                    state,
+                    modifiers,
+                    dead_key,
+                    d.timestamp()?,
+                ))
+            }
+            7 => {
+                let c = d.char()?;
+                Self::Char(CharEvent::new(c, d.bool()?, d.timestamp()?))
+            }
+            8 => Self::TextInput(TextInputEvent::new(d.string()?, d.timestamp()?)),
+            9 => Self::Focus(FocusChangedEvent::new(d.bool()?, d.timestamp()?)),
+            10 => {
+                Self::ModifiersChanged(ModifiersChangedEvent::new(d.modifiers()?, d.timestamp()?))
+            }
+            11 => {
+                let id = d.string()?;
+                let language = d.string()?;
+                let native = d.uint()? as isize;
+                Self::KeyboardLayoutChanged(KeyboardLayoutChangedEvent::new(
+                    KeyboardLayout::new(id, language, native),
+                    d.timestamp()?,
+                ))
+            }
+            12 => {
+                let ime = match d.u8()? {
+                    0 => Ime::Enabled,
+                    1 => {
+                        let text = d.string()?;
+                        let cursor_range = match d.bool()? {
+                            true => Some((d.uint()? as usize, d.uint()? as usize)),
+                            false => None,
+                        };
+                        Ime::Preedit { text, cursor_range }
+                    }
+                    2 => Ime::Commit(d.string()?),
+                    3 => Ime::Disabled,
+                    _ => return Err(invalid_data("invalid ime event")),
+                };
+                Self::Ime(ImeEvent::new(ime, d.timestamp()?))
+            }
+            13 => {
+                let (x, y) = d.pos()?;
+                Self::MouseMove(MouseMoveEvent::new(x, y, d.modifiers()?, d.timestamp()?))
+            }
+            14 => {
+                let button = match d.u8()? {
+                    0 => MouseButton::Left,
+                    1 => MouseButton::Right,
+                    2 => MouseButton::Middle,
+                    3 => MouseButton::X1,
+                    4 => MouseButton::X2,
+                    _ => return Err(invalid_data("invalid mouse button")),
+                };
+                let state = match d.u8()? {
+                    0 => ButtonState::Release,
+                    1 => ButtonState::Press,
+                    _ => return Err(invalid_data("invalid button state")),
+                };
+                let (x, y) = d.pos()?;
+                Self::MouseButton(MouseButtonEvent::new(
+                    button,
+                    state,
+                    x,
+                    y,
+                    d.modifiers()?,
+                    d.timestamp()?,
+                ))
+            }
+            15 => {
+                let delta = match d.u8()? {
+                    0 => {
+                        let (x, y) = d.pos()?;
+                        ScrollDelta::Lines(x, y)
+                    }
+                    1 => {
+                        let (x, y) = d.pos()?;
+                        ScrollDelta::Pixels(x, y)
+                    }
+                    2 => {
+                        let (x, y) = d.pos()?;
+                        ScrollDelta::Pages(x, y)
+                    }
+                    _ => return Err(invalid_data("invalid scroll delta")),
+                };
+                let phase = d.gesture_phase()?;
+                let (x, y) = d.pos()?;
+                Self::MouseWheel(MouseWheelEvent::new(
+                    delta,
+                    phase,
+                    x,
+                    y,
+                    d.modifiers()?,
+                    d.timestamp()?,
+                ))
+            }
+            16 => Self::DragEnter(d.drag()?),
+            17 => Self::DragOver(d.drag()?),
+            18 => Self::DragLeave(DragLeaveEvent::new(d.timestamp()?)),
+            19 => Self::Drop(d.drag()?),
+            20 => {
+                let id = d.uint()?;
+                let phase = match d.u8()? {
+                    0 => TouchPhase::Started,
+                    1 => TouchPhase::Moved,
+                    2 => TouchPhase::Ended,
+                    3 => TouchPhase::Cancelled,
+                    _ => return Err(invalid_data("invalid touch phase")),
+                };
+                let (x, y) = d.pos()?;
+                let force = match d.bool()? {
+                    true => Some(d.f32()?),
+                    false => None,
+                };
+                Self::Touch(TouchEvent::new(
+                    id,
+                    phase,
+                    x,
+                    y,
+                    force,
+                    d.modifiers()?,
+                    d.timestamp()?,
+                ))
+            }
+            21 => {
+                let id = d.uint()?;
+                let pointer_type = match d.u8()? {
+                    0 => PointerType::Mouse,
+                    1 => PointerType::Pen,
+                    2 => PointerType::Touch,
+                    _ => return Err(invalid_data("invalid pointer type")),
+                };
+                let phase = match d.u8()? {
+                    0 => PointerPhase::Down,
+                    1 => PointerPhase::Move,
+                    2 => PointerPhase::Up,
+                    3 => PointerPhase::Cancel,
+                    _ => return Err(invalid_data("invalid pointer phase")),
+                };
+                let (x, y) = d.pos()?;
+                let pressure = d.f32()?;
+                let tilt = d.pos()?;
+                let twist = d.f32()?;
+                let buttons = PointerButtons::from_bits(d.uint()? as u32)
+                    .ok_or_else(|| invalid_data("invalid pointer buttons"))?;
+                Self::Pointer(PointerEvent::new(
+                    id,
+                    pointer_type,
+                    phase,
+                    x,
+                    y,
+                    pressure,
+                    tilt,
+                    twist,
+                    buttons,
+                    d.bool()?,
+                    d.modifiers()?,
+                    d.timestamp()?,
+                ))
+            }
+            22 => {
+                let gesture = match d.u8()? {
+                    0 => Gesture::Pinch {
+                        scale_delta: d.f32()?,
+                    },
+                    1 => Gesture::Rotate {
+                        angle_delta: d.f32()?,
+                    },
+                    2 => {
+                        let (dx, dy) = d.pos()?;
+                        Gesture::Swipe { dx, dy }
+                    }
+                    _ => return Err(invalid_data("invalid gesture")),
+                };
+                let phase = d.gesture_phase()?;
+                let (x, y) = d.pos()?;
+                Self::Gesture(GestureEvent::new(
+                    gesture,
+                    phase,
+                    x,
+                    y,
+                    d.modifiers()?,
+                    d.timestamp()?,
+                ))
+            }
+            _ => return Err(invalid_data("unknown event")),
+        };
+        Ok(event)
+    }
+}
+
+/// An event along with when and where it was delivered.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Record {
+    time: Duration,
+    window: WindowId,
+    event: RecordedEvent,
+}
+
+impl Record {
+    pub const fn new(time: Duration, window: WindowId, event: RecordedEvent) -> Self {
+        Self {
+            time,
+            window,
+            event,
+        }
+    }
+
+    /// Returns the time since the recording started.
+    pub const fn time(&self) -> Duration {
+        self.time
+    }
+
+    pub const fn window(&self) -> WindowId {
+        self.window
+    }
+
+    pub const fn event(&self) -> &RecordedEvent {
+        &self.event
+    }
+}
+
+/// Writes a recording that a `Replayer` can read, one record at a time.
+pub struct RecordWriter<W: Write> {
+    output: W,
+}
+
+impl<W: Write> RecordWriter<W> {
+    /// Writes the recording header.
+    pub fn new(mut output: W) -> io::Result<Self> {
+        output.write_all(MAGIC)?;
+        output.write_all(&[VERSION])?;
+        output.flush()?;
+
+        Ok(Self { output })
+    }
+
+    /// Writes and flushes a record.
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        let mut e = Encoder(Vec::new());
+        e.uint(record.time.as_micros() as u64);
+        e.uint(record.window.to_raw());
+        record.event.encode(&mut e);
+
+        self.output.write_all(&e.0)?;
+        self.output.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+/// A `WindowController` that records every event before passing it on to the controller it
+/// wraps. Records are flushed as they are written, so a recording is complete up to the last
+/// event even if the application crashes. An I/O error stops the recording.
+#[cfg(target_os = "windows")]
+pub struct Recorder<C: WindowController, W: Write> {
+    controller: C,
+    output: RefCell<Option<RecordWriter<W>>>,
+    start: Instant,
+}
+
+#[cfg(target_os = "windows")]
+impl<C: WindowController, W: Write> Recorder<C, W> {
+    pub fn new(controller: C, output: W) -> io::Result<Self> {
+        Ok(Self {
+            controller,
+            output: RefCell::new(Some(RecordWriter::new(output)?)),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+
+    /// Returns whether events are still being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.output.borrow().is_some()
+    }
+
+    fn record(&self, window: &Window, event: RecordedEvent) {
+        let mut output = self.output.borrow_mut();
+        let Some(writer) = output.as_mut() else {
+            return;
+        };
+
+        let record = Record {
+            time: self.start.elapsed(),
+            window: window.id(),
+            event,
+        };
+        if writer.write(&record).is_err() {
+            *output = None;
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl<C: WindowController, W: Write> WindowController for Recorder<C, W> {
+    fn get_config(&self) -> WindowConfig {
+        self.controller.get_config()
+    }
+
+    fn on_init(&self, window: &Window) {
+        self.record(window, RecordedEvent::Init);
+        self.controller.on_init(window)
+    }
+
+    fn on_close(&self, window: &Window) -> bool {
+        self.record(window, RecordedEvent::Close);
+        self.controller.on_close(window)
+    }
+
+    fn on_closing(&self, window: &Window) {
+        self.record(window, RecordedEvent::Closing);
+        self.controller.on_closing(window)
+    }
+
+    fn on_closed(&self, window: &Window) {
+        self.record(window, RecordedEvent::Closed);
+        self.controller.on_closed(window)
+    }
+
+    fn on_moved(&self, window: &Window, event: &WindowMovedEvent) {
+        self.record(window, RecordedEvent::Moved(*event));
+        self.controller.on_moved(window, event)
+    }
+
+    fn on_resized(&self, window: &Window, event: &WindowResizedEvent) {
+        self.record(window, RecordedEvent::Resized(*event));
+        self.controller.on_resized(window, event)
+    }
+
+    fn on_key(&self, window: &Window, event: &KeyEvent) {
+        self.record(window, RecordedEvent::Key(*event));
+        self.controller.on_key(window, event)
+    }
+
+    fn on_char(&self, window: &Window, event: &CharEvent) {
+        self.record(window, RecordedEvent::Char(*event));
+        self.controller.on_char(window, event)
+    }
+
+    fn on_text_input(&self, window: &Window, event: &TextInputEvent) {
+        self.record(window, RecordedEvent::TextInput(event.clone()));
+        self.controller.on_text_input(window, event)
+    }
+
+    fn on_focus(&self, window: &Window, event: &FocusChangedEvent) {
+        self.record(window, RecordedEvent::Focus(*event));
+        self.controller.on_focus(window, event)
+    }
+
+    fn on_modifiers_changed(&self, window: &Window, event: &ModifiersChangedEvent) {
+        self.record(window, RecordedEvent::ModifiersChanged(*event));
+        self.controller.on_modifiers_changed(window, event)
+    }
+
+    fn on_keyboard_layout_changed(&self, window: &Window, event: &KeyboardLayoutChangedEvent) {
+        self.record(window, RecordedEvent::KeyboardLayoutChanged(event.clone()));
+        self.controller.on_keyboard_layout_changed(window, event)
+    }
+
+    fn on_ime(&self, window: &Window, event: &ImeEvent) {
+        self.record(window, RecordedEvent::Ime(event.clone()));
+        self.controller.on_ime(window, event)
+    }
+
+    fn on_mouse_move(&self, window: &Window, event: &MouseMoveEvent) {
+        self.record(window, RecordedEvent::MouseMove(*event));
+        self.controller.on_mouse_move(window, event)
+    }
+
+    fn on_mouse_button(&self, window: &Window, event: &MouseButtonEvent) {
+        self.record(window, RecordedEvent::MouseButton(*event));
+        self.controller.on_mouse_button(window, event)
+    }
+
+    fn on_mouse_wheel(&self, window: &Window, event: &MouseWheelEvent) {
+        self.record(window, RecordedEvent::MouseWheel(*event));
+        self.controller.on_mouse_wheel(window, event)
+    }
+
+    fn on_drag_enter(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        self.record(window, RecordedEvent::DragEnter(event.clone()));
+        self.controller.on_drag_enter(window, event)
+    }
+
+    fn on_drag_over(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        self.record(window, RecordedEvent::DragOver(event.clone()));
+        self.controller.on_drag_over(window, event)
+    }
+
+    fn on_drag_leave(&self, window: &Window, event: &DragLeaveEvent) {
+        self.record(window, RecordedEvent::DragLeave(*event));
+        self.controller.on_drag_leave(window, event)
+    }
+
+    fn on_drop(&self, window: &Window, event: &DragEvent) -> DropEffect {
+        self.record(window, RecordedEvent::Drop(event.clone()));
+        self.controller.on_drop(window, event)
+    }
+
+    fn on_touch(&self, window: &Window, event: &TouchEvent) {
+        self.record(window, RecordedEvent::Touch(*event));
+        self.controller.on_touch(window, event)
+    }
+
+    fn on_pointer(&self, window: &Window, event: &PointerEvent) {
+        self.record(window, RecordedEvent::Pointer(*event));
+        self.controller.on_pointer(window, event)
+    }
+
+    fn on_gesture(&self, window: &Window, event: &GestureEvent) {
+        self.record(window, RecordedEvent::Gesture(*event));
+        self.controller.on_gesture(window, event)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReplaySpeed {
+    /// Waits between events as long as the user did.
+    RealTime,
+    /// Delivers the events without waiting.
+    AsFastAsPossible,
+}
+
+/// Reads a recording made by a `Recorder`.
+pub struct Replayer<R: Read> {
+    input: Decoder<R>,
+}
+
+impl<R: Read> Replayer<R> {
+    pub fn new(mut input: R) -> io::Result<Self> {
+        let mut header = [0u8; 7];
+        input.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(invalid_data("not a winr recording"));
+        }
+        if header[6] != VERSION {
+            return Err(invalid_data("unsupported recording version"));
+        }
+
+        Ok(Self {
+            input: Decoder(input),
+        })
+    }
+
+    /// Reads the next record, or returns `None` at the end of the recording.
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        let time = match self.input.uint() {
+            Ok(time) => Duration::from_micros(time),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let window = WindowId::from_raw(self.input.uint()?);
+        let event = RecordedEvent::decode(&mut self.input)?;
+
+        Ok(Some(Record {
+            time,
+            window,
+            event,
+        }))
+    }
+
+    /// Delivers the remaining events to a controller, through the windows that stand in for the
+    /// windows they were recorded for. Fails with `NotFound` on the first event for a window
+    /// that `windows` has no entry for, after delivering the events before it.
+    #[cfg(target_os = "windows")]
+    pub fn replay(
+        &mut self,
+        windows: &HashMap<WindowId, &Window>,
+        controller: &dyn WindowController,
+        speed: ReplaySpeed,
+    ) -> io::Result<()> {
+        let start = Instant::now();
+        while let Some(record) = self.next_record()? {
+            let Some(window) = windows.get(&record.window) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "recording refers to a window without a replacement",
+                ));
+            };
+            if speed == ReplaySpeed::RealTime {
+                if let Some(wait) = record.time.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+            record.event.dispatch(window, controller);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for Replayer<R> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn uint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn pos(&mut self, (x, y): (f32, f32)) {
+        self.f32(x);
+        self.f32(y);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.uint(value.len() as u64);
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn opt_char(&mut self, value: Option<char>) {
+        self.bool(value.is_some());
+        if let Some(c) = value {
+            self.uint(c as u64);
+        }
+    }
+
+    fn key(&mut self, key: VirtualKey) {
+        self.str(&key.to_string());
+    }
+
+    fn key_location(&mut self, location: KeyLocation) {
+        self.u8(match location {
+            KeyLocation::Standard => 0,
+            KeyLocation::Left => 1,
+            KeyLocation::Right => 2,
+            KeyLocation::Numpad => 3,
+        });
+    }
+
+    fn key_state(&mut self, state: KeyState) {
+        self.u8(match state {
+            KeyState::Press => 0,
+            KeyState::Release => 1,
+            KeyState::Repeat => 2,
+        });
+    }
+
+    fn mouse_button(&mut self, button: MouseButton) {
+        self.u8(match button {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+            MouseButton::X1 => 3,
+            MouseButton::X2 => 4,
+        });
+    }
+
+    fn button_state(&mut self, state: ButtonState) {
+        self.u8(match state {
+            ButtonState::Release => 0,
+            ButtonState::Press => 1,
+        });
+    }
+
+    fn touch_phase(&mut self, phase: TouchPhase) {
+        self.u8(match phase {
+            TouchPhase::Started => 0,
+            TouchPhase::Moved => 1,
+            TouchPhase::Ended => 2,
+            TouchPhase::Cancelled => 3,
+        });
+    }
+
+    fn pointer_type(&mut self, pointer_type: PointerType) {
+        self.u8(match pointer_type {
+            PointerType::Mouse => 0,
+            PointerType::Pen => 1,
+            PointerType::Touch => 2,
+        });
+    }
+
+    fn pointer_phase(&mut self, phase: PointerPhase) {
+        self.u8(match phase {
+            PointerPhase::Down => 0,
+            PointerPhase::Move => 1,
+            PointerPhase::Up => 2,
+            PointerPhase::Cancel => 3,
+        });
+    }
+
+    fn gesture_phase(&mut self, phase: GesturePhase) {
+        self.u8(match phase {
+            GesturePhase::Began => 0,
+            GesturePhase::Changed => 1,
+            GesturePhase::Ended => 2,
+            GesturePhase::Momentum => 3,
+        });
+    }
+
+    fn timestamp(&mut self, timestamp: Timestamp) {
+        self.uint(timestamp.as_duration().as_micros() as u64);
+    }
+
+    fn drag(&mut self, event: &DragEvent) {
+        let data = event.data();
+        self.uint(data.files.len() as u64);
+        for file in &data.files {
+            self.str(&file.to_string_lossy());
+        }
+        self.bool(data.text.is_some());
+        if let Some(text) = &data.text {
+            self.str(text);
+        }
+        self.uint(data.uris.len() as u64);
+        for uri in &data.uris {
+            self.str(uri);
+        }
+
+        self.pos(event.pos());
+        self.uint(event.modifiers().bits() as u64);
+        self.uint(event.allowed().bits() as u64);
+        self.uint(event.effect().bits() as u64);
+        self.timestamp(event.timestamp());
+    }
+}
+
+struct Decoder<R: Read>(R);
+
+impl<R: Read> Decoder<R> {
+    fn u8(&mut self) -> io::Result<u8> {
+        let mut byte = [0u8];
+        self.0.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn uint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_data("integer too long"))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        let mut bytes = [0u8; 4];
+        self.0.read_exact(&mut bytes)?;
+        Ok(f32::from_le_bytes(bytes))
+    }
+
+    fn pos(&mut self) -> io::Result<(f32, f32)> {
+        Ok((self.f32()?, self.f32()?))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        // The length isn't trusted for the allocation, so a corrupt length fails at the end of
+        // the input instead of allocating the claimed size up front.
+        let len = self.uint()?;
+        let mut bytes = Vec::new();
+        (&mut self.0).take(len).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        String::from_utf8(bytes).map_err(|_| invalid_data("invalid string"))
+    }
+
+    fn char(&mut self) -> io::Result<char> {
+        char::from_u32(self.uint()? as u32).ok_or_else(|| invalid_data("invalid character"))
+    }
+
+    fn opt_char(&mut self) -> io::Result<Option<char>> {
+        match self.bool()? {
+            true => self.char().map(Some),
+            false => Ok(None),
+        }
+    }
+
+    fn timestamp(&mut self) -> io::Result<Timestamp> {
+        Ok(Timestamp::from_duration(Duration::from_micros(
+            self.uint()?,
+        )))
+    }
+
+    fn drag(&mut self) -> io::Result<DragEvent> {
+        let mut data = DragData::default();
+        for _ in 0..self.uint()? {
+            data.files.push(self.string()?.into());
+        }
+        if self.bool()? {
+            data.text = Some(self.string()?);
+        }
+        for _ in 0..self.uint()? {
+            data.uris.push(self.string()?);
+        }
+
+        let (x, y) = self.pos()?;
+        let modifiers = self.modifiers()?;
+        let allowed = self.drop_effect()?;
+        let effect = self.drop_effect()?;
+        let timestamp = self.timestamp()?;
+        Ok(DragEvent::new(
+            data, x, y, modifiers, allowed, effect, timestamp,
+        ))
+    }
+
+    fn modifiers(&mut self) -> io::Result<Modifiers> {
+        Modifiers::from_bits(self.uint()? as u32).ok_or_else(|| invalid_data("invalid modifiers"))
+    }
+
+    fn gesture_phase(&mut self) -> io::Result<GesturePhase> {
+        let phase = match self.u8()? {
+            0 => GesturePhase::Began,
+            1 => GesturePhase::Changed,
+            2 => GesturePhase::Ended,
+            3 => GesturePhase::Momentum,
+            _ => return Err(invalid_data("invalid gesture phase")),
+        };
+        Ok(phase)
+    }
+
+    fn drop_effect(&mut self) -> io::Result<DropEffect> {
+        DropEffect::from_bits(self.uint()? as u32)
+            .ok_or_else(|| invalid_data("invalid drop effect"))
+    }
+
+    fn key(&mut self) -> io::Result<VirtualKey> {
+        VirtualKey::from_str(&self.string()?).map_err(|_| invalid_data("unknown key"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeSet;
+
+    fn timestamp(micros: u64) -> Timestamp {
+        Timestamp::from_duration(Duration::from_micros(micros))
+    }
+
+    fn drag_event(data: DragData) -> DragEvent {
+        DragEvent::new(
+            data,
+            12.5,
+            -3.0,
+            Modifiers::LEFT_SHIFT,
+            DropEffect::COPY | DropEffect::MOVE,
+            DropEffect::MOVE,
+            timestamp(90),
+        )
+    }
+
+    /// One event of every kind, with the variants of their enums spread between them.
+    fn events() -> Vec<RecordedEvent> {
+        let modifiers = Modifiers::LEFT_CONTROL | Modifiers::CAPS_LOCK;
+        let data = DragData {
+            files: vec!["C:\\Users\\me\\file.txt".into(), "notes.md".into()],
+            text: Some("dragged text".to_string()),
+            uris: vec!["https://example.com/".to_string()],
+        };
+
+        vec![
+            RecordedEvent::Init,
+            RecordedEvent::Close,
+            RecordedEvent::Closing,
+            RecordedEvent::Closed,
+            RecordedEvent::Moved(WindowMovedEvent::new(-20.0, 40.0, timestamp(1))),
+            RecordedEvent::Resized(WindowResizedEvent::new(800.0, 600.0, timestamp(2))),
+            RecordedEvent::Key(KeyEvent::new(
+                VirtualKey::NumpadEnter,
+                KeyLocation::Numpad,
+                0xE01C,
+                KeyState::Repeat,
+                modifiers,
+                None,
+                timestamp(3),
+            )),
+            RecordedEvent::Key(KeyEvent::new(
+                VirtualKey::Unknown(0xFFFF),
+                KeyLocation::Right,
+                0x0036,
+                KeyState::Release,
+                Modifiers::empty(),
+                Some('´'),
+                timestamp(4),
+            )),
+            RecordedEvent::Char(CharEvent::new('é', true, timestamp(5))),
+            RecordedEvent::TextInput(TextInputEvent::new("日本語".to_string(), timestamp(6))),
+            RecordedEvent::Focus(FocusChangedEvent::new(true, timestamp(7))),
+            RecordedEvent::ModifiersChanged(ModifiersChangedEvent::new(modifiers, timestamp(8))),
+            RecordedEvent::KeyboardLayoutChanged(KeyboardLayoutChangedEvent::new(
+                KeyboardLayout::new("00000407".to_string(), "de-DE".to_string(), 0x0407_0407),
+                timestamp(9),
+            )),
+            RecordedEvent::Ime(ImeEvent::new(Ime::Enabled, timestamp(22))),
+            RecordedEvent::Ime(ImeEvent::new(
+                Ime::Preedit {
+                    text: "にほん".to_string(),
+                    cursor_range: Some((3, 9)),
+                },
+                timestamp(23),
+            )),
+            RecordedEvent::Ime(ImeEvent::new(
+                Ime::Preedit {
+                    text: String::new(),
+                    cursor_range: None,
+                },
+                timestamp(24),
+            )),
+            RecordedEvent::Ime(ImeEvent::new(
+                Ime::Commit("日本".to_string()),
+                timestamp(25),
+            )),
+            RecordedEvent::Ime(ImeEvent::new(Ime::Disabled, timestamp(26))),
+            RecordedEvent::MouseMove(MouseMoveEvent::new(1.5, 2.5, modifiers, timestamp(10))),
+            RecordedEvent::MouseButton(MouseButtonEvent::new(
+                MouseButton::X2,
+                ButtonState::Press,
+                3.0,
+                4.0,
+                modifiers,
+                timestamp(11),
+            )),
+            RecordedEvent::MouseButton(MouseButtonEvent::new(
+                MouseButton::Middle,
+                ButtonState::Release,
+                3.0,
+                4.0,
+                Modifiers::empty(),
+                timestamp(12),
+            )),
+            RecordedEvent::MouseWheel(MouseWheelEvent::new(
+                ScrollDelta::Lines(0.0, -3.0),
+                GesturePhase::Changed,
+                5.0,
+                6.0,
+                Modifiers::empty(),
+                timestamp(13),
+            )),
+            RecordedEvent::MouseWheel(MouseWheelEvent::new(
+                ScrollDelta::Pixels(-1.25, 7.5),
+                GesturePhase::Momentum,
+                5.0,
+                6.0,
+                modifiers,
+                timestamp(14),
+            )),
+            RecordedEvent::MouseWheel(MouseWheelEvent::new(
+                ScrollDelta::Pages(0.0, -1.0),
+                GesturePhase::Changed,
+                5.0,
+                6.0,
+                Modifiers::empty(),
+                timestamp(28),
+            )),
+            RecordedEvent::DragEnter(drag_event(data.clone())),
+            RecordedEvent::DragOver(drag_event(DragData::default())),
+            RecordedEvent::DragLeave(DragLeaveEvent::new(timestamp(27))),
+            RecordedEvent::Drop(drag_event(data)),
+            RecordedEvent::Touch(TouchEvent::new(
+                7,
+                TouchPhase::Cancelled,
+                8.0,
+                9.0,
+                Some(0.5),
+                modifiers,
+                timestamp(15),
+            )),
+            RecordedEvent::Touch(TouchEvent::new(
+                u64::MAX,
+                TouchPhase::Started,
+                8.0,
+                9.0,
+                None,
+                Modifiers::empty(),
+                timestamp(16),
+            )),
+            RecordedEvent::Pointer(PointerEvent::new(
+                3,
+                PointerType::Pen,
+                PointerPhase::Move,
+                10.0,
+                11.0,
+                0.75,
+                (-30.0, 15.0),
+                90.0,
+                PointerButtons::PRIMARY | PointerButtons::ERASER,
+                true,
+                modifiers,
+                timestamp(17),
+            )),
+            RecordedEvent::Pointer(PointerEvent::new(
+                4,
+                PointerType::Touch,
+                PointerPhase::Cancel,
+                10.0,
+                11.0,
+                0.0,
+                (0.0, 0.0),
+                0.0,
+                PointerButtons::empty(),
+                false,
+                Modifiers::empty(),
+                timestamp(18),
+            )),
+            RecordedEvent::Gesture(GestureEvent::new(
+                Gesture::Pinch { scale_delta: 1.1 },
+                GesturePhase::Began,
+                12.0,
+                13.0,
+                modifiers,
+                timestamp(19),
+            )),
+            RecordedEvent::Gesture(GestureEvent::new(
+                Gesture::Rotate { angle_delta: -0.2 },
+                GesturePhase::Changed,
+                12.0,
+                13.0,
+                Modifiers::empty(),
+                timestamp(20),
+            )),
+            RecordedEvent::Gesture(GestureEvent::new(
+                Gesture::Swipe { dx: 4.0, dy: -8.0 },
+                GesturePhase::Ended,
+                12.0,
+                13.0,
+                Modifiers::empty(),
+                timestamp(21),
+            )),
+        ]
+    }
+
+    fn encode(event: &RecordedEvent) -> Vec<u8> {
+        let mut e = Encoder(Vec::new());
+        event.encode(&mut e);
+        e.0
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<RecordedEvent> {
+        RecordedEvent::decode(&mut Decoder(bytes))
+    }
+
+    #[test]
+    fn event_round_trip() {
+        for event in events() {
+            let bytes = encode(&event);
+            let mut d = Decoder(bytes.as_slice());
+            assert_eq!(RecordedEvent::decode(&mut d).unwrap(), event);
+            assert!(d.0.is_empty(), "{:?} left {} bytes", event, d.0.len());
+        }
+    }
+
+    #[test]
+    fn every_event_kind() {
+        let tags = events()
+            .iter()
+            .map(|event| encode(event)[0])
+            .collect::<BTreeSet<_>>();
+        assert_eq!(tags, (0..=22).collect());
+    }
+
+    #[test]
+    fn truncated_events() {
+        for event in events() {
+            let bytes = encode(&event);
+            for len in 0..bytes.len() {
+                let error = decode(&bytes[..len]).unwrap_err();
+                assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "{:?}", event);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_events() {
+        assert_eq!(
+            decode(&[23]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        // A key location past the last one.
+        let mut bytes = encode(&events()[6]);
+        let location = 2 + "NumpadEnter".len();
+        bytes[location] = 4;
+        assert_eq!(
+            decode(&bytes).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut e = Encoder(vec![8]);
+        e.uint(2);
+        e.0.extend_from_slice(&[0xC3, 0x28]);
+        e.timestamp(timestamp(0));
+        assert_eq!(decode(&e.0).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn oversized_string_length() {
+        // The claimed length is far more than could be allocated, but only three bytes follow.
+        let mut e = Encoder(vec![8]);
+        e.uint(1 << 62);
+        e.0.extend_from_slice(b"abc");
+        assert_eq!(
+            decode(&e.0).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn replay_records() {
+        let events = events();
+        let mut writer = RecordWriter::new(Vec::new()).unwrap();
+        for (i, event) in events.iter().enumerate() {
+            let time = Duration::from_millis(i as u64);
+            let window = WindowId::from_raw(i as u64 % 2 + 1);
+            writer
+                .write(&Record::new(time, window, event.clone()))
+                .unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let records = Replayer::new(bytes.as_slice())
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), events.len());
+        for (i, (record, event)) in records.iter().zip(&events).enumerate() {
+            assert_eq!(record.time(), Duration::from_millis(i as u64));
+            assert_eq!(record.window(), WindowId::from_raw(i as u64 % 2 + 1));
+            assert_eq!(record.event(), event);
+        }
+    }
+
+    #[test]
+    fn invalid_header() {
+        let error = |bytes: &[u8]| Replayer::new(bytes).err().unwrap().kind();
+        assert_eq!(error(b"WINREC"), io::ErrorKind::UnexpectedEof);
+        assert_eq!(error(b"WINRED\x04"), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error(&[b'W', b'I', b'N', b'R', b'E', b'C', VERSION + 1]),
+            io::ErrorKind::InvalidData
+        );
+    }
+}